@@ -0,0 +1,5 @@
+fn main() {
+    // the native Node addon needs platform-specific linker arguments
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
@@ -0,0 +1,84 @@
+#ifndef CONTENT_TAG_H
+#define CONTENT_TAG_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum ContentTagStatus {
+  CONTENT_TAG_STATUS_OK = 0,
+  // The source didn't parse, `error` describes where.
+  CONTENT_TAG_STATUS_PARSE_ERROR = 1,
+  // A null pointer with a non-zero length, input that isn't UTF-8, or
+  // options that aren't a JSON object or set an option to a value it doesn't
+  // accept.
+  CONTENT_TAG_STATUS_INVALID_ARGUMENT = 2,
+  // A bug in the preprocessor, `error` holds the panic message if any.
+  CONTENT_TAG_STATUS_INTERNAL_ERROR = 3,
+} ContentTagStatus;
+
+// An owned byte buffer. `data` is null when `len` is 0.
+typedef struct ContentTagBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} ContentTagBuffer;
+
+typedef struct ContentTagResult {
+  enum ContentTagStatus status;
+  // The generated code for `content_tag_process`, or the JSON array of
+  // occurrences for `content_tag_parse`.
+  struct ContentTagBuffer output;
+  // The JSON source map, for `content_tag_process`.
+  struct ContentTagBuffer map;
+  // When `status` isn't `Ok`, a JSON object with a `message`, and for parse
+  // errors the rendered diagnostic as `detail` and, when the error points
+  // into the source, its byte offsets as `startByte` and `endByte`.
+  struct ContentTagBuffer error;
+} ContentTagResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Lowers the `<template>` tags in `src`. The result's `output` is the code and
+// `map` is the source map.
+//
+// # Safety
+//
+// `src` and `options_json` must each be null or point to at least the given
+// number of readable bytes. The result must be freed with
+// `content_tag_result_free`.
+struct ContentTagResult *content_tag_process(const uint8_t *src,
+                                             uintptr_t src_len,
+                                             const uint8_t *options_json,
+                                             uintptr_t options_len);
+
+// Locates the `<template>` tags in `src`. The result's `output` is a JSON
+// array with the same shape as the JS `parse` result.
+//
+// # Safety
+//
+// `src` and `options_json` must each be null or point to at least the given
+// number of readable bytes. The result must be freed with
+// `content_tag_result_free`.
+struct ContentTagResult *content_tag_parse(const uint8_t *src,
+                                           uintptr_t src_len,
+                                           const uint8_t *options_json,
+                                           uintptr_t options_len);
+
+// Frees a result and all of its buffers.
+//
+// # Safety
+//
+// `result` must be null or a pointer returned by `content_tag_process` or
+// `content_tag_parse` that hasn't been freed yet.
+void content_tag_result_free(struct ContentTagResult *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif /* CONTENT_TAG_H */
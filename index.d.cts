@@ -12,6 +12,17 @@ interface Range {
   endUtf16Codepoint: number;
 }
 
+interface IndentStrip {
+  leadingLines: number;
+  trailingLines: number;
+  indent: number;
+}
+
+interface LineOffset {
+  originalLine: number;
+  originalColumn: number;
+}
+
 interface Parsed {
   type: "expression" | "class-member";
   tagName: "template";
@@ -20,6 +31,159 @@ interface Parsed {
   contentRange: Range;
   startRange: Range;
   endRange: Range;
+  rawContents: string;
+  stripIndent: IndentStrip;
+  lineOffsets: LineOffset[];
+}
+
+interface LintBlock {
+  kind: "script" | "template";
+  /**
+   * For the script block, the source with each `<template>` replaced by a JS
+   * placeholder of the same length. For a template block, its contents.
+   */
+  text: string;
+  /** Like `0.js`, `0.ts` or `1.hbs`. */
+  filename: string;
+  /** Where the block starts in the source, 1-based. */
+  line: number;
+  /** Where the block starts in the source, 1-based, in UTF-16 code units. */
+  column: number;
+  /** Where the block starts in the source, in UTF-16 code units. */
+  offset: number;
+}
+
+/**
+ * An ESLint `LintMessage`. Positions and fix ranges are translated, any other
+ * property is passed through.
+ */
+interface LintMessage {
+  line: number;
+  column: number;
+  endLine?: number;
+  endColumn?: number;
+  fix?: { range: [number, number]; text: string };
+  suggestions?: { fix: { range: [number, number]; text: string } }[];
+  [key: string]: unknown;
+}
+
+interface EstreeLocation {
+  /** 1-based */
+  line: number;
+  /** 0-based, in UTF-16 code units */
+  column: number;
+}
+
+interface EstreeNode {
+  type: string;
+  /** Start and end offsets in UTF-16 code units. */
+  range: [number, number];
+  loc: { start: EstreeLocation; end: EstreeLocation };
+  [key: string]: unknown;
+}
+
+/**
+ * A `<template>` tag, in expression position or in a class body. `range` and
+ * `loc` cover the whole tag.
+ */
+interface GlimmerTemplate extends EstreeNode {
+  type: "GlimmerTemplate";
+  contents: string;
+}
+
+interface EstreeProgram extends EstreeNode {
+  type: "Program";
+  sourceType: "module";
+  body: EstreeNode[];
+  /** `Line` and `Block` comments, with `value` like espree. */
+  comments: EstreeNode[];
+  /** espree style tokens, plus a `GlimmerTemplate` token for each tag. */
+  tokens: EstreeNode[];
+}
+
+interface ImportUsage {
+  /** Imports that neither JS nor any template uses. */
+  unusedImports: {
+    /** The local name, like `Bar` in `import { Foo as Bar } from "foo"`. */
+    local: string;
+    source: string;
+    /** The local name in the import. */
+    range: Range;
+  }[];
+  /** Names that templates use but nothing in scope binds, in file order. */
+  unresolvedReferences: {
+    name: string;
+    kind: "component" | "modifier" | "path";
+    range: Range;
+  }[];
+}
+
+interface CrossReferences {
+  /** Every name that a template refers to, in file order. */
+  references: {
+    name: string;
+    kind: "component" | "modifier" | "path";
+    /** The name in the template. */
+    range: Range;
+    /** The name in the JS declaration it resolves to, if anything binds it. */
+    definition: Range | null;
+  }[];
+  /** The JS bindings that templates refer to, in the order they're declared. */
+  bindings: {
+    name: string;
+    definition: Range;
+    templateReferences: Range[];
+  }[];
+}
+
+interface SemanticTokens {
+  /**
+   * The legend: `keyword`, `variable`, `property`, `parameter`, `function`,
+   * `class`, `tag`, `string`, `number`, `regexp` and `comment`.
+   */
+  tokenTypes: string[];
+  /**
+   * Five numbers per token, like LSP's `SemanticTokens.data`: the line and
+   * UTF-16 start character relative to the previous token, the length, the
+   * index into `tokenTypes`, and modifiers, which are always 0.
+   */
+  data: number[];
+}
+
+interface TemplateLintConfig {
+  /**
+   * The built-in rules are `no-bare-strings`, `no-unused-block-params` and
+   * `require-button-type`. Rules that aren't listed are off, except the
+   * recommended `no-unused-block-params` and `require-button-type`, which run
+   * as errors.
+   */
+  rules?: Record<string, "off" | "warn" | "error">;
+}
+
+interface TemplateDiagnostic {
+  rule: string;
+  severity: "warn" | "error";
+  message: string;
+  /** Where the problem is in the whole file. */
+  range: Range;
+}
+
+interface TemplateFormatOptions {
+  /** Default is `80` */
+  printWidth?: number;
+  /** Spaces per level, unless the file indents with tabs. Default is `2` */
+  indentWidth?: number;
+  /**
+   * `"auto"` puts each attribute on its own line when a tag doesn't fit,
+   * `"one-per-line"` does whenever a tag has more than one, and `"never"`
+   * keeps tags on one line. Default is `"auto"`
+   */
+  attributeWrapping?: "auto" | "one-per-line" | "never";
+  /**
+   * How void elements like `<input>` are closed: `"always"` as `<input />`,
+   * `"never"` as `<input>`, or `"preserve"`. Default is `"preserve"`
+   */
+  selfClosing?: "preserve" | "always" | "never";
 }
 
 interface PreprocessorOptions {
@@ -27,6 +191,153 @@ interface PreprocessorOptions {
   inline_source_map?: boolean;
 
   filename?: string;
+
+  /**
+   * Which template compiler API the output targets. Default is `"template"`.
+   *
+   * `"precompile-template"` emits `precompileTemplate` from
+   * `@ember/template-compilation` and `setComponentTemplate` from
+   * `@ember/component`, for Ember versions that predate `template()`.
+   * Templates outside of classes are set on `templateOnly()` from
+   * `@ember/component/template-only`.
+   */
+  flavor?: "template" | "precompile-template";
+
+  /**
+   * Default is `true`. When `false`, class-member templates are lowered
+   * without `static {}` blocks: class declarations are followed by a
+   * `template(..., { component: ClassName })` statement and class expressions
+   * are wrapped in the `template()` call.
+   */
+  static_blocks?: boolean;
+
+  /**
+   * How whitespace in template contents is normalized. Default is `"dedent"`.
+   *
+   * - `"off"` passes contents through untouched
+   * - `"trim-only"` removes blank leading and trailing lines
+   * - `"dedent"` also removes common indentation, unless it mixes tabs and
+   *   spaces (see `tab_width`)
+   */
+  strip_indent?: "off" | "trim-only" | "dedent";
+
+  /**
+   * With `strip_indent: "dedent"`, count tabs up to the next multiple of this
+   * width so that indentation mixing tabs and spaces is dedented too. Must be
+   * a positive whole number, and throws with any other `strip_indent`.
+   */
+  tab_width?: number;
+
+  /**
+   * The local name of the generated import. Default is `"readable"`, which
+   * picks the shortest name that doesn't collide with anything in the module
+   * (`template`, `template1`, ...). `"fixed"` uses a name with a fixed UUID
+   * suffix, like `template_fd9b2463e5f141cfb5666b64daa1f11a`.
+   */
+  import_alias?: "readable" | "fixed";
+
+  /**
+   * The module format of the generated import and of the implied default
+   * export of a top-level template. Default is `"module"`. `"commonjs"` emits
+   * `const { template } = require("@ember/template-compiler")` and
+   * `exports.default = template(...)` instead, after
+   * `Object.defineProperty(exports, "__esModule", { value: true })`. That is
+   * the shape Babel and TypeScript emit, so their interop gives a default
+   * import the template. An anonymous `export default class` with a template
+   * that is lowered outside the class is exported the same way. Only these
+   * generated imports and exports are CommonJS: the module's own `import` and
+   * `export` statements are left as they are, so `"commonjs"` is meant for
+   * modules that are already CommonJS or are converted afterwards.
+   */
+  module_format?: "module" | "commonjs";
+
+  /**
+   * Where a generated import goes when the module doesn't already import from
+   * the same package. Default is `"start"`. `"after-imports"` places it after
+   * the last existing import. Either way it stays below directives like
+   * `"use client"`, a shebang, and leading license comments.
+   */
+  import_placement?: "start" | "after-imports";
+
+  /**
+   * Print compact output without formatting whitespace.
+   */
+  minify?: boolean;
+
+  /**
+   * A hint for the code generator, which picks how to print things like
+   * string escapes for this ECMAScript version. Syntax isn't down-leveled:
+   * static blocks, class fields and anything else in the input are printed
+   * as they are, so use `static_blocks: false` or a separate transpiler for
+   * older runtimes. Default is `"es5"`.
+   */
+  target?:
+    | "es3"
+    | "es5"
+    | "es2015"
+    | "es2016"
+    | "es2017"
+    | "es2018"
+    | "es2019"
+    | "es2020"
+    | "es2021"
+    | "es2022"
+    | "esnext";
+
+  /**
+   * Escape non-ASCII characters in the output.
+   */
+  ascii_only?: boolean;
+
+  /**
+   * Line endings of the output. Default is `"lf"`. `"preserve"` uses `"crlf"`
+   * when the input contains any CRLF line ending.
+   */
+  line_ending?: "lf" | "crlf" | "preserve";
+
+  /**
+   * Written as the source map's `sourceRoot`.
+   */
+  source_root?: string;
+
+  /**
+   * Write the source map's `sources` relative to this directory instead of
+   * as `filename` was given.
+   */
+  sources_relative_to?: string;
+
+  /**
+   * Written as the source map's `file`.
+   */
+  source_map_file?: string;
+
+  /**
+   * Include the original source as the map's `sourcesContent`. Default is
+   * `true`.
+   */
+  sources_content?: boolean;
+
+  /**
+   * Add a [debug ID](https://github.com/tc39/ecma426/blob/main/proposals/debug-id.md)
+   * to the map's `debugId` and a `//# debugId=` comment to the code. The ID is
+   * derived from the input and `filename`, so it is stable across rebuilds.
+   */
+  debug_id?: boolean;
+
+  /**
+   * For development servers. Each module-level template (in a top-level
+   * class or a top-level expression) gets an identity made of `filename`, its
+   * index in the file and a hash of its contents, and the output gets an
+   * `import.meta.hot` hook. Components keep one template per identity, so when
+   * only templates changed the new ones take effect in the components already
+   * in use and the module accepts the update; any other edit, including one to
+   * a template nested in a function, invalidates the module. The hook wraps
+   * the template factories that `template()` returns and forwards their
+   * properties, so Ember versions that read templates some other way need a
+   * full reload. Throws with the `precompile-template` flavor,
+   * `static_blocks: false` or `commonjs` output.
+   */
+  hot?: boolean;
 }
 
 /**
@@ -51,4 +362,79 @@ export class Preprocessor {
    * @returns {any}
    */
   parse(src: string, options?: PreprocessorOptions): Parsed[];
+  /**
+   * @param {string} src
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  parseEstree(src: string, options?: PreprocessorOptions): EstreeProgram;
+  /**
+   * @param {string} src
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  importUsage(src: string, options?: PreprocessorOptions): ImportUsage;
+  /**
+   * @param {string} src
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  crossReferences(src: string, options?: PreprocessorOptions): CrossReferences;
+  /**
+   * @param {string} src
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  semanticTokens(src: string, options?: PreprocessorOptions): SemanticTokens;
+  /**
+   * @param {string} src
+   * @param {TemplateLintConfig | undefined} config
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  lintTemplates(
+    src: string,
+    config?: TemplateLintConfig,
+    options?: PreprocessorOptions,
+  ): TemplateDiagnostic[];
+  /**
+   * @param {string} src
+   * @param {TemplateFormatOptions | undefined} formatOptions
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {string}
+   */
+  formatTemplates(
+    src: string,
+    formatOptions?: TemplateFormatOptions,
+    options?: PreprocessorOptions,
+  ): string;
+  /**
+   * @param {string} src
+   * @param {PreprocessorOptions | undefined} options
+   * @returns {any}
+   */
+  preprocess(src: string, options?: PreprocessorOptions): LintBlock[];
+  /**
+   * @param {any} blocks
+   * @param {any} messages
+   * @returns {any}
+   */
+  postprocess(blocks: LintBlock[], messages: LintMessage[][]): LintMessage[];
 }
+
+/**
+ * An ESLint processor built on `preprocess` and `postprocess`, which keeps the
+ * blocks of each file between the two calls.
+ */
+interface LintProcessor {
+  meta: { name: string };
+  supportsAutofix: true;
+  preprocess(text: string, filename: string): LintBlock[];
+  postprocess(messages: LintMessage[][], filename: string): LintMessage[];
+}
+
+/**
+ * Creates an ESLint processor for `.gjs` and `.gts` files, which uses a new
+ * `Preprocessor` unless it is given one.
+ */
+export function createLintProcessor(preprocessor?: Preprocessor): LintProcessor;
@@ -1,3 +1,27 @@
 const { Preprocessor } = require("./node/content_tag.cjs");
 
+// An ESLint processor, see `createLintProcessor` in index.d.cts. ESLint calls
+// `preprocess` and `postprocess` without `this`, so they close over their
+// state instead of living on a class.
+function createLintProcessor(preprocessor = new Preprocessor()) {
+  const blocks = new Map();
+  return {
+    meta: { name: "content-tag" },
+    supportsAutofix: true,
+    preprocess(text, filename) {
+      const result = preprocessor.preprocess(text, { filename });
+      blocks.set(filename, result);
+      return result;
+    },
+    postprocess(messages, filename) {
+      const result = blocks.has(filename)
+        ? preprocessor.postprocess(blocks.get(filename), messages)
+        : messages.flat();
+      blocks.delete(filename);
+      return result;
+    },
+  };
+}
+
 module.exports.Preprocessor = Preprocessor;
+module.exports.createLintProcessor = createLintProcessor;
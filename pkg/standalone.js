@@ -1,4 +1,28 @@
-import init from "./standalone/content_tag.js";
-export { Preprocessor } from "./standalone/content_tag.js";
+import init, { Preprocessor } from "./standalone/content_tag.js";
+
+export { Preprocessor };
+
+// An ESLint processor, see `createLintProcessor` in index.d.ts. ESLint calls
+// `preprocess` and `postprocess` without `this`, so they close over their
+// state instead of living on a class.
+export function createLintProcessor(preprocessor = new Preprocessor()) {
+  const blocks = new Map();
+  return {
+    meta: { name: "content-tag" },
+    supportsAutofix: true,
+    preprocess(text, filename) {
+      const result = preprocessor.preprocess(text, { filename });
+      blocks.set(filename, result);
+      return result;
+    },
+    postprocess(messages, filename) {
+      const result = blocks.has(filename)
+        ? preprocessor.postprocess(blocks.get(filename), messages)
+        : messages.flat();
+      blocks.delete(filename);
+      return result;
+    },
+  };
+}
 
 await init();
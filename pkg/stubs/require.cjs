@@ -12,4 +12,10 @@ class Preprocessor {
   }
 }
 
-module.exports = { Preprocessor };
+function createLintProcessor() {
+  throw new Error(
+    `Tried to create a lint processor using require() when an import was expected. Please change to using import.`,
+  );
+}
+
+module.exports = { Preprocessor, createLintProcessor };
@@ -0,0 +1,14 @@
+use std::io;
+use std::process::exit;
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match content_tag::lsp::run(stdin.lock(), stdout.lock()) {
+        Ok(code) => exit(code),
+        Err(err) => {
+            eprintln!("content-tag-lsp: {err}");
+            exit(1);
+        }
+    }
+}
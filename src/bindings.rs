@@ -1,15 +1,11 @@
+use crate::js_options::{options_from, parse_error_detail, parse_error_message, OptionsObject};
+use crate::lint_blocks::{self, LintBlock};
+use crate::template_format::{self, FormatOptions};
+use crate::template_lint::{LintConfig, Registry};
 use crate::{Options, Preprocessor as CorePreprocessor};
 use js_sys::Reflect;
-use std::path::PathBuf;
-use swc_common::{
-    errors::Handler,
-    sync::Lrc,
-    SourceMap, Spanned,
-};
-use swc_error_reporters::{
-    handler::{HandlerOpts, ThreadSafetyDiagnostics},
-    ErrorEmitter, GraphicalReportHandler, GraphicalTheme, ToPrettyDiagnostic,
-};
+use swc_common::{sync::Lrc, SourceMap};
+use swc_error_reporters::GraphicalTheme;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -20,6 +16,9 @@ extern "C" {
     #[wasm_bindgen(js_namespace = JSON, js_name = parse)]
     fn json_parse(value: JsValue) -> JsValue;
 
+    #[wasm_bindgen(js_namespace = JSON, js_name = stringify, catch)]
+    fn json_stringify(value: &JsValue) -> Result<JsValue, JsValue>;
+
     #[wasm_bindgen(js_name = Boolean)]
     fn js_boolean(value: &JsValue) -> bool;
 
@@ -27,32 +26,51 @@ extern "C" {
     fn js_string(value: &JsValue) -> String;
 }
 
+// unwrapping in here because `options` is known to be truthy, so the normal
+// case of not passing any options has been handled and `Reflect::get` will
+// only fail in unusual cases (like a Javascript getter throwing)
+struct JsOptions(JsValue);
+
+impl JsOptions {
+    fn get(&self, key: &str) -> JsValue {
+        Reflect::get(&self.0, &key.into()).unwrap()
+    }
+}
+
+impl OptionsObject for JsOptions {
+    fn string(&self, key: &str) -> Option<String> {
+        self.get(key).as_string()
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        self.get(key).as_f64()
+    }
+
+    fn truthy(&self, key: &str) -> Option<bool> {
+        let value = self.get(key);
+        if value.is_undefined() {
+            None
+        } else {
+            Some(js_boolean(&value))
+        }
+    }
+
+    fn coerced_string(&self, key: &str) -> Option<String> {
+        let value = self.get(key);
+        if js_boolean(&value) {
+            Some(js_string(&value))
+        } else {
+            None
+        }
+    }
+}
+
 impl Options {
-    pub fn new(options: JsValue) -> Self {
+    pub fn new(options: JsValue) -> Result<Self, JsValue> {
         if js_boolean(&options) {
-            // unwrapping here beacuse we already checked truthiness of
-            // `options`, so the normal case of not passing any options has been
-            // handled and this will only fail in unusual cases (like a
-            // Javascript getter throwing)
-            let option_filename = Reflect::get(&options, &"filename".into()).unwrap();
-            let filename = if js_boolean(&option_filename) {
-                Some(PathBuf::from(js_string(&option_filename)))
-            } else {
-                None
-            };
-
-            Self {
-                // unwrap is justified here for the same reasons as commented above
-                inline_source_map: js_boolean(
-                    &Reflect::get(&options, &"inline_source_map".into()).unwrap(),
-                ),
-                filename,
-            }
+            options_from(&JsOptions(options)).map_err(|err| js_error(err.to_string().into()))
         } else {
-            Self {
-                inline_source_map: false,
-                filename: None,
-            }
+            Ok(Default::default())
         }
     }
 }
@@ -80,50 +98,41 @@ pub struct Preprocessor {
     // core: Box<CorePreprocessor>,
 }
 
-fn capture_err_detail(
-    err: swc_ecma_parser::error::Error,
-    source_map: Lrc<SourceMap>,
-    theme: GraphicalTheme,
-) -> JsValue {
-    let mut diagnostics = ThreadSafetyDiagnostics::default();
-    let emitter = ErrorEmitter {
-        diagnostics: diagnostics.clone(),
-        cm: source_map.clone(),
-        opts: HandlerOpts::default(),
-    };
-    let handler = Handler::with_emitter(true, false, Box::new(emitter));
-    err.into_diagnostic(&handler).emit();
-    let reporter = GraphicalReportHandler::new_themed(theme);
-    let s: String = diagnostics
-        .take()
-        .iter()
-        .map(|d| d.to_pretty_string(&source_map, false, &reporter))
-        .collect();
-    s.into()
-}
-
 fn as_javascript_error(err: swc_ecma_parser::error::Error, source_map: Lrc<SourceMap>) -> JsValue {
-    let short_desc = format!("Parse Error at {}", source_map.span_to_string(err.span()));
+    let short_desc = parse_error_message(&err, &source_map);
     let js_err = js_error(short_desc.into());
     js_sys::Reflect::set(
         &js_err,
         &"source_code".into(),
-        &capture_err_detail(
+        &parse_error_detail(
             err.clone(),
             source_map.clone(),
             GraphicalTheme::unicode_nocolor(),
-        ),
+        )
+        .into(),
     )
     .unwrap();
     js_sys::Reflect::set(
         &js_err,
         &"source_code_color".into(),
-        &capture_err_detail(err, source_map, GraphicalTheme::unicode()),
+        &parse_error_detail(err, source_map, GraphicalTheme::unicode()).into(),
     )
     .unwrap();
     return js_err;
 }
 
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    match serde_json::to_string(value) {
+        Ok(serialized) => Ok(json_parse(serialized.into())),
+        Err(err) => Err(js_error(format!("Unexpected serialization error; please open an issue with the following debug info: {err:#?}").into())),
+    }
+}
+
+fn from_js<T: serde::de::DeserializeOwned>(value: &JsValue, what: &str) -> Result<T, JsValue> {
+    let json = json_stringify(value)?.as_string().unwrap_or_default();
+    serde_json::from_str(&json).map_err(|err| js_error(format!("Invalid {what}: {err}").into()))
+}
+
 #[wasm_bindgen]
 impl Preprocessor {
     #[wasm_bindgen(constructor)]
@@ -136,7 +145,7 @@ impl Preprocessor {
     }
 
     pub fn process(&self, src: String, options: JsValue) -> Result<CodeMapPair, JsValue> {
-        let options = Options::new(options);
+        let options = Options::new(options)?;
         let preprocessor = CorePreprocessor::new();
         let result = preprocessor.process(&src, options);
 
@@ -147,18 +156,113 @@ impl Preprocessor {
     }
 
     pub fn parse(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
-        let options = Options::new(options);
+        let options = Options::new(options)?;
         let preprocessor = CorePreprocessor::new();
         let result = preprocessor.parse(&src, options);
 
         match result {
-            Ok(parsed) => {
-                match serde_json::to_string(&parsed) {
-                    Ok(serialized) => Ok(json_parse(serialized.into())),
-                    Err(err) =>  Err(js_error(format!("Unexpected serialization error; please open an issue with the following debug info: {err:#?}").into()))
-                }
+            Ok(parsed) => to_js(&parsed),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    #[wasm_bindgen(js_name = parseEstree)]
+    pub fn parse_estree(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse_estree(&src, options) {
+            Ok(program) => to_js(&program),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    #[wasm_bindgen(js_name = importUsage)]
+    pub fn import_usage(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.import_usage(&src, options) {
+            Ok(usage) => to_js(&usage),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    #[wasm_bindgen(js_name = crossReferences)]
+    pub fn cross_references(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.cross_references(&src, options) {
+            Ok(xrefs) => to_js(&xrefs),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    #[wasm_bindgen(js_name = semanticTokens)]
+    pub fn semantic_tokens(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.semantic_tokens(&src, options) {
+            Ok(tokens) => to_js(&tokens),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    #[wasm_bindgen(js_name = lintTemplates)]
+    pub fn lint_templates(
+        &self,
+        src: String,
+        config: JsValue,
+        options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let config: LintConfig = if config.is_undefined() || config.is_null() {
+            Default::default()
+        } else {
+            from_js(&config, "config")?
+        };
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, options) {
+            Ok(parsed) => match Registry::default().lint(&src, &parsed, &config) {
+                Ok(diagnostics) => to_js(&diagnostics),
+                Err(err) => Err(js_error(err.to_string().into())),
             },
-            Err(err) => Err(as_javascript_error(err, preprocessor.source_map()))
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
         }
     }
+
+    #[wasm_bindgen(js_name = formatTemplates)]
+    pub fn format_templates(
+        &self,
+        src: String,
+        format_options: JsValue,
+        options: JsValue,
+    ) -> Result<String, JsValue> {
+        let format_options: FormatOptions =
+            if format_options.is_undefined() || format_options.is_null() {
+                Default::default()
+            } else {
+                from_js(&format_options, "formatOptions")?
+            };
+        let options = Options::new(options)?;
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, options) {
+            Ok(parsed) => Ok(template_format::format(&src, &parsed, &format_options)),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    pub fn preprocess(&self, src: String, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = Options::new(options)?;
+        let filename = options.filename.clone();
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, options) {
+            Ok(parsed) => to_js(&lint_blocks::preprocess(&src, &parsed, filename.as_deref())),
+            Err(err) => Err(as_javascript_error(err, preprocessor.source_map())),
+        }
+    }
+
+    pub fn postprocess(&self, blocks: JsValue, messages: JsValue) -> Result<JsValue, JsValue> {
+        let blocks: Vec<LintBlock> = from_js(&blocks, "blocks")?;
+        let messages = from_js(&messages, "messages")?;
+        to_js(&lint_blocks::postprocess(&blocks, messages))
+    }
 }
@@ -0,0 +1,311 @@
+// A C ABI for embedding the preprocessor outside of JS. Inputs are UTF-8
+// buffers, options are a JSON object with the same keys as the JS
+// `PreprocessorOptions`, and every result is owned by the caller until it is
+// passed to `content_tag_result_free`. `include/content_tag.h` is generated
+// from this file with `cbindgen --config cbindgen.toml --output include/content_tag.h`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use serde::Serialize;
+use swc_common::{sync::Lrc, SourceMap, Spanned};
+use swc_error_reporters::GraphicalTheme;
+
+use crate::js_options::{options_from, parse_error_detail, parse_error_message, OptionsObject};
+use crate::locate::byte_range;
+use crate::{Options, Preprocessor};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentTagStatus {
+    Ok = 0,
+    /// The source didn't parse, `error` describes where.
+    ParseError = 1,
+    /// A null pointer with a non-zero length, input that isn't UTF-8, or
+    /// options that aren't a JSON object or set an option to a value it doesn't
+    /// accept.
+    InvalidArgument = 2,
+    /// A bug in the preprocessor, `error` holds the panic message if any.
+    InternalError = 3,
+}
+
+/// An owned byte buffer. `data` is null when `len` is 0.
+#[repr(C)]
+pub struct ContentTagBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl ContentTagBuffer {
+    fn empty() -> Self {
+        ContentTagBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn new(bytes: Vec<u8>) -> Self {
+        if bytes.is_empty() {
+            return Self::empty();
+        }
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        ContentTagBuffer { data, len }
+    }
+
+    unsafe fn free(&mut self) {
+        if !self.data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                self.data, self.len,
+            )));
+        }
+        *self = Self::empty();
+    }
+}
+
+#[repr(C)]
+pub struct ContentTagResult {
+    pub status: ContentTagStatus,
+    /// The generated code for `content_tag_process`, or the JSON array of
+    /// occurrences for `content_tag_parse`.
+    pub output: ContentTagBuffer,
+    /// The JSON source map, for `content_tag_process`.
+    pub map: ContentTagBuffer,
+    /// When `status` isn't `Ok`, a JSON object with a `message`, and for parse
+    /// errors the rendered diagnostic as `detail` and, when the error points
+    /// into the source, its byte offsets as `startByte` and `endByte`.
+    pub error: ContentTagBuffer,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorReport {
+    message: String,
+    // the rendered diagnostic, for parse errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    // byte offsets into the source, for parse errors that point into it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_byte: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_byte: Option<usize>,
+}
+
+impl ErrorReport {
+    fn message(message: impl Into<String>) -> Self {
+        ErrorReport {
+            message: message.into(),
+            detail: None,
+            start_byte: None,
+            end_byte: None,
+        }
+    }
+
+    fn parse_error(err: swc_ecma_parser::error::Error, source_map: Lrc<SourceMap>) -> Self {
+        let range = byte_range(&source_map, err.span());
+        ErrorReport {
+            message: parse_error_message(&err, &source_map),
+            start_byte: range.as_ref().map(|range| range.start),
+            end_byte: range.map(|range| range.end),
+            detail: Some(parse_error_detail(
+                err,
+                source_map,
+                GraphicalTheme::unicode_nocolor(),
+            )),
+        }
+    }
+}
+
+fn result(
+    status: ContentTagStatus,
+    output: Vec<u8>,
+    map: Vec<u8>,
+    error: Option<ErrorReport>,
+) -> *mut ContentTagResult {
+    let error = match error {
+        Some(report) => ContentTagBuffer::new(serde_json::to_vec(&report).unwrap()),
+        None => ContentTagBuffer::empty(),
+    };
+    Box::into_raw(Box::new(ContentTagResult {
+        status,
+        output: ContentTagBuffer::new(output),
+        map: ContentTagBuffer::new(map),
+        error,
+    }))
+}
+
+fn failure(status: ContentTagStatus, report: ErrorReport) -> *mut ContentTagResult {
+    result(status, vec![], vec![], Some(report))
+}
+
+impl OptionsObject for serde_json::Value {
+    fn string(&self, key: &str) -> Option<String> {
+        self.get(key)?.as_str().map(str::to_string)
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    fn truthy(&self, key: &str) -> Option<bool> {
+        use serde_json::Value;
+        Some(match self.get(key)? {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0 && !n.is_nan()),
+            Value::String(s) => !s.is_empty(),
+            Value::Array(_) | Value::Object(_) => true,
+        })
+    }
+
+    fn coerced_string(&self, key: &str) -> Option<String> {
+        if self.truthy(key) != Some(true) {
+            return None;
+        }
+        match self.get(key)? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+unsafe fn read_str<'a>(data: *const u8, len: usize, what: &str) -> Result<&'a str, ErrorReport> {
+    if len == 0 {
+        return Ok("");
+    }
+    if data.is_null() {
+        return Err(ErrorReport::message(format!("{what} is null")));
+    }
+    std::str::from_utf8(std::slice::from_raw_parts(data, len))
+        .map_err(|err| ErrorReport::message(format!("{what} is not UTF-8: {err}")))
+}
+
+unsafe fn read_options(data: *const u8, len: usize) -> Result<Options, ErrorReport> {
+    let json = read_str(data, len, "options")?;
+    if json.is_empty() {
+        return Ok(Default::default());
+    }
+    match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(value @ serde_json::Value::Object(_)) => {
+            options_from(&value).map_err(|err| ErrorReport::message(err.to_string()))
+        }
+        Ok(_) => Err(ErrorReport::message("options is not a JSON object")),
+        Err(err) => Err(ErrorReport::message(format!(
+            "options is not valid JSON: {err}"
+        ))),
+    }
+}
+
+// Runs `f` on the decoded arguments, turning panics into `InternalError`.
+unsafe fn call(
+    src: *const u8,
+    src_len: usize,
+    options: *const u8,
+    options_len: usize,
+    f: impl FnOnce(&str, Options) -> *mut ContentTagResult,
+) -> *mut ContentTagResult {
+    let (src, options) = match (
+        read_str(src, src_len, "src"),
+        read_options(options, options_len),
+    ) {
+        (Ok(src), Ok(options)) => (src, options),
+        (Err(report), _) | (_, Err(report)) => {
+            return failure(ContentTagStatus::InvalidArgument, report)
+        }
+    };
+    match catch_unwind(AssertUnwindSafe(|| f(src, options))) {
+        Ok(result) => result,
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            failure(
+                ContentTagStatus::InternalError,
+                ErrorReport::message(message),
+            )
+        }
+    }
+}
+
+/// Lowers the `<template>` tags in `src`. The result's `output` is the code and
+/// `map` is the source map.
+///
+/// # Safety
+///
+/// `src` and `options_json` must each be null or point to at least the given
+/// number of readable bytes. The result must be freed with
+/// `content_tag_result_free`.
+#[no_mangle]
+pub unsafe extern "C" fn content_tag_process(
+    src: *const u8,
+    src_len: usize,
+    options_json: *const u8,
+    options_len: usize,
+) -> *mut ContentTagResult {
+    call(src, src_len, options_json, options_len, |src, options| {
+        let preprocessor = Preprocessor::new();
+        match preprocessor.process(src, options) {
+            Ok(output) => result(
+                ContentTagStatus::Ok,
+                output.code.into_bytes(),
+                output.map.into_bytes(),
+                None,
+            ),
+            Err(err) => failure(
+                ContentTagStatus::ParseError,
+                ErrorReport::parse_error(err, preprocessor.source_map()),
+            ),
+        }
+    })
+}
+
+/// Locates the `<template>` tags in `src`. The result's `output` is a JSON
+/// array with the same shape as the JS `parse` result.
+///
+/// # Safety
+///
+/// `src` and `options_json` must each be null or point to at least the given
+/// number of readable bytes. The result must be freed with
+/// `content_tag_result_free`.
+#[no_mangle]
+pub unsafe extern "C" fn content_tag_parse(
+    src: *const u8,
+    src_len: usize,
+    options_json: *const u8,
+    options_len: usize,
+) -> *mut ContentTagResult {
+    call(src, src_len, options_json, options_len, |src, options| {
+        let preprocessor = Preprocessor::new();
+        match preprocessor.parse(src, options) {
+            Ok(occurrences) => result(
+                ContentTagStatus::Ok,
+                serde_json::to_vec(&occurrences).unwrap(),
+                vec![],
+                None,
+            ),
+            Err(err) => failure(
+                ContentTagStatus::ParseError,
+                ErrorReport::parse_error(err, preprocessor.source_map()),
+            ),
+        }
+    })
+}
+
+/// Frees a result and all of its buffers.
+///
+/// # Safety
+///
+/// `result` must be null or a pointer returned by `content_tag_process` or
+/// `content_tag_parse` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn content_tag_result_free(result: *mut ContentTagResult) {
+    if result.is_null() {
+        return;
+    }
+    let mut result = Box::from_raw(result);
+    result.output.free();
+    result.map.free();
+    result.error.free();
+}
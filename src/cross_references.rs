@@ -0,0 +1,318 @@
+// Links the names that templates refer to with the JS declarations they
+// resolve to, for jump-to-definition, find-references and rename in editors.
+//
+// The module is resolved with its templates exposed as described in
+// template_scope.rs, and then every declaration in the module is recorded by
+// its name and syntax context, which is what the resolver gives every
+// identifier that refers to it.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use swc_atoms::Atom;
+use swc_common::{BytePos, Span, SyntaxContext};
+use swc_ecma_ast::{
+    ArrowExpr, CatchClause, ClassDecl, ClassExpr, FnDecl, FnExpr, Ident, ImportDefaultSpecifier,
+    ImportNamedSpecifier, ImportStarAsSpecifier, Module, Param, SetterProp, TsEnumDecl,
+    TsInterfaceDecl, TsParamProp, TsTypeAliasDecl, VarDeclarator,
+};
+use swc_ecma_utils::{find_pat_ids, DestructuringFinder};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::locate::Range;
+use crate::template_refs::ReferenceKind;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossReferences {
+    // every template reference, in the order they appear in the file
+    pub references: Vec<TemplateReferenceLink>,
+    // the bindings that templates refer to, in the order they are declared
+    pub bindings: Vec<BindingReferences>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateReferenceLink {
+    pub name: String,
+    pub kind: ReferenceKind,
+    // the name in the template
+    pub range: Range,
+    // the name in the declaration it resolves to, or `None` when nothing in
+    // scope binds it
+    pub definition: Option<Range>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingReferences {
+    pub name: String,
+    pub definition: Range,
+    pub template_references: Vec<Range>,
+}
+
+// `module` has been through `template_scope::expose_template_references`,
+// which returned `kinds`, and then `template_scope::resolve`.
+pub fn analyze(
+    src: &str,
+    module: &Module,
+    kinds: &HashMap<BytePos, ReferenceKind>,
+) -> CrossReferences {
+    let mut collector = Collector {
+        kinds,
+        declarations: Default::default(),
+        references: vec![],
+    };
+    module.visit_with(&mut collector);
+
+    let is_ascii = src.is_ascii();
+    let mut references = vec![];
+    // indexes into `bindings`, by declaration
+    let mut binding_indexes: HashMap<Span, usize> = HashMap::new();
+    let mut bindings: Vec<(Span, BindingReferences)> = vec![];
+    collector.references.sort_by_key(|(ident, _)| ident.span.lo);
+    for (ident, kind) in &collector.references {
+        let declaration = collector
+            .declarations
+            .get(&(ident.sym.clone(), ident.ctxt))
+            .copied();
+        if let Some(declaration) = declaration {
+            let index = *binding_indexes.entry(declaration).or_insert_with(|| {
+                bindings.push((
+                    declaration,
+                    BindingReferences {
+                        name: ident.sym.to_string(),
+                        definition: Range::new(src, &declaration, is_ascii),
+                        template_references: vec![],
+                    },
+                ));
+                bindings.len() - 1
+            });
+            bindings[index]
+                .1
+                .template_references
+                .push(Range::new(src, &ident.span, is_ascii));
+        }
+        references.push(TemplateReferenceLink {
+            name: ident.sym.to_string(),
+            kind: *kind,
+            range: Range::new(src, &ident.span, is_ascii),
+            definition: declaration.map(|span| Range::new(src, &span, is_ascii)),
+        });
+    }
+    bindings.sort_by_key(|(span, _)| span.lo);
+
+    CrossReferences {
+        references,
+        bindings: bindings.into_iter().map(|(_, binding)| binding).collect(),
+    }
+}
+
+struct Collector<'a> {
+    kinds: &'a HashMap<BytePos, ReferenceKind>,
+    // where each binding is declared, by the name and syntax context that
+    // references to it have
+    declarations: HashMap<(Atom, SyntaxContext), Span>,
+    references: Vec<(Ident, ReferenceKind)>,
+}
+
+impl Collector<'_> {
+    fn declare(&mut self, ident: &Ident) {
+        self.declarations
+            .entry((ident.sym.clone(), ident.ctxt))
+            .or_insert(ident.span);
+    }
+
+    // the names a pattern in a declaration position binds; a pattern that is
+    // assigned to, like `[x] = arr`, doesn't declare anything
+    fn declare_pat<N: VisitWith<DestructuringFinder<Ident>>>(&mut self, pat: &N) {
+        for ident in find_pat_ids::<_, Ident>(pat) {
+            self.declare(&ident);
+        }
+    }
+}
+
+impl Visit for Collector<'_> {
+    fn visit_ident(&mut self, n: &Ident) {
+        if let Some(kind) = self.kinds.get(&n.span.lo) {
+            self.references.push((n.clone(), *kind));
+        }
+    }
+
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        self.declare_pat(&n.name);
+        n.visit_children_with(self);
+    }
+
+    fn visit_param(&mut self, n: &Param) {
+        self.declare_pat(&n.pat);
+        n.visit_children_with(self);
+    }
+
+    fn visit_arrow_expr(&mut self, n: &ArrowExpr) {
+        for param in &n.params {
+            self.declare_pat(param);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_setter_prop(&mut self, n: &SetterProp) {
+        self.declare_pat(&*n.param);
+        n.visit_children_with(self);
+    }
+
+    fn visit_ts_param_prop(&mut self, n: &TsParamProp) {
+        self.declare_pat(&n.param);
+        n.visit_children_with(self);
+    }
+
+    fn visit_catch_clause(&mut self, n: &CatchClause) {
+        if let Some(param) = &n.param {
+            self.declare_pat(param);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_fn_decl(&mut self, n: &FnDecl) {
+        self.declare(&n.ident);
+        n.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, n: &FnExpr) {
+        if let Some(ident) = &n.ident {
+            self.declare(ident);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, n: &ClassDecl) {
+        self.declare(&n.ident);
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, n: &ClassExpr) {
+        if let Some(ident) = &n.ident {
+            self.declare(ident);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_import_named_specifier(&mut self, n: &ImportNamedSpecifier) {
+        self.declare(&n.local);
+    }
+
+    fn visit_import_default_specifier(&mut self, n: &ImportDefaultSpecifier) {
+        self.declare(&n.local);
+    }
+
+    fn visit_import_star_as_specifier(&mut self, n: &ImportStarAsSpecifier) {
+        self.declare(&n.local);
+    }
+
+    fn visit_ts_interface_decl(&mut self, n: &TsInterfaceDecl) {
+        self.declare(&n.id);
+        n.visit_children_with(self);
+    }
+
+    fn visit_ts_type_alias_decl(&mut self, n: &TsTypeAliasDecl) {
+        self.declare(&n.id);
+        n.visit_children_with(self);
+    }
+
+    fn visit_ts_enum_decl(&mut self, n: &TsEnumDecl) {
+        self.declare(&n.id);
+        n.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+fn text(src: &str, range: &Range) -> String {
+    src[range.start_byte()..range.end_byte()].to_string()
+}
+
+#[test]
+fn links_template_references_to_declarations() {
+    let src = r#"import Foo from "./foo";
+import { on } from "@ember/modifier";
+const greeting = "hi";
+function render(greeting) {
+  return <template>{{greeting}}<Foo /></template>;
+}
+export default class Bar {
+  <template><Foo {{on "click" this.go}} />{{greeting}}{{missing}}</template>
+}"#;
+    let xrefs = crate::Preprocessor::new()
+        .cross_references(src, Default::default())
+        .unwrap();
+
+    let references: Vec<_> = xrefs
+        .references
+        .iter()
+        .map(|r| {
+            let definition = r.definition.as_ref().map(|d| d.start_byte());
+            (r.name.as_str(), r.kind, definition)
+        })
+        .collect();
+    let foo = src.find("Foo").unwrap();
+    let on = src.find("on }").unwrap();
+    let top_greeting = src.find("greeting").unwrap();
+    let param_greeting = src.find("render(").unwrap() + 7;
+    assert_eq!(
+        references,
+        vec![
+            ("greeting", ReferenceKind::Path, Some(param_greeting)),
+            ("Foo", ReferenceKind::Component, Some(foo)),
+            ("Foo", ReferenceKind::Component, Some(foo)),
+            ("on", ReferenceKind::Modifier, Some(on)),
+            ("greeting", ReferenceKind::Path, Some(top_greeting)),
+            ("missing", ReferenceKind::Path, None),
+        ]
+    );
+
+    let bindings: Vec<_> = xrefs
+        .bindings
+        .iter()
+        .map(|b| {
+            (
+                b.name.as_str(),
+                text(src, &b.definition),
+                b.template_references.len(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        bindings,
+        vec![
+            ("Foo", "Foo".to_string(), 2),
+            ("on", "on".to_string(), 1),
+            ("greeting", "greeting".to_string(), 1),
+            ("greeting", "greeting".to_string(), 1),
+        ]
+    );
+    assert_eq!(text(src, &xrefs.bindings[0].template_references[1]), "Foo");
+}
+
+#[test]
+fn links_to_declarations_rather_than_assignments() {
+    let src = r#"let x;
+[x] = [1];
+function f() {
+  ({ y: x } = {});
+  var x;
+  return <template>{{x}}</template>;
+}
+export default <template>{{x}}</template>;"#;
+    let xrefs = crate::Preprocessor::new()
+        .cross_references(src, Default::default())
+        .unwrap();
+
+    let definitions: Vec<_> = xrefs
+        .references
+        .iter()
+        .map(|r| r.definition.as_ref().map(|d| d.start_byte()))
+        .collect();
+    assert_eq!(
+        definitions,
+        vec![Some(src.find("var x").unwrap() + 4), Some(4)]
+    );
+}
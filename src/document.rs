@@ -0,0 +1,206 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use swc_common::{sync::Lrc, SourceMap, Span};
+
+use crate::locate::{byte_range, Occurrence, Shift};
+use crate::{Options, Preprocessor, StripIndent};
+
+// A source file that is kept in sync with an editor buffer. Edits inside a
+// template's contents update the occurrences in place, since they can't change
+// the structure of the surrounding JS. Any other edit re-parses the file.
+pub struct Document {
+    src: String,
+    filename: Option<PathBuf>,
+    strip_indent: StripIndent,
+    occurrences: Vec<Occurrence>,
+    // whether `occurrences` belong to `src`
+    parses: bool,
+    // the source map of the last parse, which the spans of its errors are in
+    source_map: Lrc<SourceMap>,
+}
+
+impl Document {
+    pub fn new(src: String) -> Result<Self, swc_ecma_parser::error::Error> {
+        Self::with_options(src, Default::default())
+    }
+
+    // Only `filename` and `strip_indent` are used, as they are by
+    // `Preprocessor::parse`.
+    pub fn with_options(
+        src: String,
+        options: Options,
+    ) -> Result<Self, swc_ecma_parser::error::Error> {
+        let mut document = Document {
+            src,
+            filename: options.filename,
+            strip_indent: options.strip_indent,
+            occurrences: vec![],
+            parses: false,
+            source_map: Default::default(),
+        };
+        document.reparse()?;
+        Ok(document)
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn occurrences(&self) -> &[Occurrence] {
+        &self.occurrences
+    }
+
+    // Whether the current source parses. When it doesn't, `occurrences` are
+    // those of the last source that did, and every edit re-parses.
+    pub fn parses(&self) -> bool {
+        self.parses
+    }
+
+    // Replaces the bytes in `range` with `text`. The source is always updated,
+    // but when the edited source doesn't parse the error is returned and the
+    // occurrences are left as they were for the last source that did.
+    //
+    // Panics if `range` doesn't fall on character boundaries of the source.
+    pub fn apply_edit(
+        &mut self,
+        range: Range<usize>,
+        text: &str,
+    ) -> Result<&[Occurrence], swc_ecma_parser::error::Error> {
+        let shift = Shift::new(&self.src[range.clone()], text);
+        let inside = self.occurrences.iter().position(|occurrence| {
+            let content = occurrence.content_range();
+            content.start_byte() <= range.start && range.end <= content.end_byte()
+        });
+
+        match inside {
+            Some(index) if self.parses && !changes_structure(&self.src, &range, text) => {
+                self.occurrences[index].edit_contents(
+                    range.clone(),
+                    text,
+                    shift,
+                    self.strip_indent,
+                );
+                for occurrence in &mut self.occurrences[index + 1..] {
+                    occurrence.shift(shift);
+                }
+                self.src.replace_range(range, text);
+            }
+            _ => {
+                self.src.replace_range(range, text);
+                self.reparse()?;
+            }
+        }
+        Ok(&self.occurrences)
+    }
+
+    // Replaces the whole source, with the same error handling as `apply_edit`.
+    pub fn replace(&mut self, src: String) -> Result<&[Occurrence], swc_ecma_parser::error::Error> {
+        self.src = src;
+        self.reparse()?;
+        Ok(&self.occurrences)
+    }
+
+    // The byte range in the source of the span of an error returned by the
+    // last edit, or `None` when the span doesn't point into it.
+    pub fn byte_range(&self, span: Span) -> Option<Range<usize>> {
+        byte_range(&self.source_map, span)
+    }
+
+    fn reparse(&mut self) -> Result<(), swc_ecma_parser::error::Error> {
+        // a fresh preprocessor, because spans are only correct for the first
+        // file parsed with one
+        let preprocessor = Preprocessor::new();
+        self.source_map = preprocessor.source_map();
+        self.parses = false;
+        self.occurrences = preprocessor.parse(
+            &self.src,
+            Options {
+                filename: self.filename.clone(),
+                strip_indent: self.strip_indent,
+                ..Default::default()
+            },
+        )?;
+        self.parses = true;
+        Ok(())
+    }
+}
+
+// Whether replacing `range` with `text` inside a template's contents could
+// open or close a tag, which changes where templates start and end. Only the
+// text around the edit can combine with `text` into a tag, so it's enough to
+// compare the tags found there before and after the edit.
+fn changes_structure(src: &str, range: &Range<usize>, text: &str) -> bool {
+    const TAGS: [&str; 2] = ["<template", "</template"];
+    let reach = TAGS[1].len();
+
+    let mut start = range.start.saturating_sub(reach);
+    while !src.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (range.end + reach).min(src.len());
+    while !src.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let before = &src[start..range.start];
+    let after = &src[range.end..end];
+    let old = &src[start..end];
+    let new = format!("{before}{text}{after}");
+    TAGS.iter()
+        .any(|tag| old.matches(tag).count() != new.matches(tag).count())
+}
+
+#[cfg(test)]
+fn assert_matches_full_parse(document: &Document) {
+    let expected = Preprocessor::new()
+        .parse(document.src(), Default::default())
+        .unwrap();
+    assert_eq!(document.occurrences(), expected.as_slice());
+}
+
+#[test]
+fn edits_inside_a_template_shift_later_templates() {
+    let mut document = Document::new(
+        "const a = <template>Hello</template>;\nconst b = <template>😀 Bye</template>;".to_string(),
+    )
+    .unwrap();
+    let start = document.src().find("Hello").unwrap();
+    document
+        .apply_edit(start..start + 5, "Hi there,\n  friend 😀")
+        .unwrap();
+    assert_matches_full_parse(&document);
+}
+
+#[test]
+fn edits_in_js_reparse() {
+    let mut document = Document::new("<template>Hello</template>".to_string()).unwrap();
+    document
+        .apply_edit(0..0, "const x = <template>x</template>;\n")
+        .unwrap();
+    assert_eq!(document.occurrences().len(), 2);
+    assert_matches_full_parse(&document);
+}
+
+#[test]
+fn edits_that_close_a_template_reparse() {
+    let mut document = Document::new("const a = <template>Hello</template>;".to_string()).unwrap();
+    let start = document.src().find("llo").unwrap();
+    document
+        .apply_edit(start..start, "</template>;\n<template>")
+        .unwrap();
+    assert_eq!(document.occurrences().len(), 2);
+    assert_matches_full_parse(&document);
+}
+
+#[test]
+fn edits_after_a_failed_parse_reparse() {
+    let mut document = Document::new("let a = <template>Hello</template>;".to_string()).unwrap();
+    assert!(document.apply_edit(0..0, "let b = ;\n").is_err());
+    assert!(!document.parses());
+    let start = document.src().find("Hello").unwrap();
+    document.apply_edit(start..start + 5, "Hi").unwrap_err();
+    document.apply_edit(8..9, "1").unwrap();
+    assert!(document.parses());
+    assert_matches_full_parse(&document);
+}
@@ -0,0 +1,2436 @@
+// Converts the swc `Module` into ESTree JSON, the AST that ESLint and most JS
+// tooling consume. Nodes follow ESTree as espree produces it, with
+// `typescript-estree` node types for TypeScript syntax. `range` is in UTF-16
+// code units and `loc` columns are UTF-16 as well, like they would be for a
+// JS string.
+//
+// `<template>` tags become `GlimmerTemplate` nodes, in expression position as
+// well as in a class body:
+//
+//   { type: "GlimmerTemplate", contents: string, range, loc }
+//
+// The rarer TypeScript forms (template literal and `import()` types, type
+// predicates, ...) are emitted as `{ type: "Unsupported", range, loc }` so
+// that the tree still covers the whole source.
+
+use serde_json::{json, Value};
+use swc_common::comments::{CommentKind, SingleThreadedComments};
+use swc_common::{BytePos, Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
+
+pub(crate) fn program(
+    src: &str,
+    start: BytePos,
+    module: &Module,
+    comments: &SingleThreadedComments,
+    tokens: &[Span],
+) -> Value {
+    let converter = Converter {
+        src,
+        start,
+        lines: LineIndex::new(src),
+    };
+    let mut program = converter.node(
+        "Program",
+        converter.program_span(module),
+        json!({
+            "sourceType": "module",
+            "body": converter.module_items(&module.body),
+        }),
+    );
+    program["comments"] = converter.comments(comments);
+    program["tokens"] = converter.tokens(module, tokens);
+    program
+}
+
+struct Converter<'a> {
+    src: &'a str,
+    // the position of the first byte of `src`
+    start: BytePos,
+    lines: LineIndex,
+}
+
+enum Item<'a> {
+    Stmt(&'a Stmt),
+    ModuleDecl(&'a ModuleDecl),
+}
+
+impl<'a> Converter<'a> {
+    // --- positions ---
+
+    fn byte(&self, pos: BytePos) -> usize {
+        (pos.0.saturating_sub(self.start.0) as usize).min(self.src.len())
+    }
+
+    fn text(&self, span: Span) -> &'a str {
+        self.src
+            .get(self.byte(span.lo)..self.byte(span.hi))
+            .unwrap_or_default()
+    }
+
+    // The position of the first `c` at or after `pos`, or `pos` when there is
+    // none.
+    fn find(&self, pos: BytePos, c: char) -> BytePos {
+        let byte = self.byte(pos);
+        match self.src[byte..].find(c) {
+            Some(found) => pos + BytePos(found as u32),
+            None => pos,
+        }
+    }
+
+    fn location(&self, span: Span) -> (Value, Value) {
+        let (start, end) = (self.byte(span.lo), self.byte(span.hi));
+        let range = json!([
+            self.lines.utf16(self.src, start),
+            self.lines.utf16(self.src, end)
+        ]);
+        let loc = json!({
+            "start": self.lines.position(self.src, start),
+            "end": self.lines.position(self.src, end),
+        });
+        (range, loc)
+    }
+
+    fn node(&self, kind: &str, span: Span, mut fields: Value) -> Value {
+        let (range, loc) = self.location(span);
+        let object = fields.as_object_mut().unwrap();
+        object.insert("type".into(), kind.into());
+        object.insert("range".into(), range);
+        object.insert("loc".into(), loc);
+        fields
+    }
+
+    fn unsupported(&self, span: Span) -> Value {
+        self.node("Unsupported", span, json!({}))
+    }
+
+    fn program_span(&self, module: &Module) -> Span {
+        if module.span.is_dummy() {
+            Span::new(self.start, self.start + BytePos(self.src.len() as u32))
+        } else {
+            module.span
+        }
+    }
+
+    // --- statements ---
+
+    // Converts a statement list, marking its directive prologue.
+    fn module_items(&self, items: &[ModuleItem]) -> Value {
+        self.statements(items.iter().map(|item| match item {
+            ModuleItem::Stmt(stmt) => Item::Stmt(stmt),
+            ModuleItem::ModuleDecl(decl) => Item::ModuleDecl(decl),
+        }))
+    }
+
+    fn statements<'b>(&self, items: impl Iterator<Item = Item<'b>>) -> Value {
+        let mut prologue = true;
+        items
+            .map(|item| match item {
+                Item::Stmt(stmt) => {
+                    let mut converted = self.stmt(stmt);
+                    match stmt {
+                        Stmt::Expr(ExprStmt {
+                            expr: box Expr::Lit(Lit::Str(s)),
+                            ..
+                        }) if prologue => {
+                            let raw = self.text(s.span);
+                            converted["directive"] = raw[1..raw.len().max(2) - 1].into();
+                        }
+                        _ => prologue = false,
+                    }
+                    converted
+                }
+                Item::ModuleDecl(decl) => {
+                    prologue = false;
+                    self.module_decl(decl)
+                }
+            })
+            .collect()
+    }
+
+    fn block(&self, block: &BlockStmt) -> Value {
+        self.node(
+            "BlockStatement",
+            block.span,
+            json!({ "body": self.statements(block.stmts.iter().map(Item::Stmt)) }),
+        )
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> Value {
+        match stmt {
+            Stmt::Block(n) => self.block(n),
+            Stmt::Empty(n) => self.node("EmptyStatement", n.span, json!({})),
+            Stmt::Debugger(n) => self.node("DebuggerStatement", n.span, json!({})),
+            Stmt::With(n) => self.node(
+                "WithStatement",
+                n.span,
+                json!({ "object": self.expr(&n.obj), "body": self.stmt(&n.body) }),
+            ),
+            Stmt::Return(n) => self.node(
+                "ReturnStatement",
+                n.span,
+                json!({ "argument": self.opt_expr(n.arg.as_deref()) }),
+            ),
+            Stmt::Labeled(n) => self.node(
+                "LabeledStatement",
+                n.span,
+                json!({ "label": self.ident(&n.label), "body": self.stmt(&n.body) }),
+            ),
+            Stmt::Break(n) => self.node(
+                "BreakStatement",
+                n.span,
+                json!({ "label": n.label.as_ref().map(|l| self.ident(l)) }),
+            ),
+            Stmt::Continue(n) => self.node(
+                "ContinueStatement",
+                n.span,
+                json!({ "label": n.label.as_ref().map(|l| self.ident(l)) }),
+            ),
+            Stmt::If(n) => self.node(
+                "IfStatement",
+                n.span,
+                json!({
+                    "test": self.expr(&n.test),
+                    "consequent": self.stmt(&n.cons),
+                    "alternate": n.alt.as_ref().map(|alt| self.stmt(alt)),
+                }),
+            ),
+            Stmt::Switch(n) => self.node(
+                "SwitchStatement",
+                n.span,
+                json!({
+                    "discriminant": self.expr(&n.discriminant),
+                    "cases": n.cases.iter().map(|case| self.node(
+                        "SwitchCase",
+                        case.span,
+                        json!({
+                            "test": self.opt_expr(case.test.as_deref()),
+                            "consequent": case.cons.iter().map(|s| self.stmt(s)).collect::<Vec<_>>(),
+                        }),
+                    )).collect::<Vec<_>>(),
+                }),
+            ),
+            Stmt::Throw(n) => self.node(
+                "ThrowStatement",
+                n.span,
+                json!({ "argument": self.expr(&n.arg) }),
+            ),
+            Stmt::Try(n) => self.node(
+                "TryStatement",
+                n.span,
+                json!({
+                    "block": self.block(&n.block),
+                    "handler": n.handler.as_ref().map(|handler| self.node(
+                        "CatchClause",
+                        handler.span,
+                        json!({
+                            "param": handler.param.as_ref().map(|p| self.pat(p)),
+                            "body": self.block(&handler.body),
+                        }),
+                    )),
+                    "finalizer": n.finalizer.as_ref().map(|f| self.block(f)),
+                }),
+            ),
+            Stmt::While(n) => self.node(
+                "WhileStatement",
+                n.span,
+                json!({ "test": self.expr(&n.test), "body": self.stmt(&n.body) }),
+            ),
+            Stmt::DoWhile(n) => self.node(
+                "DoWhileStatement",
+                n.span,
+                json!({ "body": self.stmt(&n.body), "test": self.expr(&n.test) }),
+            ),
+            Stmt::For(n) => self.node(
+                "ForStatement",
+                n.span,
+                json!({
+                    "init": n.init.as_ref().map(|init| match init {
+                        VarDeclOrExpr::VarDecl(decl) => self.var_decl(decl),
+                        VarDeclOrExpr::Expr(expr) => self.expr(expr),
+                    }),
+                    "test": self.opt_expr(n.test.as_deref()),
+                    "update": self.opt_expr(n.update.as_deref()),
+                    "body": self.stmt(&n.body),
+                }),
+            ),
+            Stmt::ForIn(n) => self.node(
+                "ForInStatement",
+                n.span,
+                json!({
+                    "left": self.for_head(&n.left),
+                    "right": self.expr(&n.right),
+                    "body": self.stmt(&n.body),
+                }),
+            ),
+            Stmt::ForOf(n) => self.node(
+                "ForOfStatement",
+                n.span,
+                json!({
+                    "await": n.is_await,
+                    "left": self.for_head(&n.left),
+                    "right": self.expr(&n.right),
+                    "body": self.stmt(&n.body),
+                }),
+            ),
+            Stmt::Decl(decl) => self.decl(decl),
+            Stmt::Expr(n) => self.node(
+                "ExpressionStatement",
+                n.span,
+                json!({ "expression": self.expr(&n.expr) }),
+            ),
+        }
+    }
+
+    fn for_head(&self, head: &ForHead) -> Value {
+        match head {
+            ForHead::VarDecl(decl) => self.var_decl(decl),
+            ForHead::UsingDecl(decl) => self.using_decl(decl),
+            ForHead::Pat(pat) => self.pat(pat),
+        }
+    }
+
+    fn decl(&self, decl: &Decl) -> Value {
+        match decl {
+            Decl::Class(n) => self.class("ClassDeclaration", Some(&n.ident), &n.class, n.declare),
+            Decl::Fn(n) => self.function(
+                "FunctionDeclaration",
+                n.function.span,
+                Some(&n.ident),
+                &n.function,
+                n.declare,
+            ),
+            Decl::Var(n) => self.var_decl(n),
+            Decl::Using(n) => self.using_decl(n),
+            Decl::TsInterface(n) => self.ts_interface(n),
+            Decl::TsTypeAlias(n) => {
+                let mut alias = self.node(
+                    "TSTypeAliasDeclaration",
+                    n.span,
+                    json!({
+                        "id": self.ident(&n.id),
+                        "typeAnnotation": self.ts_type(&n.type_ann),
+                        "declare": n.declare,
+                    }),
+                );
+                set_opt(
+                    &mut alias,
+                    "typeParameters",
+                    n.type_params.as_ref().map(|p| self.ts_type_params(p)),
+                );
+                alias
+            }
+            Decl::TsEnum(n) => self.node(
+                "TSEnumDeclaration",
+                n.span,
+                json!({
+                    "id": self.ident(&n.id),
+                    "const": n.is_const,
+                    "declare": n.declare,
+                    "members": n.members.iter().map(|member| self.node(
+                        "TSEnumMember",
+                        member.span,
+                        json!({
+                            "id": match &member.id {
+                                TsEnumMemberId::Ident(ident) => self.ident(ident),
+                                TsEnumMemberId::Str(s) => self.str(s),
+                            },
+                            "initializer": self.opt_expr(member.init.as_deref()),
+                        }),
+                    )).collect::<Vec<_>>(),
+                }),
+            ),
+            Decl::TsModule(n) => self.ts_module(n),
+        }
+    }
+
+    fn ts_module(&self, n: &TsModuleDecl) -> Value {
+        let mut id = match &n.id {
+            TsModuleName::Ident(ident) => self.ident(ident),
+            TsModuleName::Str(s) => self.str(s),
+        };
+        // swc nests `namespace a.b.c {}` as one namespace per name, ESTree
+        // has a single declaration with a qualified name
+        let mut body = n.body.as_ref();
+        while let Some(TsNamespaceBody::TsNamespaceDecl(inner)) = body {
+            id = self.node(
+                "TSQualifiedName",
+                Span::new(n.id.span().lo, inner.id.span.hi),
+                json!({ "left": id, "right": self.ident(&inner.id) }),
+            );
+            body = Some(&*inner.body);
+        }
+        let kind = if n.global {
+            "global"
+        } else if n.namespace {
+            "namespace"
+        } else {
+            "module"
+        };
+        let mut node = self.node(
+            "TSModuleDeclaration",
+            n.span,
+            json!({
+                "id": id,
+                "kind": kind,
+                "declare": n.declare,
+                "global": n.global,
+            }),
+        );
+        if let Some(TsNamespaceBody::TsModuleBlock(block)) = body {
+            node["body"] = self.node(
+                "TSModuleBlock",
+                block.span,
+                json!({ "body": self.module_items(&block.body) }),
+            );
+        }
+        node
+    }
+
+    fn var_decl(&self, decl: &VarDecl) -> Value {
+        self.node(
+            "VariableDeclaration",
+            decl.span,
+            json!({
+                "kind": decl.kind.as_str(),
+                "declare": decl.declare,
+                "declarations": self.declarators(&decl.decls),
+            }),
+        )
+    }
+
+    fn using_decl(&self, decl: &UsingDecl) -> Value {
+        self.node(
+            "VariableDeclaration",
+            decl.span,
+            json!({
+                "kind": if decl.is_await { "await using" } else { "using" },
+                "declarations": self.declarators(&decl.decls),
+            }),
+        )
+    }
+
+    fn declarators(&self, decls: &[VarDeclarator]) -> Value {
+        decls
+            .iter()
+            .map(|d| {
+                self.node(
+                    "VariableDeclarator",
+                    d.span,
+                    json!({
+                        "id": self.pat(&d.name),
+                        "init": self.opt_expr(d.init.as_deref()),
+                        "definite": d.definite,
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    // --- modules ---
+
+    fn module_decl(&self, decl: &ModuleDecl) -> Value {
+        match decl {
+            ModuleDecl::Import(n) => self.node(
+                "ImportDeclaration",
+                n.span,
+                json!({
+                    "importKind": if n.type_only { "type" } else { "value" },
+                    "specifiers": n.specifiers.iter().map(|s| self.import_specifier(s)).collect::<Vec<_>>(),
+                    "source": self.str(&n.src),
+                    "attributes": self.import_attributes(n.with.as_deref()),
+                }),
+            ),
+            ModuleDecl::ExportDecl(n) => self.node(
+                "ExportNamedDeclaration",
+                n.span,
+                json!({
+                    "declaration": self.decl(&n.decl),
+                    "specifiers": [],
+                    "source": null,
+                    "attributes": [],
+                }),
+            ),
+            ModuleDecl::ExportNamed(n) => match n.specifiers.as_slice() {
+                [ExportSpecifier::Namespace(ns)] => self.node(
+                    "ExportAllDeclaration",
+                    n.span,
+                    json!({
+                        "exported": self.module_export_name(&ns.name),
+                        "source": n.src.as_ref().map(|s| self.str(s)),
+                        "attributes": self.import_attributes(n.with.as_deref()),
+                    }),
+                ),
+                specifiers => self.node(
+                    "ExportNamedDeclaration",
+                    n.span,
+                    json!({
+                        "exportKind": if n.type_only { "type" } else { "value" },
+                        "declaration": null,
+                        "specifiers": specifiers.iter().map(|s| self.export_specifier(s)).collect::<Vec<_>>(),
+                        "source": n.src.as_ref().map(|s| self.str(s)),
+                        "attributes": self.import_attributes(n.with.as_deref()),
+                    }),
+                ),
+            },
+            ModuleDecl::ExportDefaultDecl(n) => self.node(
+                "ExportDefaultDeclaration",
+                n.span,
+                json!({
+                    "declaration": match &n.decl {
+                        DefaultDecl::Class(c) => self.class(
+                            "ClassDeclaration",
+                            c.ident.as_ref(),
+                            &c.class,
+                            false,
+                        ),
+                        DefaultDecl::Fn(f) => self.function(
+                            "FunctionDeclaration",
+                            f.function.span,
+                            f.ident.as_ref(),
+                            &f.function,
+                            false,
+                        ),
+                        DefaultDecl::TsInterfaceDecl(i) => self.ts_interface(i),
+                    },
+                }),
+            ),
+            ModuleDecl::ExportDefaultExpr(n) => self.node(
+                "ExportDefaultDeclaration",
+                n.span,
+                json!({ "declaration": self.expr(&n.expr) }),
+            ),
+            ModuleDecl::ExportAll(n) => self.node(
+                "ExportAllDeclaration",
+                n.span,
+                json!({
+                    "exportKind": if n.type_only { "type" } else { "value" },
+                    "exported": null,
+                    "source": self.str(&n.src),
+                    "attributes": self.import_attributes(n.with.as_deref()),
+                }),
+            ),
+            ModuleDecl::TsExportAssignment(n) => self.node(
+                "TSExportAssignment",
+                n.span,
+                json!({ "expression": self.expr(&n.expr) }),
+            ),
+            other => self.unsupported(other.span()),
+        }
+    }
+
+    fn import_specifier(&self, specifier: &ImportSpecifier) -> Value {
+        match specifier {
+            ImportSpecifier::Named(n) => self.node(
+                "ImportSpecifier",
+                n.span,
+                json!({
+                    "importKind": if n.is_type_only { "type" } else { "value" },
+                    "imported": match &n.imported {
+                        Some(name) => self.module_export_name(name),
+                        None => self.ident(&n.local),
+                    },
+                    "local": self.ident(&n.local),
+                }),
+            ),
+            ImportSpecifier::Default(n) => self.node(
+                "ImportDefaultSpecifier",
+                n.span,
+                json!({ "local": self.ident(&n.local) }),
+            ),
+            ImportSpecifier::Namespace(n) => self.node(
+                "ImportNamespaceSpecifier",
+                n.span,
+                json!({ "local": self.ident(&n.local) }),
+            ),
+        }
+    }
+
+    fn export_specifier(&self, specifier: &ExportSpecifier) -> Value {
+        match specifier {
+            ExportSpecifier::Named(n) => self.node(
+                "ExportSpecifier",
+                n.span,
+                json!({
+                    "exportKind": if n.is_type_only { "type" } else { "value" },
+                    "local": self.module_export_name(&n.orig),
+                    "exported": self.module_export_name(n.exported.as_ref().unwrap_or(&n.orig)),
+                }),
+            ),
+            // `export * as ns, { a } from "mod"` isn't valid JS, so these only
+            // appear in a list of one, handled by the caller
+            ExportSpecifier::Namespace(n) => self.unsupported(n.span),
+            ExportSpecifier::Default(n) => self.unsupported(n.exported.span),
+        }
+    }
+
+    fn module_export_name(&self, name: &ModuleExportName) -> Value {
+        match name {
+            ModuleExportName::Ident(ident) => self.ident(ident),
+            ModuleExportName::Str(s) => self.str(s),
+        }
+    }
+
+    fn import_attributes(&self, with: Option<&ObjectLit>) -> Value {
+        let Some(with) = with else {
+            return json!([]);
+        };
+        with.props
+            .iter()
+            .map(|prop| match prop {
+                PropOrSpread::Prop(box Prop::KeyValue(kv)) => {
+                    let (key, _, key_span) = self.prop_name(&kv.key);
+                    self.node(
+                        "ImportAttribute",
+                        key_span.with_hi(kv.value.span().hi),
+                        json!({ "key": key, "value": self.expr(&kv.value) }),
+                    )
+                }
+                other => self.unsupported(other.span()),
+            })
+            .collect()
+    }
+
+    // --- functions and classes ---
+
+    fn function(
+        &self,
+        kind: &str,
+        span: Span,
+        id: Option<&Ident>,
+        function: &Function,
+        declare: bool,
+    ) -> Value {
+        let mut node = self.node(
+            kind,
+            span,
+            json!({
+                "id": id.map(|id| self.ident(id)),
+                "params": function.params.iter().map(|p| self.pat(&p.pat)).collect::<Vec<_>>(),
+                "body": function.body.as_ref().map(|body| self.block(body)),
+                "generator": function.is_generator,
+                "async": function.is_async,
+                "expression": false,
+            }),
+        );
+        if declare {
+            node["declare"] = true.into();
+        }
+        self.set_signature(
+            &mut node,
+            function.type_params.as_deref(),
+            function.return_type.as_deref(),
+        );
+        node
+    }
+
+    fn set_signature(
+        &self,
+        node: &mut Value,
+        type_params: Option<&TsTypeParamDecl>,
+        return_type: Option<&TsTypeAnn>,
+    ) {
+        set_opt(
+            node,
+            "typeParameters",
+            type_params.map(|p| self.ts_type_params(p)),
+        );
+        set_opt(node, "returnType", return_type.map(|t| self.ts_type_ann(t)));
+    }
+
+    // A method's function, which in ESTree starts at the parameter list.
+    fn method_value(&self, key_span: Span, function: &Function) -> Value {
+        let start = match &function.type_params {
+            Some(params) => params.span.lo,
+            None => self.find(key_span.hi, '('),
+        };
+        self.function(
+            "FunctionExpression",
+            function.span.with_lo(start),
+            None,
+            function,
+            false,
+        )
+    }
+
+    fn class(&self, kind: &str, id: Option<&Ident>, class: &Class, declare: bool) -> Value {
+        // the body starts at the first `{` after the heritage clauses
+        let heritage_end = [
+            id.map(|id| id.span.hi),
+            class.type_params.as_ref().map(|p| p.span.hi),
+            class.super_class.as_ref().map(|s| s.span().hi),
+            class.super_type_params.as_ref().map(|p| p.span.hi),
+            class.implements.last().map(|i| i.span.hi),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or(class.span.lo);
+        let body_span = class.span.with_lo(self.find(heritage_end, '{'));
+
+        let mut node = self.node(
+            kind,
+            class.span,
+            json!({
+                "id": id.map(|id| self.ident(id)),
+                "superClass": self.opt_expr(class.super_class.as_deref()),
+                "body": self.node(
+                    "ClassBody",
+                    body_span,
+                    json!({
+                        "body": class.body.iter().filter_map(|m| self.class_member(m)).collect::<Vec<_>>(),
+                    }),
+                ),
+                "decorators": self.decorators(&class.decorators),
+                "implements": class.implements.iter().map(|i| self.ts_heritage("TSClassImplements", i)).collect::<Vec<_>>(),
+                "abstract": class.is_abstract,
+            }),
+        );
+        if declare {
+            node["declare"] = true.into();
+        }
+        set_opt(
+            &mut node,
+            "typeParameters",
+            class.type_params.as_ref().map(|p| self.ts_type_params(p)),
+        );
+        set_opt(
+            &mut node,
+            "superTypeArguments",
+            class
+                .super_type_params
+                .as_ref()
+                .map(|p| self.ts_type_args(p)),
+        );
+        node
+    }
+
+    fn decorators(&self, decorators: &[Decorator]) -> Value {
+        decorators
+            .iter()
+            .map(|d| {
+                self.node(
+                    "Decorator",
+                    d.span,
+                    json!({ "expression": self.expr(&d.expr) }),
+                )
+            })
+            .collect()
+    }
+
+    fn class_member(&self, member: &ClassMember) -> Option<Value> {
+        Some(match member {
+            ClassMember::Constructor(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                let params: Vec<_> = n
+                    .params
+                    .iter()
+                    .map(|param| match param {
+                        ParamOrTsParamProp::Param(p) => self.pat(&p.pat),
+                        ParamOrTsParamProp::TsParamProp(p) => self.node(
+                            "TSParameterProperty",
+                            p.span,
+                            json!({
+                                "accessibility": p.accessibility.map(accessibility),
+                                "readonly": p.readonly,
+                                "override": p.is_override,
+                                "static": false,
+                                "decorators": self.decorators(&p.decorators),
+                                "parameter": match &p.param {
+                                    TsParamPropParam::Ident(ident) => self.binding_ident(ident),
+                                    TsParamPropParam::Assign(assign) => self.assign_pat(assign),
+                                },
+                            }),
+                        ),
+                    })
+                    .collect();
+                let value_start = self.find(key_span.hi, '(');
+                let value = self.node(
+                    "FunctionExpression",
+                    n.span.with_lo(value_start),
+                    json!({
+                        "id": null,
+                        "params": params,
+                        "body": n.body.as_ref().map(|body| self.block(body)),
+                        "generator": false,
+                        "async": false,
+                        "expression": false,
+                    }),
+                );
+                let mut node = self.node(
+                    "MethodDefinition",
+                    n.span,
+                    json!({
+                        "kind": "constructor",
+                        "key": key,
+                        "computed": computed,
+                        "static": false,
+                        "value": value,
+                    }),
+                );
+                set_opt(
+                    &mut node,
+                    "accessibility",
+                    n.accessibility.map(accessibility),
+                );
+                node
+            }
+            ClassMember::Method(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                self.method_definition(
+                    n.span,
+                    key,
+                    key_span,
+                    computed,
+                    n.kind,
+                    n.is_static,
+                    n.accessibility,
+                    &n.function,
+                )
+            }
+            ClassMember::PrivateMethod(n) => self.method_definition(
+                n.span,
+                self.private_name(&n.key),
+                n.key.span,
+                false,
+                n.kind,
+                n.is_static,
+                n.accessibility,
+                &n.function,
+            ),
+            ClassMember::ClassProp(n) => {
+                let (key, computed, _) = self.prop_name(&n.key);
+                self.property_definition(
+                    "PropertyDefinition",
+                    n.span,
+                    key,
+                    computed,
+                    n.value.as_deref(),
+                    n.type_ann.as_deref(),
+                    n.is_static,
+                    &n.decorators,
+                    n.accessibility,
+                )
+            }
+            ClassMember::PrivateProp(n) => self.property_definition(
+                "PropertyDefinition",
+                n.span,
+                self.private_name(&n.key),
+                false,
+                n.value.as_deref(),
+                n.type_ann.as_deref(),
+                n.is_static,
+                &n.decorators,
+                n.accessibility,
+            ),
+            ClassMember::AutoAccessor(n) => {
+                let (key, computed) = match &n.key {
+                    Key::Private(name) => (self.private_name(name), false),
+                    Key::Public(name) => {
+                        let (key, computed, _) = self.prop_name(name);
+                        (key, computed)
+                    }
+                };
+                self.property_definition(
+                    "AccessorProperty",
+                    n.span,
+                    key,
+                    computed,
+                    n.value.as_deref(),
+                    n.type_ann.as_deref(),
+                    n.is_static,
+                    &n.decorators,
+                    n.accessibility,
+                )
+            }
+            ClassMember::StaticBlock(n) => self.node(
+                "StaticBlock",
+                n.span,
+                json!({ "body": self.statements(n.body.stmts.iter().map(Item::Stmt)) }),
+            ),
+            ClassMember::ContentTagMember(n) => self.glimmer_template(n.span, &n.contents.value),
+            ClassMember::Empty(_) => return None,
+            other => self.unsupported(other.span()),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn method_definition(
+        &self,
+        span: Span,
+        key: Value,
+        key_span: Span,
+        computed: bool,
+        kind: MethodKind,
+        is_static: bool,
+        access: Option<Accessibility>,
+        function: &Function,
+    ) -> Value {
+        let mut node = self.node(
+            "MethodDefinition",
+            span,
+            json!({
+                "kind": match kind {
+                    MethodKind::Method => "method",
+                    MethodKind::Getter => "get",
+                    MethodKind::Setter => "set",
+                },
+                "key": key,
+                "computed": computed,
+                "static": is_static,
+                "value": self.method_value(key_span, function),
+            }),
+        );
+        set_opt(&mut node, "accessibility", access.map(accessibility));
+        node
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn property_definition(
+        &self,
+        kind: &str,
+        span: Span,
+        key: Value,
+        computed: bool,
+        value: Option<&Expr>,
+        type_ann: Option<&TsTypeAnn>,
+        is_static: bool,
+        decorators: &[Decorator],
+        access: Option<Accessibility>,
+    ) -> Value {
+        let mut node = self.node(
+            kind,
+            span,
+            json!({
+                "key": key,
+                "computed": computed,
+                "value": self.opt_expr(value),
+                "static": is_static,
+                "decorators": self.decorators(decorators),
+            }),
+        );
+        set_opt(
+            &mut node,
+            "typeAnnotation",
+            type_ann.map(|t| self.ts_type_ann(t)),
+        );
+        set_opt(&mut node, "accessibility", access.map(accessibility));
+        node
+    }
+
+    fn glimmer_template(&self, span: Span, contents: &str) -> Value {
+        self.node("GlimmerTemplate", span, json!({ "contents": contents }))
+    }
+
+    // --- expressions ---
+
+    fn opt_expr(&self, expr: Option<&Expr>) -> Value {
+        expr.map_or(Value::Null, |e| self.expr(e))
+    }
+
+    fn exprs_or_spreads(&self, args: &[ExprOrSpread]) -> Value {
+        args.iter().map(|arg| self.expr_or_spread(arg)).collect()
+    }
+
+    fn expr_or_spread(&self, arg: &ExprOrSpread) -> Value {
+        match arg.spread {
+            Some(dots) => self.node(
+                "SpreadElement",
+                dots.with_hi(arg.expr.span().hi),
+                json!({ "argument": self.expr(&arg.expr) }),
+            ),
+            None => self.expr(&arg.expr),
+        }
+    }
+
+    fn expr(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::This(n) => self.node("ThisExpression", n.span, json!({})),
+            Expr::Array(n) => self.node(
+                "ArrayExpression",
+                n.span,
+                json!({
+                    "elements": n.elems.iter().map(|e| e.as_ref().map(|e| self.expr_or_spread(e))).collect::<Vec<_>>(),
+                }),
+            ),
+            Expr::Object(n) => self.node(
+                "ObjectExpression",
+                n.span,
+                json!({
+                    "properties": n.props.iter().map(|p| self.prop_or_spread(p)).collect::<Vec<_>>(),
+                }),
+            ),
+            Expr::Fn(n) => self.function(
+                "FunctionExpression",
+                n.function.span,
+                n.ident.as_ref(),
+                &n.function,
+                false,
+            ),
+            Expr::Unary(n) => self.node(
+                "UnaryExpression",
+                n.span,
+                json!({
+                    "operator": n.op.as_str(),
+                    "prefix": true,
+                    "argument": self.expr(&n.arg),
+                }),
+            ),
+            Expr::Update(n) => self.node(
+                "UpdateExpression",
+                n.span,
+                json!({
+                    "operator": n.op.as_str(),
+                    "prefix": n.prefix,
+                    "argument": self.expr(&n.arg),
+                }),
+            ),
+            Expr::Bin(n) => self.node(
+                match n.op {
+                    BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
+                        "LogicalExpression"
+                    }
+                    _ => "BinaryExpression",
+                },
+                n.span,
+                json!({
+                    "operator": n.op.as_str(),
+                    "left": self.expr(&n.left),
+                    "right": self.expr(&n.right),
+                }),
+            ),
+            Expr::Assign(n) => self.node(
+                "AssignmentExpression",
+                n.span,
+                json!({
+                    "operator": n.op.as_str(),
+                    "left": self.assign_target(&n.left),
+                    "right": self.expr(&n.right),
+                }),
+            ),
+            Expr::Member(n) => self.member(n, false),
+            Expr::SuperProp(n) => self.super_prop(n),
+            Expr::Cond(n) => self.node(
+                "ConditionalExpression",
+                n.span,
+                json!({
+                    "test": self.expr(&n.test),
+                    "consequent": self.expr(&n.cons),
+                    "alternate": self.expr(&n.alt),
+                }),
+            ),
+            Expr::Call(n) => self.call(n),
+            Expr::New(n) => {
+                let mut node = self.node(
+                    "NewExpression",
+                    n.span,
+                    json!({
+                        "callee": self.expr(&n.callee),
+                        "arguments": n.args.as_deref().map_or(json!([]), |args| self.exprs_or_spreads(args)),
+                    }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeArguments",
+                    n.type_args.as_ref().map(|t| self.ts_type_args(t)),
+                );
+                node
+            }
+            Expr::Seq(n) => self.node(
+                "SequenceExpression",
+                n.span,
+                json!({
+                    "expressions": n.exprs.iter().map(|e| self.expr(e)).collect::<Vec<_>>(),
+                }),
+            ),
+            Expr::Ident(n) => self.ident(n),
+            Expr::Lit(n) => self.lit(n),
+            Expr::Tpl(n) => self.tpl(n),
+            Expr::TaggedTpl(n) => {
+                let mut node = self.node(
+                    "TaggedTemplateExpression",
+                    n.span,
+                    json!({ "tag": self.expr(&n.tag), "quasi": self.tpl(&n.tpl) }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeArguments",
+                    n.type_params.as_ref().map(|t| self.ts_type_args(t)),
+                );
+                node
+            }
+            Expr::Arrow(n) => {
+                let mut node = self.node(
+                    "ArrowFunctionExpression",
+                    n.span,
+                    json!({
+                        "id": null,
+                        "params": n.params.iter().map(|p| self.pat(p)).collect::<Vec<_>>(),
+                        "body": match &*n.body {
+                            BlockStmtOrExpr::BlockStmt(block) => self.block(block),
+                            BlockStmtOrExpr::Expr(expr) => self.expr(expr),
+                        },
+                        "async": n.is_async,
+                        "generator": false,
+                        "expression": matches!(&*n.body, BlockStmtOrExpr::Expr(_)),
+                    }),
+                );
+                self.set_signature(
+                    &mut node,
+                    n.type_params.as_deref(),
+                    n.return_type.as_deref(),
+                );
+                node
+            }
+            Expr::Class(n) => self.class("ClassExpression", n.ident.as_ref(), &n.class, false),
+            Expr::Yield(n) => self.node(
+                "YieldExpression",
+                n.span,
+                json!({ "argument": self.opt_expr(n.arg.as_deref()), "delegate": n.delegate }),
+            ),
+            Expr::MetaProp(n) => {
+                let (meta, property) = match n.kind {
+                    MetaPropKind::NewTarget => ("new", "target"),
+                    MetaPropKind::ImportMeta => ("import", "meta"),
+                };
+                let meta_span = n.span.with_hi(n.span.lo + BytePos(meta.len() as u32));
+                let property_span = n.span.with_lo(n.span.hi - BytePos(property.len() as u32));
+                self.node(
+                    "MetaProperty",
+                    n.span,
+                    json!({
+                        "meta": self.node("Identifier", meta_span, json!({ "name": meta })),
+                        "property": self.node("Identifier", property_span, json!({ "name": property })),
+                    }),
+                )
+            }
+            Expr::Await(n) => self.node(
+                "AwaitExpression",
+                n.span,
+                json!({ "argument": self.expr(&n.arg) }),
+            ),
+            // ESTree has no node for parentheses
+            Expr::Paren(n) => self.expr(&n.expr),
+            Expr::TsTypeAssertion(n) => self.node(
+                "TSTypeAssertion",
+                n.span,
+                json!({
+                    "typeAnnotation": self.ts_type(&n.type_ann),
+                    "expression": self.expr(&n.expr),
+                }),
+            ),
+            Expr::TsConstAssertion(n) => {
+                let const_span = n.span.with_lo(n.span.hi - BytePos(5));
+                self.node(
+                    "TSAsExpression",
+                    n.span,
+                    json!({
+                        "expression": self.expr(&n.expr),
+                        "typeAnnotation": self.node(
+                            "TSTypeReference",
+                            const_span,
+                            json!({
+                                "typeName": self.node("Identifier", const_span, json!({ "name": "const" })),
+                            }),
+                        ),
+                    }),
+                )
+            }
+            Expr::TsNonNull(n) => self.node(
+                "TSNonNullExpression",
+                n.span,
+                json!({ "expression": self.expr(&n.expr) }),
+            ),
+            Expr::TsAs(n) => self.node(
+                "TSAsExpression",
+                n.span,
+                json!({
+                    "expression": self.expr(&n.expr),
+                    "typeAnnotation": self.ts_type(&n.type_ann),
+                }),
+            ),
+            Expr::TsSatisfies(n) => self.node(
+                "TSSatisfiesExpression",
+                n.span,
+                json!({
+                    "expression": self.expr(&n.expr),
+                    "typeAnnotation": self.ts_type(&n.type_ann),
+                }),
+            ),
+            Expr::TsInstantiation(n) => self.node(
+                "TSInstantiationExpression",
+                n.span,
+                json!({
+                    "expression": self.expr(&n.expr),
+                    "typeArguments": self.ts_type_args(&n.type_args),
+                }),
+            ),
+            Expr::PrivateName(n) => self.private_name(n),
+            Expr::OptChain(n) => self.node(
+                "ChainExpression",
+                n.span,
+                json!({ "expression": self.chain_element(n) }),
+            ),
+            Expr::ContentTagExpression(n) => self.glimmer_template(n.span, &n.contents.value),
+            other => self.unsupported(other.span()),
+        }
+    }
+
+    // An expression inside an optional chain, where nested chains are part of
+    // the same `ChainExpression`.
+    fn chain_expr(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::OptChain(n) => self.chain_element(n),
+            _ => self.expr(expr),
+        }
+    }
+
+    fn chain_element(&self, chain: &OptChainExpr) -> Value {
+        match &*chain.base {
+            OptChainBase::Member(member) => self.member(member, chain.optional),
+            OptChainBase::Call(call) => {
+                let mut node = self.node(
+                    "CallExpression",
+                    call.span,
+                    json!({
+                        "callee": self.chain_expr(&call.callee),
+                        "arguments": self.exprs_or_spreads(&call.args),
+                        "optional": chain.optional,
+                    }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeArguments",
+                    call.type_args.as_ref().map(|t| self.ts_type_args(t)),
+                );
+                node
+            }
+        }
+    }
+
+    fn member(&self, member: &MemberExpr, optional: bool) -> Value {
+        let (property, computed) = match &member.prop {
+            MemberProp::Ident(name) => (self.ident_name(name), false),
+            MemberProp::PrivateName(name) => (self.private_name(name), false),
+            MemberProp::Computed(c) => (self.expr(&c.expr), true),
+        };
+        self.node(
+            "MemberExpression",
+            member.span,
+            json!({
+                "object": self.chain_expr(&member.obj),
+                "property": property,
+                "computed": computed,
+                "optional": optional,
+            }),
+        )
+    }
+
+    fn super_prop(&self, n: &SuperPropExpr) -> Value {
+        let (property, computed) = match &n.prop {
+            SuperProp::Ident(name) => (self.ident_name(name), false),
+            SuperProp::Computed(c) => (self.expr(&c.expr), true),
+        };
+        self.node(
+            "MemberExpression",
+            n.span,
+            json!({
+                "object": self.node("Super", n.obj.span, json!({})),
+                "property": property,
+                "computed": computed,
+                "optional": false,
+            }),
+        )
+    }
+
+    fn call(&self, call: &CallExpr) -> Value {
+        let callee = match &call.callee {
+            Callee::Import(_) => {
+                return self.node(
+                    "ImportExpression",
+                    call.span,
+                    json!({
+                        "source": call.args.first().map(|a| self.expr(&a.expr)),
+                        "options": call.args.get(1).map(|a| self.expr(&a.expr)),
+                    }),
+                );
+            }
+            Callee::Super(s) => self.node("Super", s.span, json!({})),
+            Callee::Expr(e) => self.expr(e),
+        };
+        let mut node = self.node(
+            "CallExpression",
+            call.span,
+            json!({
+                "callee": callee,
+                "arguments": self.exprs_or_spreads(&call.args),
+                "optional": false,
+            }),
+        );
+        set_opt(
+            &mut node,
+            "typeArguments",
+            call.type_args.as_ref().map(|t| self.ts_type_args(t)),
+        );
+        node
+    }
+
+    fn prop_or_spread(&self, prop: &PropOrSpread) -> Value {
+        match prop {
+            PropOrSpread::Spread(s) => self.node(
+                "SpreadElement",
+                s.dot3_token.with_hi(s.expr.span().hi),
+                json!({ "argument": self.expr(&s.expr) }),
+            ),
+            PropOrSpread::Prop(prop) => self.prop(prop),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn property(
+        &self,
+        span: Span,
+        key: Value,
+        value: Value,
+        kind: &str,
+        computed: bool,
+        method: bool,
+        shorthand: bool,
+    ) -> Value {
+        self.node(
+            "Property",
+            span,
+            json!({
+                "key": key,
+                "value": value,
+                "kind": kind,
+                "computed": computed,
+                "method": method,
+                "shorthand": shorthand,
+            }),
+        )
+    }
+
+    fn prop(&self, prop: &Prop) -> Value {
+        match prop {
+            Prop::Shorthand(ident) => self.property(
+                ident.span,
+                self.ident(ident),
+                self.ident(ident),
+                "init",
+                false,
+                false,
+                true,
+            ),
+            Prop::KeyValue(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                self.property(
+                    key_span.with_hi(n.value.span().hi),
+                    key,
+                    self.expr(&n.value),
+                    "init",
+                    computed,
+                    false,
+                    false,
+                )
+            }
+            // only valid as an assignment target, like `({ a = 1 } = b)`
+            Prop::Assign(n) => self.property(
+                n.span,
+                self.ident(&n.key),
+                self.node(
+                    "AssignmentPattern",
+                    n.span,
+                    json!({ "left": self.ident(&n.key), "right": self.expr(&n.value) }),
+                ),
+                "init",
+                false,
+                false,
+                true,
+            ),
+            Prop::Getter(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                let mut value = self.node(
+                    "FunctionExpression",
+                    n.span.with_lo(self.find(key_span.hi, '(')),
+                    json!({
+                        "id": null,
+                        "params": [],
+                        "body": n.body.as_ref().map(|b| self.block(b)),
+                        "generator": false,
+                        "async": false,
+                        "expression": false,
+                    }),
+                );
+                set_opt(
+                    &mut value,
+                    "returnType",
+                    n.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+                );
+                self.property(n.span, key, value, "get", computed, false, false)
+            }
+            Prop::Setter(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                let value = self.node(
+                    "FunctionExpression",
+                    n.span.with_lo(self.find(key_span.hi, '(')),
+                    json!({
+                        "id": null,
+                        "params": [self.pat(&n.param)],
+                        "body": n.body.as_ref().map(|b| self.block(b)),
+                        "generator": false,
+                        "async": false,
+                        "expression": false,
+                    }),
+                );
+                self.property(n.span, key, value, "set", computed, false, false)
+            }
+            Prop::Method(n) => {
+                let (key, computed, key_span) = self.prop_name(&n.key);
+                self.property(
+                    n.function.span.with_lo(key_span.lo.min(n.function.span.lo)),
+                    key,
+                    self.method_value(key_span, &n.function),
+                    "init",
+                    computed,
+                    true,
+                    false,
+                )
+            }
+        }
+    }
+
+    // The key, whether it is computed, and the span it starts the property
+    // with (including the brackets of a computed key).
+    fn prop_name(&self, name: &PropName) -> (Value, bool, Span) {
+        match name {
+            PropName::Ident(n) => (self.ident_name(n), false, n.span),
+            PropName::Str(n) => (self.str(n), false, n.span),
+            PropName::Num(n) => (self.lit(&Lit::Num(n.clone())), false, n.span),
+            PropName::BigInt(n) => (self.lit(&Lit::BigInt(n.clone())), false, n.span),
+            PropName::Computed(n) => (self.expr(&n.expr), true, n.span),
+        }
+    }
+
+    fn lit(&self, lit: &Lit) -> Value {
+        match lit {
+            Lit::Str(n) => self.str(n),
+            Lit::Bool(n) => self.node(
+                "Literal",
+                n.span,
+                json!({ "value": n.value, "raw": self.text(n.span) }),
+            ),
+            Lit::Null(n) => self.node("Literal", n.span, json!({ "value": null, "raw": "null" })),
+            Lit::Num(n) => self.node(
+                "Literal",
+                n.span,
+                json!({ "value": n.value, "raw": self.text(n.span) }),
+            ),
+            Lit::BigInt(n) => {
+                let raw = self.text(n.span);
+                self.node(
+                    "Literal",
+                    n.span,
+                    json!({
+                        "value": null,
+                        "raw": raw,
+                        "bigint": raw.trim_end_matches('n').replace('_', ""),
+                    }),
+                )
+            }
+            Lit::Regex(n) => self.node(
+                "Literal",
+                n.span,
+                json!({
+                    // a `RegExp` in JS, which JSON can't hold
+                    "value": null,
+                    "raw": self.text(n.span),
+                    "regex": { "pattern": &*n.exp, "flags": &*n.flags },
+                }),
+            ),
+            other => self.unsupported(other.span()),
+        }
+    }
+
+    fn str(&self, s: &Str) -> Value {
+        self.node(
+            "Literal",
+            s.span,
+            json!({ "value": s.value.to_string_lossy(), "raw": self.text(s.span) }),
+        )
+    }
+
+    fn tpl(&self, tpl: &Tpl) -> Value {
+        self.node(
+            "TemplateLiteral",
+            tpl.span,
+            json!({
+                "quasis": tpl.quasis.iter().map(|q| self.node(
+                    "TemplateElement",
+                    template_element_span(q),
+                    json!({
+                        "value": {
+                            "raw": &*q.raw,
+                            "cooked": q.cooked.as_ref().map(|c| c.to_string_lossy()),
+                        },
+                        "tail": q.tail,
+                    }),
+                )).collect::<Vec<_>>(),
+                "expressions": tpl.exprs.iter().map(|e| self.expr(e)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn ident(&self, ident: &Ident) -> Value {
+        let mut node = self.node("Identifier", ident.span, json!({ "name": &*ident.sym }));
+        if ident.optional {
+            node["optional"] = true.into();
+        }
+        node
+    }
+
+    fn ident_name(&self, ident: &IdentName) -> Value {
+        self.node("Identifier", ident.span, json!({ "name": &*ident.sym }))
+    }
+
+    fn private_name(&self, name: &PrivateName) -> Value {
+        self.node(
+            "PrivateIdentifier",
+            name.span,
+            json!({ "name": &*name.name }),
+        )
+    }
+
+    // --- patterns ---
+
+    fn binding_ident(&self, ident: &BindingIdent) -> Value {
+        let mut node = self.ident(&ident.id);
+        set_opt(
+            &mut node,
+            "typeAnnotation",
+            ident.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+        );
+        node
+    }
+
+    fn assign_pat(&self, n: &AssignPat) -> Value {
+        self.node(
+            "AssignmentPattern",
+            n.span,
+            json!({ "left": self.pat(&n.left), "right": self.expr(&n.right) }),
+        )
+    }
+
+    fn pat(&self, pat: &Pat) -> Value {
+        match pat {
+            Pat::Ident(n) => self.binding_ident(n),
+            Pat::Array(n) => self.array_pat(n),
+            Pat::Rest(n) => self.rest_pat(n),
+            Pat::Object(n) => self.object_pat(n),
+            Pat::Assign(n) => self.assign_pat(n),
+            Pat::Expr(expr) => self.expr(expr),
+            Pat::Invalid(n) => self.unsupported(n.span),
+        }
+    }
+
+    fn array_pat(&self, n: &ArrayPat) -> Value {
+        let mut node = self.node(
+            "ArrayPattern",
+            n.span,
+            json!({
+                "elements": n.elems.iter().map(|e| e.as_ref().map(|p| self.pat(p))).collect::<Vec<_>>(),
+            }),
+        );
+        set_opt(
+            &mut node,
+            "typeAnnotation",
+            n.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+        );
+        node
+    }
+
+    fn rest_pat(&self, n: &RestPat) -> Value {
+        let mut node = self.node(
+            "RestElement",
+            n.span,
+            json!({ "argument": self.pat(&n.arg) }),
+        );
+        set_opt(
+            &mut node,
+            "typeAnnotation",
+            n.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+        );
+        node
+    }
+
+    fn object_pat(&self, n: &ObjectPat) -> Value {
+        let properties: Vec<_> = n
+            .props
+            .iter()
+            .map(|prop| match prop {
+                ObjectPatProp::KeyValue(kv) => {
+                    let (key, computed, key_span) = self.prop_name(&kv.key);
+                    self.property(
+                        key_span.with_hi(kv.value.span().hi),
+                        key,
+                        self.pat(&kv.value),
+                        "init",
+                        computed,
+                        false,
+                        false,
+                    )
+                }
+                ObjectPatProp::Assign(a) => {
+                    let value = match &a.value {
+                        Some(default) => self.node(
+                            "AssignmentPattern",
+                            a.span,
+                            json!({ "left": self.ident(&a.key.id), "right": self.expr(default) }),
+                        ),
+                        None => self.ident(&a.key.id),
+                    };
+                    self.property(
+                        a.span,
+                        self.ident(&a.key.id),
+                        value,
+                        "init",
+                        false,
+                        false,
+                        true,
+                    )
+                }
+                ObjectPatProp::Rest(rest) => self.rest_pat(rest),
+            })
+            .collect();
+        let mut node = self.node("ObjectPattern", n.span, json!({ "properties": properties }));
+        set_opt(
+            &mut node,
+            "typeAnnotation",
+            n.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+        );
+        node
+    }
+
+    fn assign_target(&self, target: &AssignTarget) -> Value {
+        match target {
+            AssignTarget::Simple(simple) => match simple {
+                SimpleAssignTarget::Ident(n) => self.binding_ident(n),
+                SimpleAssignTarget::Member(n) => self.member(n, false),
+                SimpleAssignTarget::SuperProp(n) => self.super_prop(n),
+                SimpleAssignTarget::Paren(n) => self.expr(&n.expr),
+                SimpleAssignTarget::OptChain(n) => self.node(
+                    "ChainExpression",
+                    n.span,
+                    json!({ "expression": self.chain_element(n) }),
+                ),
+                SimpleAssignTarget::TsAs(n) => self.expr(&Expr::TsAs(n.clone())),
+                SimpleAssignTarget::TsSatisfies(n) => self.expr(&Expr::TsSatisfies(n.clone())),
+                SimpleAssignTarget::TsNonNull(n) => self.expr(&Expr::TsNonNull(n.clone())),
+                SimpleAssignTarget::TsTypeAssertion(n) => {
+                    self.expr(&Expr::TsTypeAssertion(n.clone()))
+                }
+                SimpleAssignTarget::TsInstantiation(n) => {
+                    self.expr(&Expr::TsInstantiation(n.clone()))
+                }
+                SimpleAssignTarget::Invalid(n) => self.unsupported(n.span),
+            },
+            AssignTarget::Pat(pat) => match pat {
+                AssignTargetPat::Array(n) => self.array_pat(n),
+                AssignTargetPat::Object(n) => self.object_pat(n),
+                AssignTargetPat::Invalid(n) => self.unsupported(n.span),
+            },
+        }
+    }
+
+    // --- TypeScript ---
+
+    fn ts_type_ann(&self, ann: &TsTypeAnn) -> Value {
+        self.node(
+            "TSTypeAnnotation",
+            ann.span,
+            json!({ "typeAnnotation": self.ts_type(&ann.type_ann) }),
+        )
+    }
+
+    fn ts_type_params(&self, params: &TsTypeParamDecl) -> Value {
+        self.node(
+            "TSTypeParameterDeclaration",
+            params.span,
+            json!({
+                "params": params.params.iter().map(|p| self.ts_type_param(p)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn ts_type_param(&self, param: &TsTypeParam) -> Value {
+        self.node(
+            "TSTypeParameter",
+            param.span,
+            json!({
+                "name": self.ident(&param.name),
+                "constraint": param.constraint.as_ref().map(|t| self.ts_type(t)),
+                "default": param.default.as_ref().map(|t| self.ts_type(t)),
+                "in": param.is_in,
+                "out": param.is_out,
+                "const": param.is_const,
+            }),
+        )
+    }
+
+    fn ts_type_args(&self, args: &TsTypeParamInstantiation) -> Value {
+        self.node(
+            "TSTypeParameterInstantiation",
+            args.span,
+            json!({ "params": args.params.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+        )
+    }
+
+    fn ts_heritage(&self, kind: &str, heritage: &TsExprWithTypeArgs) -> Value {
+        let mut node = self.node(
+            kind,
+            heritage.span,
+            json!({ "expression": self.expr(&heritage.expr) }),
+        );
+        set_opt(
+            &mut node,
+            "typeArguments",
+            heritage.type_args.as_ref().map(|t| self.ts_type_args(t)),
+        );
+        node
+    }
+
+    fn ts_interface(&self, n: &TsInterfaceDecl) -> Value {
+        let mut node = self.node(
+            "TSInterfaceDeclaration",
+            n.span,
+            json!({
+                "id": self.ident(&n.id),
+                "extends": n.extends.iter().map(|e| self.ts_heritage("TSInterfaceHeritage", e)).collect::<Vec<_>>(),
+                "body": self.node(
+                    "TSInterfaceBody",
+                    n.body.span,
+                    json!({ "body": n.body.body.iter().map(|m| self.ts_type_element(m)).collect::<Vec<_>>() }),
+                ),
+                "declare": n.declare,
+            }),
+        );
+        set_opt(
+            &mut node,
+            "typeParameters",
+            n.type_params.as_ref().map(|p| self.ts_type_params(p)),
+        );
+        node
+    }
+
+    fn ts_type_element(&self, element: &TsTypeElement) -> Value {
+        match element {
+            TsTypeElement::TsPropertySignature(n) => {
+                let mut node = self.node(
+                    "TSPropertySignature",
+                    n.span,
+                    json!({
+                        "key": self.expr(&n.key),
+                        "computed": n.computed,
+                        "optional": n.optional,
+                        "readonly": n.readonly,
+                        "static": false,
+                    }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeAnnotation",
+                    n.type_ann.as_ref().map(|t| self.ts_type_ann(t)),
+                );
+                node
+            }
+            TsTypeElement::TsMethodSignature(n) => {
+                let mut node = self.node(
+                    "TSMethodSignature",
+                    n.span,
+                    json!({
+                        "key": self.expr(&n.key),
+                        "computed": n.computed,
+                        "optional": n.optional,
+                        "kind": "method",
+                        "params": n.params.iter().map(|p| self.ts_fn_param(p)).collect::<Vec<_>>(),
+                    }),
+                );
+                self.set_signature(&mut node, n.type_params.as_deref(), n.type_ann.as_deref());
+                node
+            }
+            other => self.unsupported(other.span()),
+        }
+    }
+
+    fn ts_fn_param(&self, param: &TsFnParam) -> Value {
+        match param {
+            TsFnParam::Ident(n) => self.binding_ident(n),
+            TsFnParam::Array(n) => self.array_pat(n),
+            TsFnParam::Rest(n) => self.rest_pat(n),
+            TsFnParam::Object(n) => self.object_pat(n),
+        }
+    }
+
+    fn ts_entity_name(&self, name: &TsEntityName) -> Value {
+        match name {
+            TsEntityName::Ident(ident) => self.ident(ident),
+            TsEntityName::TsQualifiedName(q) => self.node(
+                "TSQualifiedName",
+                q.span,
+                json!({ "left": self.ts_entity_name(&q.left), "right": self.ident_name(&q.right) }),
+            ),
+        }
+    }
+
+    fn ts_type(&self, ty: &TsType) -> Value {
+        match ty {
+            TsType::TsKeywordType(n) => self.node(ts_keyword(n.kind), n.span, json!({})),
+            TsType::TsThisType(n) => self.node("TSThisType", n.span, json!({})),
+            TsType::TsTypeRef(n) => {
+                let mut node = self.node(
+                    "TSTypeReference",
+                    n.span,
+                    json!({ "typeName": self.ts_entity_name(&n.type_name) }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeArguments",
+                    n.type_params.as_ref().map(|t| self.ts_type_args(t)),
+                );
+                node
+            }
+            TsType::TsTypeLit(n) => self.node(
+                "TSTypeLiteral",
+                n.span,
+                json!({ "members": n.members.iter().map(|m| self.ts_type_element(m)).collect::<Vec<_>>() }),
+            ),
+            TsType::TsArrayType(n) => self.node(
+                "TSArrayType",
+                n.span,
+                json!({ "elementType": self.ts_type(&n.elem_type) }),
+            ),
+            TsType::TsTupleType(n) => self.node(
+                "TSTupleType",
+                n.span,
+                json!({
+                    "elementTypes": n.elem_types.iter().map(|e| match &e.label {
+                        Some(label) => self.node(
+                            "TSNamedTupleMember",
+                            e.span,
+                            json!({
+                                "label": self.pat(label),
+                                "elementType": self.ts_type(&e.ty),
+                                "optional": false,
+                            }),
+                        ),
+                        None => self.ts_type(&e.ty),
+                    }).collect::<Vec<_>>(),
+                }),
+            ),
+            TsType::TsOptionalType(n) => self.node(
+                "TSOptionalType",
+                n.span,
+                json!({ "typeAnnotation": self.ts_type(&n.type_ann) }),
+            ),
+            TsType::TsRestType(n) => self.node(
+                "TSRestType",
+                n.span,
+                json!({ "typeAnnotation": self.ts_type(&n.type_ann) }),
+            ),
+            TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsUnionType(n)) => self
+                .node(
+                    "TSUnionType",
+                    n.span,
+                    json!({ "types": n.types.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+                ),
+            TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsIntersectionType(
+                n,
+            )) => self.node(
+                "TSIntersectionType",
+                n.span,
+                json!({ "types": n.types.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+            ),
+            // like parentheses in expressions, these have no node
+            TsType::TsParenthesizedType(n) => self.ts_type(&n.type_ann),
+            TsType::TsTypeOperator(n) => self.node(
+                "TSTypeOperator",
+                n.span,
+                json!({
+                    "operator": match n.op {
+                        TsTypeOperatorOp::KeyOf => "keyof",
+                        TsTypeOperatorOp::Unique => "unique",
+                        TsTypeOperatorOp::ReadOnly => "readonly",
+                    },
+                    "typeAnnotation": self.ts_type(&n.type_ann),
+                }),
+            ),
+            TsType::TsIndexedAccessType(n) => self.node(
+                "TSIndexedAccessType",
+                n.span,
+                json!({
+                    "objectType": self.ts_type(&n.obj_type),
+                    "indexType": self.ts_type(&n.index_type),
+                }),
+            ),
+            TsType::TsLitType(n) => {
+                let literal = match &n.lit {
+                    TsLit::Number(num) => self.lit(&Lit::Num(num.clone())),
+                    TsLit::Str(s) => self.str(s),
+                    TsLit::Bool(b) => self.lit(&Lit::Bool(*b)),
+                    TsLit::BigInt(b) => self.lit(&Lit::BigInt(b.clone())),
+                    TsLit::Tpl(t) => self.unsupported(t.span),
+                };
+                self.node("TSLiteralType", n.span, json!({ "literal": literal }))
+            }
+            TsType::TsTypeQuery(n) => match &n.expr_name {
+                TsTypeQueryExpr::TsEntityName(name) => self.node(
+                    "TSTypeQuery",
+                    n.span,
+                    json!({ "exprName": self.ts_entity_name(name) }),
+                ),
+                _ => self.unsupported(n.span),
+            },
+            TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsFnType(n)) => {
+                let mut node = self.node(
+                    "TSFunctionType",
+                    n.span,
+                    json!({ "params": n.params.iter().map(|p| self.ts_fn_param(p)).collect::<Vec<_>>() }),
+                );
+                self.set_signature(&mut node, n.type_params.as_deref(), Some(&*n.type_ann));
+                node
+            }
+            TsType::TsConditionalType(n) => self.node(
+                "TSConditionalType",
+                n.span,
+                json!({
+                    "checkType": self.ts_type(&n.check_type),
+                    "extendsType": self.ts_type(&n.extends_type),
+                    "trueType": self.ts_type(&n.true_type),
+                    "falseType": self.ts_type(&n.false_type),
+                }),
+            ),
+            TsType::TsInferType(n) => self.node(
+                "TSInferType",
+                n.span,
+                json!({ "typeParameter": self.ts_type_param(&n.type_param) }),
+            ),
+            // `{ [K in T as N]?: V }`, with the type parameter spread over
+            // `key` and `constraint` like typescript-estree does
+            TsType::TsMappedType(n) => {
+                let mut node = self.node(
+                    "TSMappedType",
+                    n.span,
+                    json!({
+                        "key": self.ident(&n.type_param.name),
+                        "constraint": n.type_param.constraint.as_ref().map(|t| self.ts_type(t)),
+                        "nameType": n.name_type.as_ref().map(|t| self.ts_type(t)),
+                        "optional": true_plus_minus(n.optional),
+                        "readonly": true_plus_minus(n.readonly),
+                    }),
+                );
+                set_opt(
+                    &mut node,
+                    "typeAnnotation",
+                    n.type_ann.as_ref().map(|t| self.ts_type(t)),
+                );
+                node
+            }
+            other => self.unsupported(other.span()),
+        }
+    }
+
+    // --- comments and tokens ---
+
+    fn comments(&self, comments: &SingleThreadedComments) -> Value {
+        let (leading, trailing) = comments.borrow_all();
+        let mut all: Vec<_> = leading
+            .values()
+            .chain(trailing.values())
+            .flatten()
+            .cloned()
+            .collect();
+        // a comment can be both trailing one token and leading the next
+        all.sort_by_key(|c| (c.span.lo, c.span.hi));
+        all.dedup_by_key(|c| (c.span.lo, c.span.hi));
+        all.iter()
+            .map(|c| {
+                self.node(
+                    match c.kind {
+                        CommentKind::Line => "Line",
+                        CommentKind::Block => "Block",
+                    },
+                    c.span,
+                    json!({ "value": &*c.text }),
+                )
+            })
+            .collect()
+    }
+
+    // `tokens` are the spans of the tokens the lexer produced. ESTree has a
+    // single token for each part of a template literal between expressions,
+    // and we add one for each `<template>` tag, so the tokens swc produces
+    // inside of those are replaced.
+    fn tokens(&self, module: &Module, tokens: &[Span]) -> Value {
+        let mut hints = TokenHints::default();
+        module.visit_with(&mut hints);
+
+        let covered = |span: &Span| {
+            hints
+                .templates
+                .iter()
+                .chain(&hints.quasis)
+                .any(|outer| outer.lo <= span.lo && span.hi <= outer.hi)
+        };
+
+        let mut all: Vec<(Span, &str)> = tokens
+            .iter()
+            .filter(|span| !covered(span))
+            .map(|span| (*span, self.token_kind(*span, &hints)))
+            .collect();
+        all.extend(hints.quasis.iter().map(|span| (*span, "Template")));
+        all.extend(
+            hints
+                .templates
+                .iter()
+                .map(|span| (*span, "GlimmerTemplate")),
+        );
+        all.sort_by_key(|(span, _)| span.lo);
+
+        all.into_iter()
+            .map(|(span, kind)| {
+                let value = self.text(span);
+                let mut token = self.node(kind, span, json!({ "value": value }));
+                match kind {
+                    "PrivateIdentifier" => token["value"] = value[1..].into(),
+                    "RegularExpression" => {
+                        let end = value.rfind('/').unwrap_or(0);
+                        token["regex"] = json!({
+                            "pattern": &value[1.min(end)..end],
+                            "flags": &value[end + 1..],
+                        });
+                    }
+                    _ => {}
+                }
+                token
+            })
+            .collect()
+    }
+
+    fn token_kind(&self, span: Span, hints: &TokenHints) -> &'static str {
+        if hints.regexes.contains(&span) {
+            return "RegularExpression";
+        }
+        let text = self.text(span);
+        let mut chars = text.chars();
+        match chars.next() {
+            Some('"' | '\'') => "String",
+            Some('0'..='9') => "Numeric",
+            Some('.') if chars.next().is_some_and(|c| c.is_ascii_digit()) => "Numeric",
+            Some('#') if text.len() > 1 => "PrivateIdentifier",
+            Some(c) if c == '_' || c == '$' || c == '\\' || c.is_alphabetic() => match text {
+                "true" | "false" => "Boolean",
+                "null" => "Null",
+                _ if KEYWORDS.contains(&text) => "Keyword",
+                _ => "Identifier",
+            },
+            _ => "Punctuator",
+        }
+    }
+}
+
+pub(crate) const KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+// The spans that decide how tokens are reported: regular expressions, the
+// parts of template literals and `<template>` tags.
+#[derive(Default)]
+struct TokenHints {
+    regexes: Vec<Span>,
+    quasis: Vec<Span>,
+    templates: Vec<Span>,
+}
+
+impl Visit for TokenHints {
+    fn visit_regex(&mut self, n: &Regex) {
+        self.regexes.push(n.span);
+    }
+
+    fn visit_tpl_element(&mut self, n: &TplElement) {
+        self.quasis.push(template_element_span(n));
+    }
+
+    fn visit_expr(&mut self, n: &Expr) {
+        if let Expr::ContentTagExpression(tag) = n {
+            self.templates.push(tag.span);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_member(&mut self, n: &ClassMember) {
+        if let ClassMember::ContentTagMember(tag) = n {
+            self.templates.push(tag.span);
+        }
+        n.visit_children_with(self);
+    }
+}
+
+// swc's span only covers the raw text, but in ESTree a template element
+// includes the delimiters around it: the backtick or `}` before, and the `${`
+// or backtick after.
+fn template_element_span(element: &TplElement) -> Span {
+    let after = if element.tail { 1 } else { 2 };
+    Span::new(
+        element.span.lo - BytePos(1),
+        element.span.hi + BytePos(after),
+    )
+}
+
+fn set_opt(node: &mut Value, key: &str, value: Option<Value>) {
+    if let Some(value) = value {
+        node[key] = value;
+    }
+}
+
+// the `?` and `readonly` modifiers of a mapped type
+fn true_plus_minus(modifier: Option<TruePlusMinus>) -> Value {
+    match modifier {
+        None => false.into(),
+        Some(TruePlusMinus::True) => true.into(),
+        Some(TruePlusMinus::Plus) => "+".into(),
+        Some(TruePlusMinus::Minus) => "-".into(),
+    }
+}
+
+fn accessibility(accessibility: Accessibility) -> Value {
+    match accessibility {
+        Accessibility::Public => "public",
+        Accessibility::Protected => "protected",
+        Accessibility::Private => "private",
+    }
+    .into()
+}
+
+fn ts_keyword(kind: TsKeywordTypeKind) -> &'static str {
+    match kind {
+        TsKeywordTypeKind::TsAnyKeyword => "TSAnyKeyword",
+        TsKeywordTypeKind::TsUnknownKeyword => "TSUnknownKeyword",
+        TsKeywordTypeKind::TsNumberKeyword => "TSNumberKeyword",
+        TsKeywordTypeKind::TsObjectKeyword => "TSObjectKeyword",
+        TsKeywordTypeKind::TsBooleanKeyword => "TSBooleanKeyword",
+        TsKeywordTypeKind::TsBigIntKeyword => "TSBigIntKeyword",
+        TsKeywordTypeKind::TsStringKeyword => "TSStringKeyword",
+        TsKeywordTypeKind::TsSymbolKeyword => "TSSymbolKeyword",
+        TsKeywordTypeKind::TsVoidKeyword => "TSVoidKeyword",
+        TsKeywordTypeKind::TsUndefinedKeyword => "TSUndefinedKeyword",
+        TsKeywordTypeKind::TsNullKeyword => "TSNullKeyword",
+        TsKeywordTypeKind::TsNeverKeyword => "TSNeverKeyword",
+        TsKeywordTypeKind::TsIntrinsicKeyword => "TSIntrinsicKeyword",
+    }
+}
+
+// Line starts and UTF-16 offsets for the source, computed once so that every
+// node can be located without rescanning.
+pub(crate) struct LineIndex {
+    // byte offsets where lines start
+    line_starts: Vec<usize>,
+    // the UTF-16 offset of every byte offset, or `None` for ASCII sources
+    // where they're the same
+    utf16: Option<Vec<usize>>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(src: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut chars = src.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\r' if chars.peek().is_some_and(|(_, next)| *next == '\n') => {}
+                '\n' | '\r' | '\u{2028}' | '\u{2029}' => line_starts.push(i + c.len_utf8()),
+                _ => {}
+            }
+        }
+
+        let utf16 = (!src.is_ascii()).then(|| {
+            let mut offsets = Vec::with_capacity(src.len() + 1);
+            let mut units = 0;
+            for c in src.chars() {
+                // bytes inside a character map to where it starts
+                offsets.extend(std::iter::repeat(units).take(c.len_utf8()));
+                units += c.len_utf16();
+            }
+            offsets.push(units);
+            offsets
+        });
+
+        LineIndex { line_starts, utf16 }
+    }
+
+    pub(crate) fn utf16(&self, src: &str, byte: usize) -> usize {
+        match &self.utf16 {
+            Some(offsets) => offsets[byte.min(src.len())],
+            None => byte,
+        }
+    }
+
+    // The zero-based line and UTF-16 column of `byte`.
+    pub(crate) fn line_column(&self, src: &str, byte: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= byte) - 1;
+        let column = self.utf16(src, byte) - self.utf16(src, self.line_starts[line]);
+        (line, column)
+    }
+
+    fn position(&self, src: &str, byte: usize) -> Value {
+        let (line, column) = self.line_column(src, byte);
+        json!({ "line": line + 1, "column": column })
+    }
+}
+
+#[cfg(test)]
+fn estree(src: &str) -> Value {
+    crate::Preprocessor::new()
+        .parse_estree(src, Default::default())
+        .unwrap()
+}
+
+#[cfg(test)]
+fn without_locations(mut value: Value) -> Value {
+    fn strip(value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                object.remove("range");
+                object.remove("loc");
+                object.values_mut().for_each(strip);
+            }
+            Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    strip(&mut value);
+    value
+}
+
+#[test]
+fn converts_templates() {
+    let program = estree(
+        "import Foo from './foo';\nexport const A = <template><Foo /></template>;\nclass B {\n  <template>b</template>\n}\n",
+    );
+    let body = &program["body"];
+    assert_eq!(body[0]["type"], "ImportDeclaration");
+    assert_eq!(body[0]["specifiers"][0]["type"], "ImportDefaultSpecifier");
+    assert_eq!(body[0]["source"]["value"], "./foo");
+
+    let template = &body[1]["declaration"]["declarations"][0]["init"];
+    assert_eq!(
+        *template,
+        json!({
+            "type": "GlimmerTemplate",
+            "contents": "<Foo />",
+            "range": [42, 70],
+            "loc": { "start": { "line": 2, "column": 17 }, "end": { "line": 2, "column": 45 } },
+        })
+    );
+
+    let member = &body[2]["body"]["body"][0];
+    assert_eq!(member["type"], "GlimmerTemplate");
+    assert_eq!(member["contents"], "b");
+}
+
+#[test]
+fn converts_expressions() {
+    let program = estree("a?.b.c(1, ...d);\n`x${y}z`;\nlet { e = 1, ...f } = g;");
+    assert_eq!(
+        without_locations(program["body"][0]["expression"].clone()),
+        json!({
+            "type": "ChainExpression",
+            "expression": {
+                "type": "CallExpression",
+                "callee": {
+                    "type": "MemberExpression",
+                    "object": {
+                        "type": "MemberExpression",
+                        "object": { "type": "Identifier", "name": "a" },
+                        "property": { "type": "Identifier", "name": "b" },
+                        "computed": false,
+                        "optional": true,
+                    },
+                    "property": { "type": "Identifier", "name": "c" },
+                    "computed": false,
+                    "optional": false,
+                },
+                "arguments": [
+                    { "type": "Literal", "value": 1.0, "raw": "1" },
+                    { "type": "SpreadElement", "argument": { "type": "Identifier", "name": "d" } },
+                ],
+                "optional": false,
+            },
+        })
+    );
+
+    let tpl = &program["body"][1]["expression"];
+    assert_eq!(tpl["quasis"][0]["range"], json!([17, 21]));
+    assert_eq!(tpl["quasis"][1]["range"], json!([22, 25]));
+
+    let pattern = &program["body"][2]["declarations"][0]["id"];
+    assert_eq!(pattern["properties"][0]["shorthand"], true);
+    assert_eq!(
+        pattern["properties"][0]["value"]["type"],
+        "AssignmentPattern"
+    );
+    assert_eq!(pattern["properties"][1]["type"], "RestElement");
+}
+
+#[test]
+fn reports_comments_and_tokens() {
+    let program =
+        estree("// hi\nconst a = /x/g; /* 😀 */ let b = `t${a}`;\n<template>x</template>");
+    assert_eq!(
+        without_locations(program["comments"].clone()),
+        json!([
+            { "type": "Line", "value": " hi" },
+            { "type": "Block", "value": " 😀 " },
+        ])
+    );
+    assert_eq!(program["comments"][1]["range"], json!([22, 30]));
+
+    let tokens: Vec<_> = program["tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| (t["type"].as_str().unwrap(), t["value"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            ("Keyword", "const"),
+            ("Identifier", "a"),
+            ("Punctuator", "="),
+            ("RegularExpression", "/x/g"),
+            ("Punctuator", ";"),
+            ("Identifier", "let"),
+            ("Identifier", "b"),
+            ("Punctuator", "="),
+            ("Template", "`t${"),
+            ("Identifier", "a"),
+            ("Template", "}`"),
+            ("Punctuator", ";"),
+            ("GlimmerTemplate", "<template>x</template>"),
+        ]
+    );
+    // the emoji is two UTF-16 code units, and everything after it moves by one
+    // less than its four bytes
+    assert_eq!(program["tokens"][6]["range"], json!([35, 36]));
+}
+
+#[test]
+fn converts_typescript() {
+    let program = estree(
+        "interface I<T> extends J { a?: string; m(x: number): T[] }\nconst x = y as unknown as I<'a' | 1>;",
+    );
+    let interface = without_locations(program["body"][0].clone());
+    assert_eq!(interface["type"], "TSInterfaceDeclaration");
+    assert_eq!(
+        interface["typeParameters"]["params"][0]["name"]["name"],
+        "T"
+    );
+    assert_eq!(interface["extends"][0]["expression"]["name"], "J");
+    assert_eq!(
+        interface["body"]["body"][0]["typeAnnotation"]["typeAnnotation"]["type"],
+        "TSStringKeyword"
+    );
+    assert_eq!(interface["body"]["body"][1]["type"], "TSMethodSignature");
+
+    let init = &program["body"][1]["declarations"][0]["init"];
+    assert_eq!(init["type"], "TSAsExpression");
+    assert_eq!(init["expression"]["type"], "TSAsExpression");
+    let union = &init["typeAnnotation"]["typeArguments"]["params"][0];
+    assert_eq!(union["type"], "TSUnionType");
+    assert_eq!(union["types"][1]["literal"]["value"], 1.0);
+}
+
+#[test]
+fn converts_conditional_and_mapped_types_and_namespaces() {
+    let program = estree(concat!(
+        "type A<T> = T extends Array<infer U> ? U : never;\n",
+        "type B<T> = { readonly [K in keyof T]-?: T[K] };\n",
+        "namespace N.M { export const x = 1; }\n",
+        "declare module \"m\" {}",
+    ));
+    let conditional = &program["body"][0]["typeAnnotation"];
+    assert_eq!(conditional["type"], "TSConditionalType");
+    assert_eq!(conditional["checkType"]["typeName"]["name"], "T");
+    let infer = &conditional["extendsType"]["typeArguments"]["params"][0];
+    assert_eq!(infer["type"], "TSInferType");
+    assert_eq!(infer["typeParameter"]["name"]["name"], "U");
+    assert_eq!(conditional["trueType"]["typeName"]["name"], "U");
+    assert_eq!(conditional["falseType"]["type"], "TSNeverKeyword");
+
+    let mapped = &program["body"][1]["typeAnnotation"];
+    assert_eq!(mapped["type"], "TSMappedType");
+    assert_eq!(mapped["key"]["name"], "K");
+    assert_eq!(mapped["constraint"]["type"], "TSTypeOperator");
+    assert_eq!(mapped["readonly"], true);
+    assert_eq!(mapped["optional"], "-");
+    assert_eq!(mapped["typeAnnotation"]["type"], "TSIndexedAccessType");
+
+    let namespace = &program["body"][2];
+    assert_eq!(namespace["type"], "TSModuleDeclaration");
+    assert_eq!(namespace["kind"], "namespace");
+    assert_eq!(namespace["id"]["type"], "TSQualifiedName");
+    assert_eq!(namespace["id"]["range"], json!([109, 112]));
+    assert_eq!(namespace["id"]["left"]["name"], "N");
+    assert_eq!(namespace["id"]["right"]["name"], "M");
+    assert_eq!(namespace["body"]["type"], "TSModuleBlock");
+    assert_eq!(
+        namespace["body"]["body"][0]["type"],
+        "ExportNamedDeclaration"
+    );
+
+    let module = &program["body"][3];
+    assert_eq!(module["kind"], "module");
+    assert_eq!(module["declare"], true);
+    assert_eq!(module["id"]["value"], "m");
+}
+
+#[test]
+fn marks_directives() {
+    let program = estree("'use client';\nfunction f() { \"use strict\"; x; }");
+    assert_eq!(program["body"][0]["directive"], "use client");
+    let body = &program["body"][1]["body"]["body"];
+    assert_eq!(body[0]["directive"], "use strict");
+    assert_eq!(body[1].get("directive"), None);
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use swc_common::{BytePos, Span};
+use swc_ecma_ast::{
+    Class, ClassDecl, ClassExpr, ClassMember, ContentTagExpression, ContentTagMember, Decl,
+    DefaultDecl, ExportDecl, ExportDefaultDecl, ExportDefaultExpr, Expr, ExprStmt, Ident, Module,
+    ModuleDecl, ModuleItem, Stmt,
+};
+use swc_ecma_utils::private_ident;
+use uuid::Uuid;
+
+// Stable identities for the templates of one module, for the `hot` option.
+// A template is identified by the filename, its index among the module's
+// templates in source order and a hash of its contents, so an edit to a
+// template changes only that template's identity. The module's JS, with the
+// template contents left out, gets a hash of its own so that the
+// `import.meta.hot` hook can tell a template-only edit from one that needs a
+// full reload.
+//
+// Only templates that are evaluated once per load get an identity: those in
+// the body of a module-level class, and expression templates at module level,
+// like `export default <template>` or `const X = <template>`. A template in a
+// function can capture different locals every time it runs, so it can't share
+// a slot; its contents count as JS, and editing it reloads the module.
+pub(crate) struct HotReload {
+    // keyed by the start of the template's span
+    ids: HashMap<BytePos, String>,
+    js_hash: String,
+    used: bool,
+    helpers: HotHelpers,
+}
+
+// The module-level bindings that `snippets::hot_reload_hook` adds. Like the
+// imports in lib.rs they start out with fixed aliases, which `process` renames
+// to names that nothing in the module uses, see `ALIASES`.
+pub(crate) struct HotHelpers {
+    pub slot: Ident,
+    pub class: Ident,
+    pub template: Ident,
+    pub get_component_template: Ident,
+    pub set_component_template: Ident,
+    pub template_only: Ident,
+}
+
+const SLOT_ALIAS: &str = "contentTagHotSlot_fd9b2463e5f141cfb5666b64daa1f11a";
+const CLASS_ALIAS: &str = "contentTagHotClass_fd9b2463e5f141cfb5666b64daa1f11a";
+const TEMPLATE_ALIAS: &str = "contentTagHotTemplate_fd9b2463e5f141cfb5666b64daa1f11a";
+const GET_COMPONENT_TEMPLATE_ALIAS: &str =
+    "getComponentTemplate_hot_fd9b2463e5f141cfb5666b64daa1f11a";
+const SET_COMPONENT_TEMPLATE_ALIAS: &str =
+    "setComponentTemplate_hot_fd9b2463e5f141cfb5666b64daa1f11a";
+const TEMPLATE_ONLY_ALIAS: &str = "templateOnly_hot_fd9b2463e5f141cfb5666b64daa1f11a";
+
+// Each helper's alias and the name `imports::rename_aliases` builds on.
+pub(crate) const ALIASES: &[(&str, &str)] = &[
+    (SLOT_ALIAS, "contentTagHotSlot"),
+    (CLASS_ALIAS, "contentTagHotClass"),
+    (TEMPLATE_ALIAS, "contentTagHotTemplate"),
+    (GET_COMPONENT_TEMPLATE_ALIAS, "getComponentTemplate"),
+    (SET_COMPONENT_TEMPLATE_ALIAS, "setComponentTemplate"),
+    (TEMPLATE_ONLY_ALIAS, "templateOnly"),
+];
+
+impl HotHelpers {
+    fn new() -> Self {
+        HotHelpers {
+            slot: private_ident!(SLOT_ALIAS),
+            class: private_ident!(CLASS_ALIAS),
+            template: private_ident!(TEMPLATE_ALIAS),
+            get_component_template: private_ident!(GET_COMPONENT_TEMPLATE_ALIAS),
+            set_component_template: private_ident!(SET_COMPONENT_TEMPLATE_ALIAS),
+            template_only: private_ident!(TEMPLATE_ONLY_ALIAS),
+        }
+    }
+}
+
+const HOT_RELOAD_NAMESPACE: Uuid = Uuid::from_u128(0x2d7e9c14_5a3b_4f60_8c1d_7b9e0a4f6c32);
+
+fn content_hash(bytes: &[u8]) -> String {
+    Uuid::new_v5(&HOT_RELOAD_NAMESPACE, bytes)
+        .simple()
+        .to_string()[..8]
+        .to_string()
+}
+
+impl HotReload {
+    pub fn new(module: &Module, src: &str, start: BytePos, filename: &str) -> Self {
+        let mut collector = TemplateCollector::default();
+        collector.module(module);
+        let mut templates = collector.templates;
+        templates.sort_by_key(|(span, _)| span.lo);
+
+        let byte = |pos: BytePos| (pos - start).0 as usize;
+        let mut js = Vec::with_capacity(src.len());
+        let mut last = 0;
+        let mut ids = HashMap::new();
+        for (index, (span, contents)) in templates.iter().enumerate() {
+            let contents = &src[byte(contents.lo)..byte(contents.hi)];
+            let hash = content_hash(contents.as_bytes());
+            ids.insert(span.lo, format!("{filename}:{index}:{hash}"));
+
+            js.extend_from_slice(src[last..byte(span.lo)].as_bytes());
+            last = byte(span.hi);
+        }
+        js.extend_from_slice(src[last..].as_bytes());
+
+        HotReload {
+            ids,
+            js_hash: content_hash(&js),
+            used: false,
+            helpers: HotHelpers::new(),
+        }
+    }
+
+    // The identity of the template whose span starts at `pos`.
+    pub fn id(&mut self, pos: BytePos) -> Option<&str> {
+        let id = self.ids.get(&pos)?;
+        self.used = true;
+        Some(id)
+    }
+
+    pub fn js_hash(&self) -> &str {
+        &self.js_hash
+    }
+
+    pub fn helpers(&self) -> &HotHelpers {
+        &self.helpers
+    }
+
+    // Whether any template was given its identity, and so whether the module
+    // needs the `import.meta.hot` hook.
+    pub fn used(&self) -> bool {
+        self.used
+    }
+}
+
+#[derive(Default)]
+struct TemplateCollector {
+    // the span of each template and of its contents
+    templates: Vec<(Span, Span)>,
+}
+
+impl TemplateCollector {
+    fn module(&mut self, module: &Module) {
+        for item in &module.body {
+            match item {
+                ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. }))
+                | ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(ExportDefaultExpr {
+                    expr,
+                    ..
+                })) => self.expr(expr),
+                ModuleItem::Stmt(Stmt::Decl(decl))
+                | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                    self.decl(decl)
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                    decl: DefaultDecl::Class(ClassExpr { class, .. }),
+                    ..
+                })) => self.class(class),
+                _ => {}
+            }
+        }
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Class(ClassDecl { class, .. }) => self.class(class),
+            Decl::Var(var) => {
+                for init in var.decls.iter().filter_map(|d| d.init.as_deref()) {
+                    self.expr(init);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::ContentTagExpression(ContentTagExpression { span, contents, .. }) => {
+                self.templates.push((*span, contents.span));
+            }
+            Expr::Class(ClassExpr { class, .. }) => self.class(class),
+            Expr::Paren(paren) => self.expr(&paren.expr),
+            Expr::TsSatisfies(satisfies) => self.expr(&satisfies.expr),
+            Expr::TsAs(as_expr) => self.expr(&as_expr.expr),
+            _ => {}
+        }
+    }
+
+    fn class(&mut self, class: &Class) {
+        for member in &class.body {
+            if let ClassMember::ContentTagMember(ContentTagMember { span, contents, .. }) = member {
+                self.templates.push((*span, contents.span));
+            }
+        }
+    }
+}
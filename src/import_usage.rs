@@ -0,0 +1,210 @@
+// Finds the imports that nothing uses and the template references that
+// nothing binds. JS tooling can't answer either on its own, because it doesn't
+// see into `<template>` tags, where an imported component or helper is often
+// the only use of its import.
+//
+// The module is resolved with its templates exposed as described in
+// template_scope.rs, the same way `process` decides what a template can see,
+// so a template that uses a shadowed name doesn't count as using the import.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use swc_atoms::Atom;
+use swc_common::{BytePos, Mark, Span, SyntaxContext};
+use swc_ecma_ast::{Ident, ImportDecl, ImportSpecifier, Module, ModuleDecl, ModuleItem};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::locate::Range;
+use crate::template_refs::ReferenceKind;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportUsage {
+    pub unused_imports: Vec<UnusedImport>,
+    // in the order they appear in the file
+    pub unresolved_references: Vec<UnresolvedReference>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusedImport {
+    // the local name, like `Bar` in `import { Foo as Bar } from "foo"`
+    pub local: String,
+    pub source: String,
+    // the local name in the import
+    pub range: Range,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedReference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    // the name in the template
+    pub range: Range,
+}
+
+// `module` has been through `template_scope::expose_template_references`,
+// which returned `kinds`, and then `template_scope::resolve`, which returned
+// `unresolved_mark`.
+pub fn analyze(
+    src: &str,
+    module: &Module,
+    kinds: &HashMap<BytePos, ReferenceKind>,
+    unresolved_mark: Mark,
+) -> ImportUsage {
+    let mut collector = ReferenceCollector::default();
+    module.visit_with(&mut collector);
+
+    let is_ascii = src.is_ascii();
+    let mut unused_imports = vec![];
+    for item in &module.body {
+        let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+            continue;
+        };
+        for specifier in &import.specifiers {
+            let local = match specifier {
+                ImportSpecifier::Named(s) => &s.local,
+                ImportSpecifier::Default(s) => &s.local,
+                ImportSpecifier::Namespace(s) => &s.local,
+            };
+            let used = collector
+                .references
+                .iter()
+                .any(|r| r.sym == local.sym && r.ctxt == local.ctxt);
+            if !used {
+                unused_imports.push(UnusedImport {
+                    local: local.sym.to_string(),
+                    source: import.src.value.to_string_lossy().into_owned(),
+                    range: Range::new(src, &local.span, is_ascii),
+                });
+            }
+        }
+    }
+
+    let mut unresolved_references: Vec<_> = collector
+        .references
+        .iter()
+        .filter(|r| r.ctxt.outer() == unresolved_mark)
+        .filter_map(|r| {
+            let kind = kinds.get(&r.span.lo)?;
+            Some(UnresolvedReference {
+                name: r.sym.to_string(),
+                kind: *kind,
+                range: Range::new(src, &r.span, is_ascii),
+            })
+        })
+        .collect();
+    unresolved_references.sort_by_key(|r| r.range.start_byte());
+
+    ImportUsage {
+        unused_imports,
+        unresolved_references,
+    }
+}
+
+struct Reference {
+    sym: Atom,
+    ctxt: SyntaxContext,
+    span: Span,
+}
+
+#[derive(Default)]
+struct ReferenceCollector {
+    references: Vec<Reference>,
+}
+
+impl Visit for ReferenceCollector {
+    // the imported bindings themselves aren't uses
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+
+    fn visit_ident(&mut self, n: &Ident) {
+        self.references.push(Reference {
+            sym: n.sym.clone(),
+            ctxt: n.ctxt,
+            span: n.span,
+        });
+    }
+}
+
+#[cfg(test)]
+fn usage(src: &str) -> (Vec<String>, Vec<(String, ReferenceKind, String)>) {
+    let usage = crate::Preprocessor::new()
+        .import_usage(src, Default::default())
+        .unwrap();
+    (
+        usage
+            .unused_imports
+            .into_iter()
+            .map(|i| format!("{} from {}", i.local, i.source))
+            .collect(),
+        usage
+            .unresolved_references
+            .into_iter()
+            .map(|r| {
+                let text = src[r.range.start_byte()..r.range.end_byte()].to_string();
+                (r.name, r.kind, text)
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn counts_template_references_as_uses() {
+    let (unused, unresolved) = usage(
+        r#"import Foo from "./foo";
+import { bar, baz as qux } from "./helpers";
+import * as ns from "./ns";
+import autofocus from "./autofocus";
+import Unused, { alsoUnused } from "./unused";
+export default <template>
+  <Foo @x={{bar 1}} />
+  <input {{autofocus}} />
+  {{ns.thing}}
+  <Missing />
+  {{#each items as |item|}}{{item}}{{/each}}
+</template>"#,
+    );
+    assert_eq!(
+        unused,
+        vec![
+            "qux from ./helpers",
+            "Unused from ./unused",
+            "alsoUnused from ./unused"
+        ]
+    );
+    assert_eq!(
+        unresolved,
+        vec![
+            (
+                "Missing".to_string(),
+                ReferenceKind::Component,
+                "Missing".to_string()
+            ),
+            (
+                "items".to_string(),
+                ReferenceKind::Path,
+                "items".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn respects_js_scope() {
+    let (unused, unresolved) = usage(
+        r#"import Foo from "./foo";
+import Bar from "./bar";
+import type { Baz } from "./types";
+const used: Baz = Bar;
+function shadowed(Foo) {
+  return <template><Foo /></template>;
+}
+class C {
+  <template>{{Bar}}{{shadowed}}</template>
+}"#,
+    );
+    assert_eq!(unused, vec!["Foo from ./foo"]);
+    assert_eq!(unresolved, vec![]);
+}
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::{Atom, Wtf8Atom};
+use swc_common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
+use swc_common::{Span, Spanned, DUMMY_SP};
+use swc_ecma_ast::{
+    AssignPatProp, BindingIdent, ClassDecl, ClassExpr, ClassMember, ContentTagExpression,
+    ContentTagMember, Expr, ExprStmt, FnDecl, FnExpr, Ident, ImportDecl, ImportDefaultSpecifier,
+    ImportNamedSpecifier, ImportSpecifier, ImportStarAsSpecifier, KeyValuePatProp, Lit, Module,
+    ModuleDecl, ModuleExportName, ModuleItem, ObjectPatProp, Pat, PropName, Stmt,
+};
+use swc_ecma_utils::private_ident;
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+use crate::template_refs::template_references;
+use crate::{ImportPlacement, ModuleFormat};
+
+// The local binding that generated code uses to refer to an import, such as
+// `template` from `@ember/template-compiler`. A `target_specifier` of
+// `default` stands for the module's default export.
+pub struct ImportBinding<'a> {
+    target_module: &'a str,
+    target_specifier: &'a str,
+    pub local: Ident,
+    // whether `local` comes from an import the module already has
+    existing: bool,
+}
+
+impl<'a> ImportBinding<'a> {
+    // Reuses an existing import of `target_specifier` when its local name isn't
+    // shadowed anywhere in the module, otherwise picks a fresh `alias`.
+    pub fn find_or_create(
+        module: &Module,
+        target_module: &'a str,
+        target_specifier: &'a str,
+        alias: &str,
+    ) -> Self {
+        match reusable_import(module, target_module, target_specifier) {
+            Some(local) => Self {
+                target_module,
+                target_specifier,
+                local,
+                existing: true,
+            },
+            None => Self {
+                target_module,
+                target_specifier,
+                local: private_ident!(alias),
+                existing: false,
+            },
+        }
+    }
+
+    // Makes sure the module imports `local`. Call this only once the binding
+    // is known to be used.
+    pub fn insert(
+        &self,
+        module: &mut Module,
+        format: ModuleFormat,
+        placement: ImportPlacement,
+        comments: &SingleThreadedComments,
+    ) {
+        if self.existing {
+            return;
+        }
+        let specifier = if self.target_specifier == "default" {
+            ImportSpecifier::Default(ImportDefaultSpecifier {
+                span: Default::default(),
+                local: self.local.clone(),
+            })
+        } else {
+            ImportSpecifier::Named(ImportNamedSpecifier {
+                span: Default::default(),
+                local: self.local.clone(),
+                imported: Some(ModuleExportName::Ident(Ident::new_no_ctxt(
+                    self.target_specifier.into(),
+                    Default::default(),
+                ))),
+                is_type_only: false,
+            })
+        };
+        let specifier = match format {
+            ModuleFormat::Module => match merge_import(module, self.target_module, specifier) {
+                Some(specifier) => specifier,
+                None => return,
+            },
+            ModuleFormat::CommonJs => specifier,
+        };
+
+        let index = insertion_index(module, placement);
+        let span = if index == 0 {
+            // the other comments in front of the first statement stay keyed
+            // to it, and so below the import: the emitter leaves comments at
+            // the start of the module to the statement that starts there
+            hoist_license_comments(module, comments)
+        } else {
+            DUMMY_SP
+        };
+        let item = match format {
+            ModuleFormat::Module => ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                span,
+                specifiers: vec![specifier],
+                src: Box::new(self.target_module.into()),
+                type_only: false,
+                with: None,
+                phase: Default::default(),
+            })),
+            ModuleFormat::CommonJs => crate::snippets::require_declaration(
+                span,
+                self.target_module,
+                self.target_specifier,
+                &self.local,
+            ),
+        };
+        module.body.insert(index, item);
+    }
+}
+
+fn is_import_from(decl: &ImportDecl, target_module: &str) -> bool {
+    !decl.type_only
+        && decl.phase == Default::default()
+        && decl.src.value == Wtf8Atom::from(target_module)
+}
+
+fn reusable_import(module: &Module, target_module: &str, target_specifier: &str) -> Option<Ident> {
+    let local = module.body.iter().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) if is_import_from(decl, target_module) => {
+            decl.specifiers
+                .iter()
+                .find_map(|specifier| match specifier {
+                    ImportSpecifier::Named(named)
+                        if !named.is_type_only && imported_name_is(named, target_specifier) =>
+                    {
+                        Some(named.local.clone())
+                    }
+                    ImportSpecifier::Default(default) if target_specifier == "default" => {
+                        Some(default.local.clone())
+                    }
+                    _ => None,
+                })
+        }
+        _ => None,
+    })?;
+
+    let mut counter = DeclarationCounter {
+        name: &local.sym,
+        count: 0,
+    };
+    module.visit_with(&mut counter);
+    if counter.count == 1 {
+        Some(local)
+    } else {
+        None
+    }
+}
+
+fn imported_name_is(named: &ImportNamedSpecifier, name: &str) -> bool {
+    match &named.imported {
+        Some(ModuleExportName::Ident(ident)) => &*ident.sym == name,
+        Some(ModuleExportName::Str(str)) => str.value == Wtf8Atom::from(name),
+        None => &*named.local.sym == name,
+    }
+}
+
+// Counts the declarations of `name` in every scope of a module.
+struct DeclarationCounter<'a> {
+    name: &'a str,
+    count: usize,
+}
+
+impl<'a> DeclarationCounter<'a> {
+    fn check(&mut self, ident: &Ident) {
+        if &*ident.sym == self.name {
+            self.count += 1;
+        }
+    }
+}
+
+impl<'a> Visit for DeclarationCounter<'a> {
+    fn visit_binding_ident(&mut self, n: &BindingIdent) {
+        self.check(&n.id);
+    }
+
+    fn visit_fn_decl(&mut self, n: &FnDecl) {
+        self.check(&n.ident);
+        n.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, n: &FnExpr) {
+        if let Some(ident) = &n.ident {
+            self.check(ident);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, n: &ClassDecl) {
+        self.check(&n.ident);
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, n: &ClassExpr) {
+        if let Some(ident) = &n.ident {
+            self.check(ident);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_import_named_specifier(&mut self, n: &ImportNamedSpecifier) {
+        self.check(&n.local);
+    }
+
+    fn visit_import_default_specifier(&mut self, n: &ImportDefaultSpecifier) {
+        self.check(&n.local);
+    }
+
+    fn visit_import_star_as_specifier(&mut self, n: &ImportStarAsSpecifier) {
+        self.check(&n.local);
+    }
+}
+
+// Adds `specifier` to an existing import of the same module when the import's
+// shape allows another specifier of its kind, otherwise hands it back.
+fn merge_import(
+    module: &mut Module,
+    target_module: &str,
+    specifier: ImportSpecifier,
+) -> Option<ImportSpecifier> {
+    let is_default = matches!(specifier, ImportSpecifier::Default(_));
+    let existing = module.body.iter_mut().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(decl))
+            if is_import_from(decl, target_module)
+                && !decl.specifiers.iter().any(|s| match s {
+                    ImportSpecifier::Namespace(_) => true,
+                    ImportSpecifier::Default(_) => is_default,
+                    ImportSpecifier::Named(_) => false,
+                }) =>
+        {
+            Some(decl)
+        }
+        _ => None,
+    });
+
+    match existing {
+        // a default specifier has to come first
+        Some(decl) if is_default => {
+            decl.specifiers.insert(0, specifier);
+            None
+        }
+        Some(decl) => {
+            decl.specifiers.push(specifier);
+            None
+        }
+        None => Some(specifier),
+    }
+}
+
+// New imports go after the directive prologue (`"use strict"`, `"use client"`)
+// and, with `ImportPlacement::AfterImports`, after the last existing import.
+fn insertion_index(module: &Module, placement: ImportPlacement) -> usize {
+    let directives = module
+        .body
+        .iter()
+        .take_while(|item| is_directive(item))
+        .count();
+    match placement {
+        ImportPlacement::Start => directives,
+        ImportPlacement::AfterImports => module
+            .body
+            .iter()
+            .rposition(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+            .map_or(directives, |last| directives.max(last + 1)),
+    }
+}
+
+fn is_directive(item: &ModuleItem) -> bool {
+    matches!(
+        item,
+        ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+            expr: box Expr::Lit(Lit::Str(_)),
+            ..
+        }))
+    )
+}
+
+// License comments in front of the first statement have to stay at the top of
+// the file, so they are moved onto the import that is about to be inserted
+// above that statement. Returns the span to give the import.
+fn hoist_license_comments(module: &Module, comments: &SingleThreadedComments) -> Span {
+    let Some(first) = module.body.first() else {
+        return DUMMY_SP;
+    };
+    let first_lo = first.span_lo();
+    let Some(mut leading) = comments.take_leading(first_lo) else {
+        return DUMMY_SP;
+    };
+    let Some(last_license) = leading.iter().rposition(is_license_comment) else {
+        comments.add_leading_comments(first_lo, leading);
+        return DUMMY_SP;
+    };
+
+    let rest = leading.split_off(last_license + 1);
+    if !rest.is_empty() {
+        comments.add_leading_comments(first_lo, rest);
+    }
+    let pos = leading[0].span.lo;
+    comments.add_leading_comments(pos, leading);
+    Span::new(pos, pos)
+}
+
+fn is_license_comment(comment: &Comment) -> bool {
+    comment.kind == CommentKind::Block
+        && (comment.text.starts_with('!')
+            || comment.text.contains("@license")
+            || comment.text.contains("@preserve"))
+}
+
+// Every identifier name in the module, plus the names its templates refer to,
+// since those are resolved at runtime through `eval` and must not be shadowed
+// by a generated import. This needs to run before templates are lowered.
+pub fn taken_names(module: &Module) -> HashSet<Atom> {
+    let mut collector = NameCollector::default();
+    module.visit_with(&mut collector);
+    collector.names
+}
+
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<Atom>,
+}
+
+impl NameCollector {
+    fn add_template_references(&mut self, contents: &str) {
+        for reference in template_references(contents) {
+            self.names.insert(reference.name.into());
+        }
+    }
+}
+
+impl Visit for NameCollector {
+    fn visit_ident(&mut self, n: &Ident) {
+        self.names.insert(n.sym.clone());
+    }
+
+    fn visit_expr(&mut self, n: &Expr) {
+        if let Expr::ContentTagExpression(ContentTagExpression { contents, .. }) = n {
+            self.add_template_references(&contents.value);
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_member(&mut self, n: &ClassMember) {
+        if let ClassMember::ContentTagMember(ContentTagMember { contents, .. }) = n {
+            self.add_template_references(&contents.value);
+        }
+        n.visit_children_with(self);
+    }
+}
+
+// Renames each fixed `alias` to the shortest name built from its `base`
+// (`template`, `template1`, `template2`, ...) that isn't already taken.
+pub fn rename_aliases(module: &mut Module, aliases: &[(&str, &str)], taken: &mut HashSet<Atom>) {
+    let mut renames = HashMap::new();
+    for (alias, base) in aliases {
+        let mut name: Atom = (*base).into();
+        let mut suffix = 0;
+        while taken.contains(&name) {
+            suffix += 1;
+            name = format!("{base}{suffix}").into();
+        }
+        taken.insert(name.clone());
+        renames.insert(Atom::from(*alias), name);
+    }
+    module.visit_mut_with(&mut AliasRenamer { renames });
+}
+
+struct AliasRenamer {
+    renames: HashMap<Atom, Atom>,
+}
+
+impl VisitMut for AliasRenamer {
+    fn visit_mut_ident(&mut self, n: &mut Ident) {
+        if let Some(name) = self.renames.get(&n.sym) {
+            n.sym = name.clone();
+        }
+    }
+
+    fn visit_mut_import_named_specifier(&mut self, n: &mut ImportNamedSpecifier) {
+        n.visit_mut_children_with(self);
+        // `import { template as template }` becomes `import { template }`
+        if let Some(ModuleExportName::Ident(imported)) = &n.imported {
+            if imported.sym == n.local.sym {
+                n.imported = None;
+            }
+        }
+    }
+
+    fn visit_mut_object_pat_prop(&mut self, n: &mut ObjectPatProp) {
+        n.visit_mut_children_with(self);
+        // `const { template: template } = require(...)` becomes
+        // `const { template } = require(...)`
+        if let ObjectPatProp::KeyValue(KeyValuePatProp {
+            key: PropName::Ident(key),
+            value: box Pat::Ident(binding),
+        }) = n
+        {
+            if key.sym == binding.id.sym && binding.type_ann.is_none() {
+                *n = ObjectPatProp::Assign(AssignPatProp {
+                    span: key.span,
+                    key: binding.clone(),
+                    value: None,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use swc_common::{errors::Handler, sync::Lrc, SourceMap, Spanned};
+use swc_error_reporters::{
+    handler::{HandlerOpts, ThreadSafetyDiagnostics},
+    ErrorEmitter, GraphicalReportHandler, GraphicalTheme, ToPrettyDiagnostic,
+};
+
+use crate::{
+    ClassMemberLowering, EsVersion, ImportAlias, ImportPlacement, LineEnding, ModuleFormat,
+    Options, OutputFlavor, SourceMapOptions, StripIndent,
+};
+
+// The options object that every JS binding accepts, read through whatever
+// accessors the binding's runtime provides.
+pub trait OptionsObject {
+    // the value if it is a string
+    fn string(&self, key: &str) -> Option<String>;
+    // the value if it is a number
+    fn number(&self, key: &str) -> Option<f64>;
+    // the value's truthiness, or `None` when it is undefined
+    fn truthy(&self, key: &str) -> Option<bool>;
+    // the value converted with `String()`, when it is truthy
+    fn coerced_string(&self, key: &str) -> Option<String>;
+}
+
+// An option that is set to something it can't be. Bindings throw this as an
+// `Error` with the `Display` text as its message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidOption {
+    pub key: &'static str,
+    // what the option may be
+    pub expected: String,
+}
+
+impl fmt::Display for InvalidOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid option `{}`: expected {}",
+            self.key, self.expected
+        )
+    }
+}
+
+impl std::error::Error for InvalidOption {}
+
+// The value of a string option that has to be one of `choices`, or `default`
+// when the option isn't set.
+fn choice<T: Copy>(
+    object: &impl OptionsObject,
+    key: &'static str,
+    choices: &[(&str, T)],
+    default: T,
+) -> Result<T, InvalidOption> {
+    let invalid = || InvalidOption {
+        key,
+        expected: format!(
+            "one of {}",
+            choices
+                .iter()
+                .map(|(name, _)| format!("\"{name}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    match object.string(key) {
+        Some(value) => choices
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, choice)| *choice)
+            .ok_or_else(invalid),
+        // something other than a string
+        None if object.truthy(key) == Some(true) => Err(invalid()),
+        None => Ok(default),
+    }
+}
+
+pub fn options_from(object: &impl OptionsObject) -> Result<Options, InvalidOption> {
+    let flavor = choice(
+        object,
+        "flavor",
+        &[
+            ("template", OutputFlavor::Template),
+            ("precompile-template", OutputFlavor::PrecompileTemplate),
+        ],
+        OutputFlavor::Template,
+    )?;
+
+    let class_member_lowering = match object.truthy("static_blocks") {
+        Some(false) => ClassMemberLowering::OutsideClass,
+        _ => ClassMemberLowering::StaticBlock,
+    };
+
+    let strip_indent = choice(
+        object,
+        "strip_indent",
+        &[
+            ("off", StripIndent::Off),
+            ("trim-only", StripIndent::TrimOnly),
+            ("dedent", StripIndent::Dedent),
+        ],
+        StripIndent::Dedent,
+    )?;
+    let strip_indent = match (strip_indent, tab_width(object)?) {
+        (StripIndent::Dedent, Some(width)) => StripIndent::DedentWithTabWidth(width),
+        (_, Some(_)) => {
+            return Err(InvalidOption {
+                key: "tab_width",
+                expected: r#"no value with `strip_indent: "off"` or `"trim-only"`"#.to_string(),
+            })
+        }
+        (strip_indent, None) => strip_indent,
+    };
+
+    let import_alias = choice(
+        object,
+        "import_alias",
+        &[
+            ("readable", ImportAlias::Readable),
+            ("fixed", ImportAlias::Fixed),
+        ],
+        ImportAlias::Readable,
+    )?;
+
+    let module_format = choice(
+        object,
+        "module_format",
+        &[
+            ("module", ModuleFormat::Module),
+            ("commonjs", ModuleFormat::CommonJs),
+        ],
+        ModuleFormat::Module,
+    )?;
+
+    let import_placement = choice(
+        object,
+        "import_placement",
+        &[
+            ("start", ImportPlacement::Start),
+            ("after-imports", ImportPlacement::AfterImports),
+        ],
+        ImportPlacement::Start,
+    )?;
+
+    let target = choice(
+        object,
+        "target",
+        &[
+            ("es3", EsVersion::Es3),
+            ("es5", EsVersion::Es5),
+            ("es2015", EsVersion::Es2015),
+            ("es2016", EsVersion::Es2016),
+            ("es2017", EsVersion::Es2017),
+            ("es2018", EsVersion::Es2018),
+            ("es2019", EsVersion::Es2019),
+            ("es2020", EsVersion::Es2020),
+            ("es2021", EsVersion::Es2021),
+            ("es2022", EsVersion::Es2022),
+            ("esnext", EsVersion::EsNext),
+        ],
+        EsVersion::Es5,
+    )?;
+
+    let line_ending = choice(
+        object,
+        "line_ending",
+        &[
+            ("lf", LineEnding::Lf),
+            ("crlf", LineEnding::Crlf),
+            ("preserve", LineEnding::Preserve),
+        ],
+        LineEnding::Lf,
+    )?;
+
+    let source_map = SourceMapOptions {
+        source_root: object.string("source_root"),
+        sources_relative_to: object.string("sources_relative_to").map(PathBuf::from),
+        file: object.string("source_map_file"),
+        omit_sources_content: object.truthy("sources_content") == Some(false),
+    };
+
+    let options = Options {
+        filename: object.coerced_string("filename").map(PathBuf::from),
+        inline_source_map: object.truthy("inline_source_map").unwrap_or(false),
+        flavor,
+        class_member_lowering,
+        strip_indent,
+        import_alias,
+        module_format,
+        import_placement,
+        minify: object.truthy("minify").unwrap_or(false),
+        target,
+        ascii_only: object.truthy("ascii_only").unwrap_or(false),
+        line_ending,
+        source_map,
+        debug_id: object.truthy("debug_id").unwrap_or(false),
+        hot: object.truthy("hot").unwrap_or(false),
+    };
+    if options.hot && !options.supports_hot() {
+        return Err(InvalidOption {
+            key: "hot",
+            expected: r#"false with the "precompile-template" flavor, `static_blocks: false` or "commonjs" output"#
+                .to_string(),
+        });
+    }
+    Ok(options)
+}
+
+fn tab_width(object: &impl OptionsObject) -> Result<Option<usize>, InvalidOption> {
+    let invalid = || InvalidOption {
+        key: "tab_width",
+        expected: "a positive whole number".to_string(),
+    };
+    match object.number("tab_width") {
+        Some(width) if width >= 1.0 && width.fract() == 0.0 && width <= u32::MAX as f64 => {
+            Ok(Some(width as usize))
+        }
+        Some(_) => Err(invalid()),
+        None if object.truthy("tab_width") == Some(true) => Err(invalid()),
+        None => Ok(None),
+    }
+}
+
+// The short message of the `Error` that a binding throws for a parse error.
+pub fn parse_error_message(err: &swc_ecma_parser::error::Error, source_map: &SourceMap) -> String {
+    format!("Parse Error at {}", source_map.span_to_string(err.span()))
+}
+
+// The rendered diagnostic that a binding attaches to the `Error` it throws, as
+// `source_code` (without color) and `source_code_color`.
+pub fn parse_error_detail(
+    err: swc_ecma_parser::error::Error,
+    source_map: Lrc<SourceMap>,
+    theme: GraphicalTheme,
+) -> String {
+    let mut diagnostics = ThreadSafetyDiagnostics::default();
+    let emitter = ErrorEmitter {
+        diagnostics: diagnostics.clone(),
+        cm: source_map.clone(),
+        opts: HandlerOpts::default(),
+    };
+    let handler = Handler::with_emitter(true, false, Box::new(emitter));
+    err.into_diagnostic(&handler).emit();
+    let reporter = GraphicalReportHandler::new_themed(theme);
+    diagnostics
+        .take()
+        .iter()
+        .map(|d| d.to_pretty_string(&source_map, false, &reporter))
+        .collect()
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct TestObject(std::collections::HashMap<&'static str, &'static str>);
+
+#[cfg(test)]
+impl OptionsObject for TestObject {
+    fn string(&self, key: &str) -> Option<String> {
+        self.0.get(key).map(|value| value.to_string())
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        self.0.get(key).and_then(|value| value.parse().ok())
+    }
+
+    fn truthy(&self, key: &str) -> Option<bool> {
+        self.0
+            .get(key)
+            .map(|value| !value.is_empty() && *value != "false")
+    }
+
+    fn coerced_string(&self, key: &str) -> Option<String> {
+        self.string(key).filter(|value| !value.is_empty())
+    }
+}
+
+#[test]
+fn missing_options_are_defaults() {
+    let options = options_from(&TestObject::default()).unwrap();
+    assert_eq!(options.filename, None);
+    assert_eq!(
+        options.class_member_lowering,
+        ClassMemberLowering::StaticBlock
+    );
+    assert_eq!(options.strip_indent, StripIndent::Dedent);
+    assert!(!options.source_map.omit_sources_content);
+    assert!(!options.hot);
+}
+
+#[test]
+fn reads_options() {
+    let options = options_from(&TestObject(
+        [
+            ("filename", "a.gjs"),
+            ("static_blocks", "false"),
+            ("tab_width", "4"),
+            ("module_format", "commonjs"),
+            ("sources_content", "false"),
+        ]
+        .into(),
+    ))
+    .unwrap();
+    assert_eq!(options.filename, Some("a.gjs".into()));
+    assert_eq!(
+        options.class_member_lowering,
+        ClassMemberLowering::OutsideClass
+    );
+    assert_eq!(options.strip_indent, StripIndent::DedentWithTabWidth(4));
+    assert_eq!(options.module_format, ModuleFormat::CommonJs);
+    assert!(options.source_map.omit_sources_content);
+
+    let options = options_from(&TestObject([("hot", "true")].into())).unwrap();
+    assert!(options.hot);
+}
+
+#[test]
+fn rejects_invalid_options() {
+    let error = |entries: &[(&'static str, &'static str)]| {
+        options_from(&TestObject(entries.iter().copied().collect()))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(&[("strip_indent", "dedent-all")]),
+        r#"Invalid option `strip_indent`: expected one of "off", "trim-only", "dedent""#
+    );
+    assert!(error(&[("target", "es2023")])
+        .starts_with(r#"Invalid option `target`: expected one of "es3", "es5", "es2015","#));
+    assert_eq!(
+        error(&[("module_format", "cjs")]),
+        r#"Invalid option `module_format`: expected one of "module", "commonjs""#
+    );
+    assert_eq!(
+        error(&[("line_ending", "CRLF")]),
+        r#"Invalid option `line_ending`: expected one of "lf", "crlf", "preserve""#
+    );
+    for option in [
+        ("flavor", "precompile-template"),
+        ("static_blocks", "false"),
+        ("module_format", "commonjs"),
+    ] {
+        assert!(
+            error(&[("hot", "true"), option]).starts_with("Invalid option `hot`: expected false")
+        );
+    }
+    for width in ["-2", "0", "1.5", "NaN", "Infinity"] {
+        assert_eq!(
+            error(&[("tab_width", width)]),
+            "Invalid option `tab_width`: expected a positive whole number"
+        );
+    }
+    for strip_indent in ["off", "trim-only"] {
+        assert_eq!(
+            options_from(&TestObject(
+                [("strip_indent", strip_indent), ("tab_width", "4")].into()
+            ))
+            .unwrap_err()
+            .key,
+            "tab_width"
+        );
+    }
+}
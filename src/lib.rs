@@ -4,31 +4,207 @@
 extern crate lazy_static;
 
 use base64::{engine::general_purpose, Engine as _};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use swc_common::comments::SingleThreadedComments;
 use swc_common::source_map::SourceMapGenConfig;
-use swc_common::{self, sync::Lrc, FileName, Mark, SourceMap};
+use swc_common::{self, sync::Lrc, FileName, Mark, SourceFile, SourceMap, Span};
 use swc_core::common::GLOBALS;
-use swc_ecma_ast::{
-    Ident, ImportDecl, ImportNamedSpecifier, ImportSpecifier, Module, ModuleDecl, ModuleExportName,
-    ModuleItem,
-};
+use swc_ecma_ast::Module;
+use swc_ecma_codegen::text_writer::{omit_trailing_semi, JsWriter, WriteJs};
 use swc_ecma_codegen::Emitter;
 use swc_ecma_parser::TsSyntax;
-use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
+use swc_ecma_parser::{lexer::Lexer, Capturing, Parser, StringInput, Syntax};
 use swc_ecma_transforms::resolver;
-use swc_ecma_utils::private_ident;
 use swc_ecma_visit::{VisitMutWith, VisitWith};
+use uuid::Uuid;
 
+pub use cross_references::{BindingReferences, CrossReferences, TemplateReferenceLink};
+pub use document::Document;
+pub use import_usage::{ImportUsage, UnresolvedReference, UnusedImport};
+pub use semantic_tokens::{SemanticTokens, TOKEN_TYPES};
+pub use swc_ecma_ast::EsVersion;
+pub use template_refs::ReferenceKind;
+pub use virtual_documents::{VirtualDocument, VirtualDocumentKind, VirtualDocuments};
+
+// the wasm binding; a native Node build exports `node`'s instead
+#[cfg(not(feature = "node"))]
 mod bindings;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod cross_references;
+mod document;
+mod estree;
+mod hot_reload;
+mod import_usage;
+mod imports;
+mod js_options;
+pub mod lint_blocks;
 mod locate;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "node")]
+mod node;
+mod semantic_tokens;
 mod snippets;
+pub mod template_format;
+pub mod template_lint;
+mod template_refs;
+mod template_scope;
+pub mod template_syntax;
 mod transform;
+mod virtual_documents;
 
 #[derive(Default)]
 pub struct Options {
     pub filename: Option<PathBuf>,
     pub inline_source_map: bool,
+    pub flavor: OutputFlavor,
+    pub class_member_lowering: ClassMemberLowering,
+    pub strip_indent: StripIndent,
+    pub import_alias: ImportAlias,
+    pub module_format: ModuleFormat,
+    pub import_placement: ImportPlacement,
+    // compact output, without the whitespace and formatting of the default
+    pub minify: bool,
+    // a hint for the code generator about how to print things like string
+    // escapes; it doesn't down-level syntax, so static blocks and class
+    // fields are printed whatever the target
+    pub target: EsVersion,
+    // escape non-ASCII characters in strings and identifiers
+    pub ascii_only: bool,
+    pub line_ending: LineEnding,
+    pub source_map: SourceMapOptions,
+    // add a debug ID (https://github.com/tc39/ecma426/blob/main/proposals/debug-id.md)
+    // to both the code and the map, derived from the input and filename so
+    // that rebuilding the same file gives the same ID
+    pub debug_id: bool,
+    // for development servers: give each module-level template an identity
+    // (filename, occurrence index and content hash) and add an
+    // `import.meta.hot` hook that swaps changed templates into the components
+    // already in use instead of reloading the module. See hot_reload.rs. Only
+    // works with the options that `supports_hot` accepts: `process` panics
+    // with any others, and the JS bindings and the C ABI reject them.
+    pub hot: bool,
+}
+
+impl Options {
+    // Whether `hot` can be set with the rest of these options. Hot reloading
+    // swaps the templates that `template()` sets from static blocks and from
+    // module-level ES module code.
+    pub fn supports_hot(&self) -> bool {
+        self.flavor == OutputFlavor::Template
+            && self.class_member_lowering == ClassMemberLowering::StaticBlock
+            && self.module_format == ModuleFormat::Module
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct SourceMapOptions {
+    // written as the map's `sourceRoot`
+    pub source_root: Option<String>,
+    // write `sources` relative to this directory instead of as the filename
+    // was given, which is usually an absolute path
+    pub sources_relative_to: Option<PathBuf>,
+    // written as the map's `file`
+    pub file: Option<String>,
+    // leave the original source out of the map
+    pub omit_sources_content: bool,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    // `Crlf` when the input contains a `\r\n`, `Lf` otherwise
+    Preserve,
+}
+
+impl LineEnding {
+    fn for_input(self, src: &str) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Preserve if src.contains("\r\n") => "\r\n",
+            LineEnding::Preserve => "\n",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFlavor {
+    // `template()` from `@ember/template-compiler`
+    #[default]
+    Template,
+    // `precompileTemplate()` from `@ember/template-compilation`, set on its
+    // component with `setComponentTemplate()` from `@ember/component`: the
+    // class for class members, `templateOnly()` from
+    // `@ember/component/template-only` for expressions. This is for Ember
+    // versions that predate the runtime template compiler API.
+    PrecompileTemplate,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClassMemberLowering {
+    // `class X { static { template(..., { component: this }) } }`
+    #[default]
+    StaticBlock,
+    // `class X {}; template(..., { component: X })`, for runtimes and bundlers
+    // without class static blocks. The `PrecompileTemplate` flavor always
+    // lowers this way.
+    OutsideClass,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleFormat {
+    // `import { template } from "@ember/template-compiler"` and
+    // `export default template(...)`
+    #[default]
+    Module,
+    // `const { template } = require("@ember/template-compiler")` and
+    // `exports.default = template(...)`, marked with `__esModule` for
+    // Babel-style interop. The module's own imports and exports are left
+    // alone.
+    CommonJs,
+}
+
+// Where a generated import goes when the module doesn't already import from
+// the same package. Either way it stays below any directive prologue
+// (`"use client"`) and leading license comments.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportPlacement {
+    // before everything else
+    #[default]
+    Start,
+    // after the last existing import
+    AfterImports,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportAlias {
+    // the shortest name that doesn't collide with anything in the module,
+    // like `template` or `template1`
+    #[default]
+    Readable,
+    // the fixed `IMPORT_ALIAS` style names, for tools that look for them
+    Fixed,
+}
+
+// How whitespace around and inside template contents is normalized before
+// the template is handed to the compiler.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StripIndent {
+    // contents are passed through untouched
+    Off,
+    // only blank leading and trailing lines are removed
+    TrimOnly,
+    // blank leading and trailing lines and common indentation are removed,
+    // unless the indentation mixes tabs and spaces
+    #[default]
+    Dedent,
+    // like `Dedent`, but tabs count up to the next multiple of the given
+    // width, so mixed indentation can be compared
+    DedentWithTabWidth(usize),
 }
 
 pub struct Preprocessor {
@@ -36,24 +212,71 @@ pub struct Preprocessor {
     comments: SingleThreadedComments,
 }
 
+// A module parsed by `Preprocessor::parse_module`.
+struct ParsedModule {
+    module: Module,
+    source_file: Lrc<SourceFile>,
+    // the spans of the tokens the lexer produced, in source order
+    tokens: Vec<Span>,
+}
+
 pub struct CodeMapPair {
     pub code: String,
     pub map: String,
 }
 
 pub const IMPORT_ALIAS: &str = "template_fd9b2463e5f141cfb5666b64daa1f11a";
+pub const PRECOMPILE_TEMPLATE_ALIAS: &str = "precompileTemplate_fd9b2463e5f141cfb5666b64daa1f11a";
+pub const SET_COMPONENT_TEMPLATE_ALIAS: &str =
+    "setComponentTemplate_fd9b2463e5f141cfb5666b64daa1f11a";
+pub const TEMPLATE_ONLY_ALIAS: &str = "templateOnly_fd9b2463e5f141cfb5666b64daa1f11a";
 
-struct SourceMapConfig;
-impl SourceMapGenConfig for SourceMapConfig {
+struct SourceMapConfig<'a> {
+    options: &'a SourceMapOptions,
+}
+impl<'a> SourceMapGenConfig for SourceMapConfig<'a> {
     fn file_name_to_source(&self, f: &swc_common::FileName) -> String {
-        f.to_string()
+        match (f, &self.options.sources_relative_to) {
+            (FileName::Real(path), Some(base)) => relative_path(path, base),
+            _ => f.to_string(),
+        }
     }
 
     fn inline_sources_content(&self, _: &swc_common::FileName) -> bool {
-        true
+        !self.options.omit_sources_content
     }
 }
 
+const DEBUG_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c2a4e_8b3d_4c5a_9e7f_0d2b4a6c8e1f);
+
+fn debug_id(src: &str, filename: Option<&Path>) -> Uuid {
+    let mut name = filename
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .into_bytes();
+    name.push(0);
+    name.extend_from_slice(src.as_bytes());
+    Uuid::new_v5(&DEBUG_ID_NAMESPACE, &name)
+}
+
+// `path` relative to the directory `base`, with `/` separators as source maps
+// expect.
+fn relative_path(path: &Path, base: &Path) -> String {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    base[common..]
+        .iter()
+        .map(|_| "..".to_string())
+        .chain(
+            path[common..]
+                .iter()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 impl Preprocessor {
     pub fn new() -> Self {
         Self {
@@ -62,13 +285,16 @@ impl Preprocessor {
         }
     }
 
-    pub fn parse(
+    // Parses `src` as TypeScript with `<template>` tags, recording its comments
+    // in `self.comments`. Call it inside `GLOBALS.set`, together with
+    // whatever uses the module.
+    fn parse_module(
         &self,
         src: &str,
-        options: Options,
-    ) -> Result<Vec<locate::Occurrence>, swc_ecma_parser::error::Error> {
-        let filename = match options.filename {
-            Some(name) => FileName::Real(name),
+        options: &Options,
+    ) -> Result<ParsedModule, swc_ecma_parser::error::Error> {
+        let filename = match &options.filename {
+            Some(name) => FileName::Real(name.clone()),
             None => FileName::Anon,
         };
 
@@ -76,7 +302,7 @@ impl Preprocessor {
             .source_map
             .new_source_file(filename.into(), src.to_string());
 
-        let lexer = Lexer::new(
+        let lexer = Capturing::new(Lexer::new(
             Syntax::Typescript(TsSyntax {
                 decorators: true,
                 ..Default::default()
@@ -84,28 +310,118 @@ impl Preprocessor {
             Default::default(),
             StringInput::from(&*source_file),
             Some(&self.comments),
-        );
+        ));
+        let tokens = lexer.tokens().clone();
         let mut parser = Parser::new_from(lexer);
+        let module = parser.parse_module()?;
+        let tokens = tokens.borrow().iter().map(|token| token.span).collect();
+
+        Ok(ParsedModule {
+            module,
+            source_file,
+            tokens,
+        })
+    }
+
+    pub fn parse(
+        &self,
+        src: &str,
+        options: Options,
+    ) -> Result<Vec<locate::Occurrence>, swc_ecma_parser::error::Error> {
         GLOBALS.set(&Default::default(), || {
-            let parsed_module = parser.parse_module()?;
+            let parsed = self.parse_module(src, &options)?;
 
-            let mut visitor = locate::LocateContentTagVisitor::new(src.to_string());
+            let mut visitor = locate::LocateContentTagVisitor::new(src.to_string())
+                .with_strip_indent(options.strip_indent);
 
-            parsed_module.visit_with(&mut visitor);
+            parsed.module.visit_with(&mut visitor);
 
             Ok(visitor.occurrences)
         })
     }
 
+    // Links every name that a template refers to with the JS declaration it
+    // resolves to. See cross_references.rs.
+    pub fn cross_references(
+        &self,
+        src: &str,
+        options: Options,
+    ) -> Result<CrossReferences, swc_ecma_parser::error::Error> {
+        GLOBALS.set(&Default::default(), || {
+            let mut module = self.parse_module(src, &options)?.module;
+            let kinds = template_scope::expose_template_references(&mut module);
+            template_scope::resolve(&mut module);
+            Ok(cross_references::analyze(src, &module, &kinds))
+        })
+    }
+
+    // The imports that neither JS nor any template uses, and the names that
+    // templates use without a binding for them. See import_usage.rs.
+    pub fn import_usage(
+        &self,
+        src: &str,
+        options: Options,
+    ) -> Result<ImportUsage, swc_ecma_parser::error::Error> {
+        GLOBALS.set(&Default::default(), || {
+            let mut module = self.parse_module(src, &options)?.module;
+            let kinds = template_scope::expose_template_references(&mut module);
+            let unresolved_mark = template_scope::resolve(&mut module);
+            Ok(import_usage::analyze(src, &module, &kinds, unresolved_mark))
+        })
+    }
+
+    // The whole module as ESTree JSON, with `<template>` tags as
+    // `GlimmerTemplate` nodes. See estree.rs for the format.
+    pub fn parse_estree(
+        &self,
+        src: &str,
+        options: Options,
+    ) -> Result<serde_json::Value, swc_ecma_parser::error::Error> {
+        GLOBALS.set(&Default::default(), || {
+            let parsed = self.parse_module(src, &options)?;
+
+            Ok(estree::program(
+                src,
+                parsed.source_file.start_pos,
+                &parsed.module,
+                &self.comments,
+                &parsed.tokens,
+            ))
+        })
+    }
+
+    // LSP semantic tokens for the whole file, JS and templates. See
+    // semantic_tokens.rs for the encoding.
+    pub fn semantic_tokens(
+        &self,
+        src: &str,
+        options: Options,
+    ) -> Result<SemanticTokens, swc_ecma_parser::error::Error> {
+        GLOBALS.set(&Default::default(), || {
+            let parsed = self.parse_module(src, &options)?;
+
+            Ok(semantic_tokens::semantic_tokens(
+                src,
+                parsed.source_file.start_pos,
+                &parsed.module,
+                &self.comments,
+                &parsed.tokens,
+            ))
+        })
+    }
+
     pub fn process(
         &self,
         src: &str,
         options: Options,
     ) -> Result<CodeMapPair, swc_ecma_parser::error::Error> {
-        let target_specifier = "template";
-        let target_module = "@ember/template-compiler";
-        let filename = match options.filename {
-            Some(name) => FileName::Real(name),
+        assert!(
+            !options.hot || options.supports_hot(),
+            "`hot` needs the `template()` flavor, static blocks and ES module output"
+        );
+
+        let filename = match &options.filename {
+            Some(name) => FileName::Real(name.clone()),
             None => FileName::Anon,
         };
 
@@ -126,15 +442,107 @@ impl Preprocessor {
         GLOBALS.set(&Default::default(), || {
             let mut parsed_module = parser.parse_module()?;
 
-            let id = private_ident!(IMPORT_ALIAS);
-            let mut needs_import = false;
-            parsed_module.visit_mut_with(&mut transform::TransformVisitor::new(
-                &id,
-                Some(&mut needs_import),
-            ));
+            let taken_names = match options.import_alias {
+                ImportAlias::Readable => Some(imports::taken_names(&parsed_module)),
+                ImportAlias::Fixed => None,
+            };
+
+            match options.flavor {
+                OutputFlavor::Template => {
+                    let template = imports::ImportBinding::find_or_create(
+                        &parsed_module,
+                        "@ember/template-compiler",
+                        "template",
+                        IMPORT_ALIAS,
+                    );
+                    let mut needs_import = false;
+                    let mut visitor =
+                        transform::TransformVisitor::new(&template.local, Some(&mut needs_import))
+                            .with_options(&options);
+                    if options.hot {
+                        let filename = options
+                            .filename
+                            .as_deref()
+                            .map(|f| f.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        visitor = visitor.with_hot_reload(hot_reload::HotReload::new(
+                            &parsed_module,
+                            src,
+                            source_file.start_pos,
+                            &filename,
+                        ));
+                    }
+                    parsed_module.visit_mut_with(&mut visitor);
+
+                    if needs_import {
+                        template.insert(
+                            &mut parsed_module,
+                            options.module_format,
+                            options.import_placement,
+                            &self.comments,
+                        );
+                    }
+                }
+                OutputFlavor::PrecompileTemplate => {
+                    let precompile_template = imports::ImportBinding::find_or_create(
+                        &parsed_module,
+                        "@ember/template-compilation",
+                        "precompileTemplate",
+                        PRECOMPILE_TEMPLATE_ALIAS,
+                    );
+                    let set_component_template = imports::ImportBinding::find_or_create(
+                        &parsed_module,
+                        "@ember/component",
+                        "setComponentTemplate",
+                        SET_COMPONENT_TEMPLATE_ALIAS,
+                    );
+                    let template_only = imports::ImportBinding::find_or_create(
+                        &parsed_module,
+                        "@ember/component/template-only",
+                        "default",
+                        TEMPLATE_ONLY_ALIAS,
+                    );
+                    let mut needs_precompile = false;
+                    let mut needs_set_component_template = false;
+                    let mut needs_template_only = false;
+                    let mut visitor = transform::TransformVisitor::new(
+                        &precompile_template.local,
+                        Some(&mut needs_precompile),
+                    )
+                    .with_options(&options)
+                    .with_precompile_template(
+                        &set_component_template.local,
+                        Some(&mut needs_set_component_template),
+                        &template_only.local,
+                        Some(&mut needs_template_only),
+                    );
+                    parsed_module.visit_mut_with(&mut visitor);
 
-            if needs_import {
-                insert_import(&mut parsed_module, target_module, target_specifier, &id)
+                    if needs_template_only {
+                        template_only.insert(
+                            &mut parsed_module,
+                            options.module_format,
+                            options.import_placement,
+                            &self.comments,
+                        );
+                    }
+                    if needs_set_component_template {
+                        set_component_template.insert(
+                            &mut parsed_module,
+                            options.module_format,
+                            options.import_placement,
+                            &self.comments,
+                        );
+                    }
+                    if needs_precompile {
+                        precompile_template.insert(
+                            &mut parsed_module,
+                            options.module_format,
+                            options.import_placement,
+                            &self.comments,
+                        );
+                    }
+                }
             }
 
             let unresolved_mark = Mark::new();
@@ -142,41 +550,90 @@ impl Preprocessor {
 
             parsed_module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
 
-            let codemap = self.print(&parsed_module, options.inline_source_map);
+            if let Some(mut taken_names) = taken_names {
+                imports::rename_aliases(
+                    &mut parsed_module,
+                    &[
+                        (IMPORT_ALIAS, "template"),
+                        (PRECOMPILE_TEMPLATE_ALIAS, "precompileTemplate"),
+                        (SET_COMPONENT_TEMPLATE_ALIAS, "setComponentTemplate"),
+                        (TEMPLATE_ONLY_ALIAS, "templateOnly"),
+                    ],
+                    &mut taken_names,
+                );
+                if options.hot {
+                    imports::rename_aliases(
+                        &mut parsed_module,
+                        hot_reload::ALIASES,
+                        &mut taken_names,
+                    );
+                }
+            }
+
+            let codemap = self.print(&parsed_module, src, &options);
 
             Ok(codemap)
         })
     }
 
-    fn print(&self, module: &Module, inline_source_map: bool) -> CodeMapPair {
+    fn print(&self, module: &Module, src: &str, options: &Options) -> CodeMapPair {
         let mut buf = vec![];
         let mut srcmap = vec![];
         let mut source_map_buffer = vec![];
+        let writer = JsWriter::new(
+            self.source_map().clone(),
+            options.line_ending.for_input(src),
+            &mut buf,
+            Some(&mut srcmap),
+        );
+        let wr: Box<dyn WriteJs> = if options.minify {
+            Box::new(omit_trailing_semi(writer))
+        } else {
+            Box::new(writer)
+        };
         let mut emitter = Emitter {
-            cfg: Default::default(),
+            cfg: swc_ecma_codegen::Config::default()
+                .with_minify(options.minify)
+                .with_target(options.target)
+                .with_ascii_only(options.ascii_only),
             cm: self.source_map.clone(),
-            wr: swc_ecma_codegen::text_writer::JsWriter::new(
-                self.source_map().clone(),
-                "\n",
-                &mut buf,
-                Some(&mut srcmap),
-            ),
+            wr,
             comments: Some(&self.comments),
         };
         emitter.emit_module(module).unwrap();
+        drop(emitter);
 
-        self.source_map()
-            .build_source_map(&srcmap, None, SourceMapConfig {})
-            .to_writer(&mut source_map_buffer)
-            .unwrap();
+        let mut map = self.source_map().build_source_map(
+            &srcmap,
+            None,
+            SourceMapConfig {
+                options: &options.source_map,
+            },
+        );
+        map.set_source_root(options.source_map.source_root.clone());
+        map.set_file(options.source_map.file.clone());
+        map.to_writer(&mut source_map_buffer).unwrap();
 
-        if inline_source_map {
+        if options.debug_id {
+            let id = debug_id(src, options.filename.as_deref());
+            let mut json: serde_json::Value = serde_json::from_slice(&source_map_buffer).unwrap();
+            json["debugId"] = id.to_string().into();
+            source_map_buffer = serde_json::to_vec(&json).unwrap();
+
+            let newline = options.line_ending.for_input(src);
+            if !buf.ends_with(b"\n") {
+                buf.extend_from_slice(newline.as_bytes());
+            }
+            buf.extend_from_slice(format!("//# debugId={id}{newline}").as_bytes());
+        }
+
+        if options.inline_source_map {
             let mut comment = "//# sourceMappingURL=data:application/json;base64,"
                 .to_owned()
                 .into_bytes();
             buf.append(&mut comment);
 
-            let mut encoded = general_purpose::URL_SAFE_NO_PAD
+            let mut encoded = general_purpose::STANDARD
                 .encode(source_map_buffer.clone())
                 .into_bytes();
 
@@ -196,33 +653,6 @@ impl Preprocessor {
     }
 }
 
-fn insert_import(
-    parsed_module: &mut Module,
-    target_module: &str,
-    target_specifier: &str,
-    local: &Ident,
-) {
-    parsed_module.body.insert(
-        0,
-        ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
-            span: Default::default(),
-            specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
-                span: Default::default(),
-                local: local.clone(),
-                imported: Some(ModuleExportName::Ident(Ident::new_no_ctxt(
-                    target_specifier.into(),
-                    Default::default(),
-                ))),
-                is_type_only: false,
-            })],
-            src: Box::new(target_module.into()),
-            type_only: false,
-            with: None,
-            phase: Default::default(),
-        })),
-    );
-}
-
 #[cfg(test)]
 mod test_helpers;
 
@@ -233,31 +663,137 @@ macro_rules! testcase {
             test_helpers::testcase($input, $expected)
         }
     };
+    ($test_name:ident, $options:expr, $input:expr, $expected:expr) => {
+        #[test]
+        fn $test_name() -> Result<(), swc_ecma_parser::error::Error> {
+            test_helpers::testcase_with_options($input, $options, $expected)
+        }
+    };
 }
 
 testcase! {
   no_preexisting_import,
   r#"let x = <template>hello</template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} });"#
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
 }
 
 testcase! {
   preexisting_import,
   r#"import { template } from "@ember/template-compiler";
      let x = <template>hello</template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-     import { template } from "@ember/template-compiler";
-     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} });"#
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
 }
 
 testcase! {
   preexisting_renamed_import,
   r#"import { template as t } from "@ember/template-compiler";
      let x = <template>hello</template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-     import { template as t } from "@ember/template-compiler";
-     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} })"#
+  r#"import { template as t } from "@ember/template-compiler";
+     let x = t(`hello`, { eval() { return eval(arguments[0])} })"#
+}
+
+testcase! {
+  preexisting_shadowed_import,
+  r#"import { template } from "@ember/template-compiler";
+     export default function (template) {
+       return <template>hello</template>;
+     }"#,
+  r#"import { template, template as template1 } from "@ember/template-compiler";
+     export default function(template) {
+       return template1(`hello`, { eval() { return eval(arguments[0])} });
+     }"#
+}
+
+testcase! {
+  preexisting_type_only_import,
+  r#"import type { template } from "@ember/template-compiler";
+     let x = <template>hello</template>"#,
+  r#"import { template as template1 } from "@ember/template-compiler";
+     import type { template } from "@ember/template-compiler";
+     let x = template1(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  merges_into_preexisting_import_declaration,
+  r#"import { other } from "@ember/template-compiler";
+     let x = <template>hello</template>"#,
+  r#"import { other, template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  preexisting_import_among_other_specifiers,
+  r#"import { other, template } from "@ember/template-compiler";
+     export default class extends Component {
+       <template>hello</template>
+     }"#,
+  r#"import { other, template } from "@ember/template-compiler";
+     export default class extends Component {
+       static {
+         template(`hello`, { component: this, eval() { return eval(arguments[0])} });
+       }
+     }"#
+}
+
+testcase! {
+  merges_into_preexisting_default_import,
+  r#"import compiler from "@ember/template-compiler";
+     let x = <template>hello</template>"#,
+  r#"import compiler, { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  does_not_merge_into_namespace_import,
+  r#"import * as compiler from "@ember/template-compiler";
+     let x = <template>hello</template>"#,
+  r#"import { template } from "@ember/template-compiler";
+     import * as compiler from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  preexisting_renamed_import_in_nested_scope,
+  r#"import { template as t } from "@ember/template-compiler";
+     export function make() {
+       return <template>hello</template>;
+     }"#,
+  r#"import { template as t } from "@ember/template-compiler";
+     export function make() {
+       return t(`hello`, { eval() { return eval(arguments[0])} });
+     }"#
+}
+
+testcase! {
+  precompile_template_flavor_reuses_preexisting_imports,
+  Options { flavor: OutputFlavor::PrecompileTemplate, ..Default::default() },
+  r#"import { precompileTemplate as compile } from "@ember/template-compilation";
+     import { setComponentTemplate } from "@ember/component";
+     import templateOnly from "@ember/component/template-only";
+     export default <template>hello</template>"#,
+  r#"import { precompileTemplate as compile } from "@ember/template-compilation";
+     import { setComponentTemplate } from "@ember/component";
+     import templateOnly from "@ember/component/template-only";
+     export default setComponentTemplate(compile(`hello`, { strictMode: true, scope: () => ({}) }), templateOnly());"#
+}
+
+testcase! {
+  precompile_template_flavor_merges_into_preexisting_imports,
+  Options { flavor: OutputFlavor::PrecompileTemplate, ..Default::default() },
+  r#"import Component from "@ember/component";
+     import { other } from "@ember/component/template-only";
+     export const Hello = <template>hello</template>;
+     export default class extends Component {
+       <template><Hello /></template>
+     }"#,
+  r#"import { precompileTemplate } from "@ember/template-compilation";
+     import Component, { setComponentTemplate } from "@ember/component";
+     import templateOnly, { other } from "@ember/component/template-only";
+     export const Hello = setComponentTemplate(precompileTemplate(`hello`, { strictMode: true, scope: () => ({}) }), templateOnly());
+     export default setComponentTemplate(precompileTemplate(`<Hello />`, { strictMode: true, scope: () => ({ Hello }) }), class extends Component {
+     });"#
 }
 
 testcase! {
@@ -271,10 +807,10 @@ testcase! {
   r#"function template() {};
      console.log(template());
      export default <template>Hi</template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
+  r#"import { template as template1 } from "@ember/template-compiler";
      function template() {};
      console.log(template());
-     export default template_UUID(`Hi`, { eval() { return eval(arguments[0])} });"#
+     export default template1(`Hi`, { eval() { return eval(arguments[0])} });"#
 }
 
 testcase! {
@@ -283,10 +819,10 @@ testcase! {
          console.log(template);
          return <template>X</template>;
        };"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
+  r#"import { template as template1 } from "@ember/template-compiler";
        export default function(template) {
          console.log(template);
-         return template_UUID(`X`, { eval() { return eval(arguments[0])} });
+         return template1(`X`, { eval() { return eval(arguments[0])} });
        };"#
 }
 
@@ -296,18 +832,18 @@ testcase! {
         console.log(message);
         return <template>hello</template>
     }"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
+  r#"import { template } from "@ember/template-compiler";
        function makeComponent(message: string) {
          console.log(message);
-         return template_UUID(`hello`, { eval() { return eval(arguments[0]) } });
+         return template(`hello`, { eval() { return eval(arguments[0]) } });
        }"#
 }
 
 testcase! {
   handles_typescript_implied_export_default,
   r#"<template>hello</template> satisfies MyType"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-export default template_UUID(`hello`, {
+  r#"import { template } from "@ember/template-compiler";
+export default template(`hello`, {
     eval () {
         return eval(arguments[0]);
     }
@@ -320,11 +856,11 @@ testcase! {
         function t(this: Context, ...args: unknown[]) {};
         return <template></template>
     }"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
+  r#"import { template } from "@ember/template-compiler";
        function f(this: Context, ...args: unknown[]) {
          function t(this: Context, ...args: unknown[]) {}
          ;
-         return template_UUID(``, { eval() { return eval(arguments[0]) } });
+         return template(``, { eval() { return eval(arguments[0]) } });
        }"#
 }
 
@@ -335,13 +871,240 @@ fn preserves_type_reexport() -> Result<(), swc_ecma_parser::error::Error> {
     test_helpers::testcase_substr("export { type X } from 'elsewhere';", "type X")
 }
 
+#[test]
+fn minified_output() -> Result<(), swc_ecma_parser::error::Error> {
+    let output = Preprocessor::new().process(
+        "let x = <template>hello</template>;\nexport default x;\n",
+        Options {
+            minify: true,
+            ..Default::default()
+        },
+    )?;
+    assert!(!output.code.contains('\n'), "{}", output.code);
+    assert!(
+        output.code.contains("template(`hello`,{eval(){"),
+        "{}",
+        output.code
+    );
+    Ok(())
+}
+
+#[test]
+fn ascii_only_output() -> Result<(), swc_ecma_parser::error::Error> {
+    let output = Preprocessor::new().process(
+        "const greeting = \"héllo\";\n<template>{{greeting}}</template>",
+        Options {
+            ascii_only: true,
+            ..Default::default()
+        },
+    )?;
+    assert!(!output.code.contains('é'), "{}", output.code);
+    Ok(())
+}
+
+#[test]
+fn line_endings() -> Result<(), swc_ecma_parser::error::Error> {
+    let src = "let x = <template>hello</template>;\r\nexport default x;\r\n";
+    let p = Preprocessor::new();
+
+    let preserved = p.process(
+        src,
+        Options {
+            line_ending: LineEnding::Preserve,
+            ..Default::default()
+        },
+    )?;
+    assert!(preserved.code.contains("\r\n"), "{:?}", preserved.code);
+    assert_eq!(
+        preserved.code.matches('\n').count(),
+        preserved.code.matches("\r\n").count(),
+        "{:?}",
+        preserved.code
+    );
+
+    let default = p.process(src, Default::default())?;
+    assert!(!default.code.contains('\r'), "{:?}", default.code);
+    Ok(())
+}
+
+#[test]
+fn source_map_options() -> Result<(), swc_ecma_parser::error::Error> {
+    let output = Preprocessor::new().process(
+        "<template>hello</template>",
+        Options {
+            filename: Some("/ci/build/app/components/hello.gjs".into()),
+            source_map: SourceMapOptions {
+                source_root: Some("/app".to_string()),
+                sources_relative_to: Some("/ci/build/app".into()),
+                file: Some("hello.js".to_string()),
+                omit_sources_content: true,
+            },
+            ..Default::default()
+        },
+    )?;
+    let map: serde_json::Value = serde_json::from_str(&output.map).unwrap();
+    assert_eq!(map["sourceRoot"], "/app");
+    assert_eq!(map["file"], "hello.js");
+    assert_eq!(map["sources"], serde_json::json!(["components/hello.gjs"]));
+    assert!(map.get("sourcesContent").is_none(), "{}", output.map);
+    Ok(())
+}
+
+#[test]
+fn relative_sources_outside_base() {
+    assert_eq!(
+        relative_path(Path::new("/repo/src/a.gjs"), Path::new("/repo/dist")),
+        "../src/a.gjs"
+    );
+}
+
+#[test]
+fn inline_source_map_uses_standard_base64() -> Result<(), swc_ecma_parser::error::Error> {
+    let output = Preprocessor::new().process(
+        "<template>hello</template>",
+        Options {
+            inline_source_map: true,
+            ..Default::default()
+        },
+    )?;
+    let (_, encoded) = output
+        .code
+        .split_once("//# sourceMappingURL=data:application/json;base64,")
+        .unwrap();
+    let decoded = general_purpose::STANDARD.decode(encoded.trim()).unwrap();
+    assert_eq!(String::from_utf8(decoded).unwrap(), output.map);
+    Ok(())
+}
+
+#[test]
+fn debug_ids() -> Result<(), swc_ecma_parser::error::Error> {
+    let process = |filename: &str| {
+        Preprocessor::new().process(
+            "<template>hello</template>",
+            Options {
+                filename: Some(filename.into()),
+                debug_id: true,
+                ..Default::default()
+            },
+        )
+    };
+    let output = process("hello.gjs")?;
+    let map: serde_json::Value = serde_json::from_str(&output.map).unwrap();
+    let id = map["debugId"].as_str().unwrap();
+    assert!(
+        output.code.ends_with(&format!("\n//# debugId={id}\n")),
+        "{}",
+        output.code
+    );
+
+    assert_eq!(process("hello.gjs")?.code, output.code);
+    assert_ne!(process("goodbye.gjs")?.code, output.code);
+    Ok(())
+}
+
+#[test]
+fn hot_reload() -> Result<(), swc_ecma_parser::error::Error> {
+    let process = |src: &str| {
+        Preprocessor::new()
+            .process(
+                src,
+                Options {
+                    filename: Some("app/hello.gjs".into()),
+                    hot: true,
+                    ..Default::default()
+                },
+            )
+            .map(|output| output.code)
+    };
+    let quoted_after = |code: &str, prefix: &str| {
+        let start = code.find(prefix).unwrap() + prefix.len();
+        code[start..start + 8].to_string()
+    };
+    let id = |code: &str| quoted_after(code, r#""app/hello.gjs:0:"#);
+    let js = |code: &str| quoted_after(code, r#"const js = ""#);
+
+    let code = process("class Hello {\n  <template>hello</template>\n}")?;
+    assert!(
+        code.contains(" contentTagHotClass(import.meta.hot, \"app/hello.gjs:0:"),
+        "{code}"
+    );
+    assert!(code.contains("import.meta.hot.accept();"), "{code}");
+    assert!(
+        code.contains("import { getComponentTemplate, setComponentTemplate } from"),
+        "{code}"
+    );
+    // the class gets its slot's template, and `template()` doesn't set one too
+    assert!(!code.contains("component: this"), "{code}");
+
+    // editing the template changes only its identity
+    let template_edit = process("class Hello {\n  <template>goodbye</template>\n}")?;
+    assert_ne!(id(&template_edit), id(&code));
+    assert_eq!(js(&template_edit), js(&code));
+
+    // editing the JS changes only the module's hash
+    let js_edit = process("class Hello {\n  x = 1;\n  <template>hello</template>\n}")?;
+    assert_eq!(id(&js_edit), id(&code));
+    assert_ne!(js(&js_edit), js(&code));
+
+    // a module-level template is one component across reloads
+    let code = process("<template>hello</template>")?;
+    assert!(code.contains("import.meta.hot.accept();"), "{code}");
+    assert!(
+        code.contains(" contentTagHotTemplate(import.meta.hot, \"app/hello.gjs:0:"),
+        "{code}"
+    );
+
+    // the helpers don't shadow the module's own bindings
+    let code = process(
+        "import { setComponentTemplate } from \"@ember/component\";\nconst contentTagHotSlot = 1;\n<template>hello</template>",
+    )?;
+    assert!(
+        code.contains("function contentTagHotSlot1(hot, id, compiled)"),
+        "{code}"
+    );
+    assert!(
+        code.contains("setComponentTemplate as setComponentTemplate1"),
+        "{code}"
+    );
+    assert!(code.contains("const contentTagHotSlot = 1;"), "{code}");
+
+    // a template in a function counts as JS, so without others there is no
+    // hook, and editing it changes the module's hash
+    let nested = |greeting: &str| {
+        process(&format!(
+            "export function hello() {{\n  return <template>{greeting}</template>;\n}}"
+        ))
+    };
+    assert!(!nested("hello")?.contains("import.meta.hot"));
+    let with_class = |greeting: &str| {
+        process(&format!(
+            "class Hello {{\n  <template>hi</template>\n}}\nfunction f() {{\n  return <template>{greeting}</template>;\n}}"
+        ))
+    };
+    assert_ne!(js(&with_class("hello")?), js(&with_class("goodbye")?));
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "`hot` needs")]
+fn hot_reload_unsupported() {
+    let _ = Preprocessor::new().process(
+        "<template>hello</template>",
+        Options {
+            hot: true,
+            flavor: OutputFlavor::PrecompileTemplate,
+            ..Default::default()
+        },
+    );
+}
+
 testcase! {
   extraneous_indentation_strip,
   r#"let x = <template>
     hello
   </template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} });"#
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
 }
 
 testcase! {
@@ -354,11 +1117,269 @@ testcase! {
       content
     </div>
   </template>"#,
-  r#"import { template as template_UUID } from "@ember/template-compiler";
-     let x = template_UUID(`hello
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`hello
 
 extra line break
 <div>
   content
 </div>`, { eval() { return eval(arguments[0])} });"#
 }
+
+testcase! {
+  precompile_template_flavor,
+  Options { flavor: OutputFlavor::PrecompileTemplate, ..Default::default() },
+  r#"import Greeting from "./greeting";
+     export const Hello = <template><Greeting @name="world" /></template>;
+     export default class extends Component {
+       <template><Hello /></template>
+     }"#,
+  r#"import { precompileTemplate } from "@ember/template-compilation";
+     import { setComponentTemplate } from "@ember/component";
+     import templateOnly from "@ember/component/template-only";
+     import Greeting from "./greeting";
+     export const Hello = setComponentTemplate(precompileTemplate(`<Greeting @name="world" />`, { strictMode: true, scope: () => ({ Greeting }) }), templateOnly());
+     export default setComponentTemplate(precompileTemplate(`<Hello />`, { strictMode: true, scope: () => ({ Hello }) }), class extends Component {
+     });"#
+}
+
+testcase! {
+  precompile_template_flavor_without_class_members,
+  Options { flavor: OutputFlavor::PrecompileTemplate, ..Default::default() },
+  r#"<template>hello</template>"#,
+  r#"import { precompileTemplate } from "@ember/template-compilation";
+     import { setComponentTemplate } from "@ember/component";
+     import templateOnly from "@ember/component/template-only";
+     export default setComponentTemplate(precompileTemplate(`hello`, { strictMode: true, scope: () => ({}) }), templateOnly());"#
+}
+
+testcase! {
+  class_members_outside_class,
+  Options { class_member_lowering: ClassMemberLowering::OutsideClass, ..Default::default() },
+  r#"export default class Hello extends Component {
+       <template>Hello</template>
+     }"#,
+  r#"import { template } from "@ember/template-compiler";
+     export default class Hello extends Component {
+     }
+     template(`Hello`, { component: Hello, eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  strip_indent_off,
+  Options { strip_indent: StripIndent::Off, ..Default::default() },
+  r#"let x = <template>
+    hello
+  </template>"#,
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`
+    hello
+  `, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  strip_indent_trim_only,
+  Options { strip_indent: StripIndent::TrimOnly, ..Default::default() },
+  r#"let x = <template>
+    hello
+  </template>"#,
+  r#"import { template } from "@ember/template-compiler";
+     let x = template(`    hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  avoids_names_used_in_templates,
+  r#"export default <template>{{template}}</template>"#,
+  r#"import { template as template1 } from "@ember/template-compiler";
+     export default template1(`{{template}}`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  fixed_import_alias,
+  Options { import_alias: ImportAlias::Fixed, ..Default::default() },
+  r#"let x = <template>hello</template>"#,
+  r#"import { template as template_UUID } from "@ember/template-compiler";
+     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_import_after_directives,
+  r#""use client";
+     'use strict';
+     import Foo from "./foo";
+     <template><Foo /></template>"#,
+  r#""use client";
+     'use strict';
+     import { template } from "@ember/template-compiler";
+     import Foo from "./foo";
+     export default template(`<Foo />`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_import_after_shebang,
+  r#"#!/usr/bin/env node
+     let x = <template>hello</template>"#,
+  r#"#!/usr/bin/env node
+     import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_import_after_license_comment,
+  r#"/** @license MIT */
+     let x = <template>hello</template>"#,
+  r#"/** @license MIT */
+     import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_import_after_shebang_and_license_comment,
+  r#"#!/usr/bin/env node
+     /*! license text */
+     let x = <template>hello</template>"#,
+  r#"#!/usr/bin/env node
+     /*! license text */
+     import { template } from "@ember/template-compiler";
+     let x = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_import_after_last_import,
+  Options { import_placement: ImportPlacement::AfterImports, ..Default::default() },
+  r#""use strict";
+     import Foo from "./foo";
+     import Bar from "./bar";
+     <template><Foo /><Bar /></template>"#,
+  r#""use strict";
+     import Foo from "./foo";
+     import Bar from "./bar";
+     import { template } from "@ember/template-compiler";
+     export default template(`<Foo /><Bar />`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  inserts_require_after_directives,
+  Options { module_format: ModuleFormat::CommonJs, ..Default::default() },
+  r#""use strict";
+     <template>hello</template>"#,
+  r#""use strict";
+     const { template } = require("@ember/template-compiler");
+     exports.default = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  keeps_comment_before_top_level_template,
+  r#"/** The greeting */
+     <template>hello</template>"#,
+  r#"import { template } from "@ember/template-compiler";
+     /** The greeting */
+     export default template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  keeps_comment_before_first_statement_below_import,
+  r#"// eslint-disable-next-line no-unused-vars
+     const Greeting = <template>hello</template>;"#,
+  r#"import { template } from "@ember/template-compiler";
+     // eslint-disable-next-line no-unused-vars
+     const Greeting = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  keeps_comment_before_class_member_template,
+  r#"import Component from "@glimmer/component";
+     export default class extends Component {
+       // @ts-expect-error
+       <template>{{this.missing}}</template>
+     }"#,
+  r#"import { template } from "@ember/template-compiler";
+     import Component from "@glimmer/component";
+     export default class extends Component {
+       // @ts-expect-error
+       static {
+         template(`{{this.missing}}`, { component: this, eval() { return eval(arguments[0])} });
+       }
+     }"#
+}
+
+testcase! {
+  keeps_comment_after_top_level_template,
+  r#"<template>{{this.missing}}</template> // eslint-disable-line"#,
+  r#"import { template } from "@ember/template-compiler";
+     export default template(`{{this.missing}}`, { eval() { return eval(arguments[0])} }); // eslint-disable-line"#
+}
+
+testcase! {
+  keeps_comment_after_class_member_template,
+  r#"export default class extends Component {
+       <template>{{this.missing}}</template> // eslint-disable-line
+     }"#,
+  r#"import { template } from "@ember/template-compiler";
+     export default class extends Component {
+       static {
+         template(`{{this.missing}}`, { component: this, eval() { return eval(arguments[0])} });
+       } // eslint-disable-line
+     }"#
+}
+
+testcase! {
+  keeps_comment_before_class_member_template_outside_class,
+  Options { class_member_lowering: ClassMemberLowering::OutsideClass, ..Default::default() },
+  r#"import Component from "@glimmer/component";
+     export default class Hello extends Component {
+       /** renders the greeting */
+       <template>Hello</template>
+     }"#,
+  r#"import { template } from "@ember/template-compiler";
+     import Component from "@glimmer/component";
+     export default class Hello extends Component {
+     }
+     /** renders the greeting */
+     template(`Hello`, { component: Hello, eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  commonjs_module_format,
+  Options { module_format: ModuleFormat::CommonJs, ..Default::default() },
+  r#"const Component = require("@glimmer/component");
+     const Hello = <template>Hello</template>;
+     <template><Hello /></template>"#,
+  r#"const { template } = require("@ember/template-compiler");
+     const Component = require("@glimmer/component");
+     const Hello = template(`Hello`, { eval() { return eval(arguments[0])} });
+     Object.defineProperty(exports, "__esModule", { value: true });
+     exports.default = template(`<Hello />`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  commonjs_module_format_leaves_own_imports_and_exports,
+  Options { module_format: ModuleFormat::CommonJs, ..Default::default() },
+  r#"import Component from "@glimmer/component";
+     export const name = "hello";
+     <template>hello</template>"#,
+  r#"const { template } = require("@ember/template-compiler");
+     import Component from "@glimmer/component";
+     export const name = "hello";
+     Object.defineProperty(exports, "__esModule", { value: true });
+     exports.default = template(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  commonjs_module_format_with_fixed_alias,
+  Options { module_format: ModuleFormat::CommonJs, import_alias: ImportAlias::Fixed, ..Default::default() },
+  r#"let x = <template>hello</template>"#,
+  r#"const { template: template_UUID } = require("@ember/template-compiler");
+     let x = template_UUID(`hello`, { eval() { return eval(arguments[0])} });"#
+}
+
+testcase! {
+  commonjs_precompile_template_flavor,
+  Options { flavor: OutputFlavor::PrecompileTemplate, module_format: ModuleFormat::CommonJs, ..Default::default() },
+  r#"<template>hello</template>"#,
+  r#"const { precompileTemplate } = require("@ember/template-compilation");
+     const { setComponentTemplate } = require("@ember/component");
+     const { default: templateOnly } = require("@ember/component/template-only");
+     Object.defineProperty(exports, "__esModule", { value: true });
+     exports.default = setComponentTemplate(precompileTemplate(`hello`, { strictMode: true, scope: () => ({}) }), templateOnly());"#
+}
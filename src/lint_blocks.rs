@@ -0,0 +1,245 @@
+// Splits a source file into blocks that linters can check separately, and
+// translates what they report back, for an ESLint processor's `preprocess`
+// and `postprocess`.
+//
+// The script block is the source with every `<template>` replaced by a
+// placeholder that is still valid JS: a template literal for expressions and
+// a static block holding a comment for class members. Placeholders keep the
+// line breaks and the UTF-16 length of what they replace, so positions in the
+// script block are positions in the source. Each template's contents become a
+// block of their own, and positions in those are moved by where the contents
+// start.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::locate::{ContentTagKind, Occurrence};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintBlockKind {
+    Script,
+    Template,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintBlock {
+    pub kind: LintBlockKind,
+    pub text: String,
+    // a name for the block with an extension that picks the right linter
+    // config, like `0.js` or `1.hbs`
+    pub filename: String,
+    // where the block starts in the source, as 1-based line and UTF-16 column
+    // like ESLint reports them, and as a UTF-16 offset like ESLint fix ranges
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+const EXPRESSION_PLACEHOLDER: (&str, &str) = ("`", "`");
+const CLASS_MEMBER_PLACEHOLDER: (&str, &str) = ("static{/*", "*/}");
+
+// `occurrences` must come from parsing `src`. The script block's extension is
+// `.ts` for `.gts` files and `.js` otherwise.
+pub fn preprocess(
+    src: &str,
+    occurrences: &[Occurrence],
+    filename: Option<&Path>,
+) -> Vec<LintBlock> {
+    let typescript = filename
+        .and_then(|f| f.extension())
+        .is_some_and(|ext| ext == "gts" || ext == "ts");
+
+    let mut script = String::with_capacity(src.len());
+    let mut copied = 0;
+    for occurrence in occurrences {
+        let range = occurrence.range();
+        script.push_str(&src[copied..range.start_byte()]);
+        let placeholder = match occurrence.kind() {
+            ContentTagKind::Expression => EXPRESSION_PLACEHOLDER,
+            ContentTagKind::ClassMember => CLASS_MEMBER_PLACEHOLDER,
+        };
+        script.push_str(&placeholder_for(
+            &src[range.start_byte()..range.end_byte()],
+            placeholder,
+        ));
+        copied = range.end_byte();
+    }
+    script.push_str(&src[copied..]);
+
+    let mut blocks = vec![LintBlock {
+        kind: LintBlockKind::Script,
+        text: script,
+        filename: format!("0.{}", if typescript { "ts" } else { "js" }),
+        line: 1,
+        column: 1,
+        offset: 0,
+    }];
+    for (index, occurrence) in occurrences.iter().enumerate() {
+        let content = occurrence.content_range();
+        let before = &src[..content.start_byte()];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        blocks.push(LintBlock {
+            kind: LintBlockKind::Template,
+            text: src[content.start_byte()..content.end_byte()].to_string(),
+            filename: format!("{}.hbs", index + 1),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].encode_utf16().count() + 1,
+            offset: before.encode_utf16().count(),
+        });
+    }
+    blocks
+}
+
+// Blanks out `tag` and wraps the blank in the placeholder's prefix and suffix.
+// Both fit, because the tag starts with `<template>` and ends with
+// `</template>`. The blank is plain spaces, which keeps the UTF-16 length that
+// ESLint counts in but not the byte length: the wider whitespace that
+// virtual_documents.rs uses to keep both would trip `no-irregular-whitespace`.
+fn placeholder_for(tag: &str, (prefix, suffix): (&str, &str)) -> String {
+    let mut blank: String = tag
+        .chars()
+        .flat_map(|c| match c {
+            '\n' | '\r' => vec![c],
+            _ => vec![' '; c.len_utf16()],
+        })
+        .collect();
+    blank.replace_range(..prefix.len(), prefix);
+    blank.replace_range(blank.len() - suffix.len().., suffix);
+    blank
+}
+
+// Moves the messages that a linter reported for each block into the source's
+// coordinates and merges them in source order. `messages` has one list per
+// block, in the order `preprocess` returned them. This takes the blocks where
+// an ESLint processor's `postprocess` takes the filename, so nothing has to
+// be kept between the calls; `createLintProcessor` in the package's entry
+// points keeps them per filename for ESLint. Messages are ESLint
+// `LintMessage` objects: `line`, `column`, `endLine`, `endColumn`, `fix.range`
+// and `suggestions[].fix.range` are translated, and everything else is kept
+// as it is.
+pub fn postprocess(blocks: &[LintBlock], messages: Vec<Vec<Value>>) -> Vec<Value> {
+    let mut merged: Vec<Value> = blocks
+        .iter()
+        .zip(messages)
+        .flat_map(|(block, messages)| {
+            messages.into_iter().map(move |mut message| {
+                translate(block, &mut message);
+                message
+            })
+        })
+        .collect();
+    merged.sort_by_key(|message| (message["line"].as_u64(), message["column"].as_u64()));
+    merged
+}
+
+fn translate(block: &LintBlock, message: &mut Value) {
+    translate_position(block, message, "line", "column");
+    translate_position(block, message, "endLine", "endColumn");
+    translate_fix(block, &mut message["fix"]);
+    if let Some(suggestions) = message["suggestions"].as_array_mut() {
+        for suggestion in suggestions {
+            translate_fix(block, &mut suggestion["fix"]);
+        }
+    }
+}
+
+fn translate_position(block: &LintBlock, message: &mut Value, line_key: &str, column_key: &str) {
+    let Some(line) = message[line_key].as_u64() else {
+        return;
+    };
+    if line == 1 {
+        if let Some(column) = message[column_key].as_u64() {
+            message[column_key] = (column as usize + block.column - 1).into();
+        }
+    }
+    message[line_key] = (line as usize + block.line - 1).into();
+}
+
+fn translate_fix(block: &LintBlock, fix: &mut Value) {
+    if let Some(range) = fix["range"].as_array_mut() {
+        for offset in range {
+            if let Some(value) = offset.as_u64() {
+                *offset = (value as usize + block.offset).into();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn blocks(src: &str, filename: &str) -> Vec<LintBlock> {
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    preprocess(src, &occurrences, Some(Path::new(filename)))
+}
+
+#[test]
+fn script_block_is_valid_js_in_the_same_positions() {
+    let src = "import Foo from './foo';\nconst a = <template>😀\n  <Foo /></template>;\nclass B {\n  <template>b</template>\n}\n";
+    let blocks = blocks(src, "a.gts");
+    let script = &blocks[0];
+    assert_eq!(script.filename, "0.ts");
+    assert_eq!(
+        script.text,
+        "import Foo from './foo';\nconst a = `           \n                   `;\nclass B {\n  static{/*          */}\n}\n"
+    );
+    assert_eq!(
+        script.text.encode_utf16().count(),
+        src.encode_utf16().count()
+    );
+    let reparsed = crate::Preprocessor::new()
+        .parse(&script.text, Default::default())
+        .unwrap();
+    assert!(reparsed.is_empty());
+
+    assert_eq!(blocks[1].text, "😀\n  <Foo />");
+    assert_eq!(blocks[1].filename, "1.hbs");
+    assert_eq!((blocks[1].line, blocks[1].column), (2, 21));
+    assert_eq!(blocks[1].offset, src.find("😀").unwrap());
+    assert_eq!((blocks[2].line, blocks[2].column), (5, 13));
+}
+
+#[test]
+fn translates_messages_back_to_the_source() {
+    let src = "const a = 1;\nconst b = <template>😀 hi\n  <button>x</button></template>;\n";
+    let blocks = blocks(src, "a.gjs");
+    assert_eq!(blocks[0].filename, "0.js");
+    let messages = postprocess(
+        &blocks,
+        vec![
+            vec![serde_json::json!({ "ruleId": "no-unused-vars", "line": 2, "column": 7 })],
+            vec![
+                serde_json::json!({
+                    "ruleId": "no-bare-strings",
+                    "line": 1,
+                    "column": 4,
+                    "endLine": 1,
+                    "endColumn": 6,
+                    "fix": { "range": [3, 5], "text": "" },
+                }),
+                serde_json::json!({ "ruleId": "require-button-type", "line": 2, "column": 3 }),
+            ],
+        ],
+    );
+    assert_eq!(
+        messages,
+        vec![
+            serde_json::json!({ "ruleId": "no-unused-vars", "line": 2, "column": 7 }),
+            serde_json::json!({
+                "ruleId": "no-bare-strings",
+                "line": 2,
+                "column": 24,
+                "endLine": 2,
+                "endColumn": 26,
+                "fix": { "range": [36, 38], "text": "" },
+            }),
+            serde_json::json!({ "ruleId": "require-button-type", "line": 3, "column": 3 }),
+        ]
+    );
+    let utf16: Vec<u16> = src.encode_utf16().collect();
+    assert_eq!(String::from_utf16(&utf16[36..38]).unwrap(), "hi");
+}
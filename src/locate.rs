@@ -1,21 +1,28 @@
 use serde::Serialize;
-use swc_common::{self, Span};
+use swc_common::{self, SourceMap, Span};
 use swc_ecma_ast::{
-    ClassMember, ContentTagContent, ContentTagEnd, ContentTagExpression, ContentTagMember,
-    ContentTagStart,
+    ClassDecl, ClassExpr, ClassMember, ContentTagContent, ContentTagEnd, ContentTagExpression,
+    ContentTagMember, ContentTagStart, DefaultDecl, ExportDefaultDecl, ExportDefaultExpr, Expr,
+    ExprStmt, ModuleItem, Pat, Stmt, VarDeclarator,
 };
 use swc_ecma_visit::{Visit, VisitWith};
 
+use crate::transform::{escape_template_literal, strip_indent, IndentStrip, LineOffset};
+use crate::StripIndent;
+
 #[derive(Default, Debug)]
 pub struct LocateContentTagVisitor {
     pub occurrences: Vec<Occurrence>,
     src: String,
     is_ascii: bool,
+    strip_indent: StripIndent,
+    // the names of the classes and bindings around the node being visited
+    enclosing_names: Vec<String>,
 }
 
-#[derive(Eq, PartialEq, Debug, Serialize)]
+#[derive(Eq, PartialEq, Debug, Serialize, Clone, Copy)]
 #[serde(rename_all = "kebab-case")]
-enum ContentTagKind {
+pub(crate) enum ContentTagKind {
     Expression,
     ClassMember,
 }
@@ -27,6 +34,25 @@ impl LocateContentTagVisitor {
             occurrences: Default::default(),
             src,
             is_ascii,
+            strip_indent: Default::default(),
+            enclosing_names: Default::default(),
+        }
+    }
+
+    pub fn with_strip_indent(mut self, policy: StripIndent) -> Self {
+        self.strip_indent = policy;
+        self
+    }
+
+    // Visits the children of `n` with `name` as the innermost enclosing name.
+    fn visit_named<N: VisitWith<Self>>(&mut self, name: Option<String>, n: &N) {
+        match name {
+            Some(name) => {
+                self.enclosing_names.push(name);
+                n.visit_children_with(self);
+                self.enclosing_names.pop();
+            }
+            None => n.visit_children_with(self),
         }
     }
 
@@ -38,7 +64,7 @@ impl LocateContentTagVisitor {
         contents: &ContentTagContent,
         closing: &ContentTagEnd,
     ) {
-        let occurrence = Occurrence {
+        let mut occurrence = Occurrence {
             kind,
             tag_name: "template".to_owned(),
             contents: contents.value.to_string(),
@@ -46,7 +72,12 @@ impl LocateContentTagVisitor {
             start_range: Range::new(&self.src, &opening.span, self.is_ascii),
             content_range: Range::new(&self.src, &contents.span, self.is_ascii),
             end_range: Range::new(&self.src, &closing.span, self.is_ascii),
+            raw_contents: Default::default(),
+            strip_indent: Default::default(),
+            line_offsets: Default::default(),
+            enclosing_name: self.enclosing_names.last().cloned(),
         };
+        occurrence.update_stripped(self.strip_indent);
 
         self.occurrences.push(occurrence);
     }
@@ -90,6 +121,46 @@ impl Visit for LocateContentTagVisitor {
 
         n.visit_children_with(self);
     }
+
+    fn visit_module_item(&mut self, n: &ModuleItem) {
+        // a bare top-level template is the module's default export
+        let name = match n {
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                expr: box Expr::ContentTagExpression(_),
+                ..
+            })) => Some("default".to_string()),
+            _ => None,
+        };
+        self.visit_named(name, n);
+    }
+
+    fn visit_class_decl(&mut self, n: &ClassDecl) {
+        self.visit_named(Some(n.ident.sym.to_string()), n);
+    }
+
+    fn visit_class_expr(&mut self, n: &ClassExpr) {
+        self.visit_named(n.ident.as_ref().map(|ident| ident.sym.to_string()), n);
+    }
+
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        let name = match &n.name {
+            Pat::Ident(binding) => Some(binding.id.sym.to_string()),
+            _ => None,
+        };
+        self.visit_named(name, n);
+    }
+
+    fn visit_export_default_decl(&mut self, n: &ExportDefaultDecl) {
+        let name = match &n.decl {
+            DefaultDecl::Class(ClassExpr { ident: Some(_), .. }) => None,
+            _ => Some("default".to_string()),
+        };
+        self.visit_named(name, n);
+    }
+
+    fn visit_export_default_expr(&mut self, n: &ExportDefaultExpr) {
+        self.visit_named(Some("default".to_string()), n);
+    }
 }
 
 #[derive(Serialize, Debug, Eq, PartialEq)]
@@ -105,6 +176,101 @@ pub struct Occurrence {
     content_range: Range,
     // the span of the closing "</template>" tag
     end_range: Range,
+    // the exact template literal contents that `process` emits for this
+    // template, after stripping and escaping
+    raw_contents: String,
+    // the whitespace that `process` strips from `contents`
+    strip_indent: IndentStrip,
+    // for each line of the stripped template, where it starts in `contents`
+    line_offsets: Vec<LineOffset>,
+    // the innermost class or binding that the template is part of, like `Foo`
+    // in `const Foo = <template>...</template>`, or `default` for the
+    // default export
+    #[serde(skip)]
+    enclosing_name: Option<String>,
+}
+
+// Only the language server reads these, and it isn't built without the `lsp`
+// feature.
+#[cfg_attr(not(feature = "lsp"), allow(dead_code))]
+impl Occurrence {
+    pub(crate) fn end_range(&self) -> &Range {
+        &self.end_range
+    }
+
+    pub(crate) fn enclosing_name(&self) -> Option<&str> {
+        self.enclosing_name.as_deref()
+    }
+}
+
+impl Occurrence {
+    pub(crate) fn range(&self) -> &Range {
+        &self.range
+    }
+
+    pub(crate) fn content_range(&self) -> &Range {
+        &self.content_range
+    }
+
+    pub(crate) fn start_range(&self) -> &Range {
+        &self.start_range
+    }
+
+    pub(crate) fn kind(&self) -> ContentTagKind {
+        self.kind
+    }
+
+    fn update_stripped(&mut self, policy: StripIndent) {
+        let stripped = strip_indent(&self.contents, policy);
+        self.raw_contents = escape_template_literal(&stripped.text.as_str().into()).to_string();
+        self.strip_indent = stripped.strip;
+        self.line_offsets = stripped.line_offsets;
+    }
+
+    // Replaces the bytes in `edit` (offsets into the whole source, and
+    // within `content_range`) with `text`.
+    pub(crate) fn edit_contents(
+        &mut self,
+        edit: std::ops::Range<usize>,
+        text: &str,
+        shift: Shift,
+        policy: StripIndent,
+    ) {
+        let start = self.content_range.start_byte;
+        self.contents
+            .replace_range(edit.start - start..edit.end - start, text);
+        self.range.shift_end(shift);
+        self.content_range.shift_end(shift);
+        self.end_range.shift(shift);
+        self.update_stripped(policy);
+    }
+
+    // Moves the whole occurrence, for an edit that happened before it.
+    pub(crate) fn shift(&mut self, shift: Shift) {
+        self.range.shift(shift);
+        self.start_range.shift(shift);
+        self.content_range.shift(shift);
+        self.end_range.shift(shift);
+    }
+}
+
+// How far an edit moves the text after it, in each unit that `Range` tracks.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Shift {
+    bytes: isize,
+    chars: isize,
+    utf16: isize,
+}
+
+impl Shift {
+    pub(crate) fn new(removed: &str, inserted: &str) -> Self {
+        let delta = |f: fn(&str) -> usize| f(inserted) as isize - f(removed) as isize;
+        Shift {
+            bytes: delta(str::len),
+            chars: delta(|s| s.chars().count()),
+            utf16: delta(|s| s.encode_utf16().count()),
+        }
+    }
 }
 
 #[derive(Serialize, Debug, Eq, PartialEq)]
@@ -117,10 +283,56 @@ pub struct Range {
     start_utf16_codepoint: usize,
     end_utf16_codepoint: usize,
 }
+
+// The byte offsets of `span` in the source file it points into, or `None` for
+// a dummy span and for one that doesn't fit in its file.
+pub(crate) fn byte_range(source_map: &SourceMap, span: Span) -> Option<std::ops::Range<usize>> {
+    if span.lo.is_dummy() || span.hi < span.lo {
+        return None;
+    }
+    let file = source_map.lookup_byte_offset(span.lo).sf;
+    let start = span.lo.0.checked_sub(file.start_pos.0)? as usize;
+    let end = span.hi.0.checked_sub(file.start_pos.0)? as usize;
+    (end <= file.src.len()).then_some(start..end)
+}
+
 impl Range {
+    pub(crate) fn start_byte(&self) -> usize {
+        self.start_byte
+    }
+
+    pub(crate) fn end_byte(&self) -> usize {
+        self.end_byte
+    }
+
+    fn shift(&mut self, shift: Shift) {
+        self.start_byte = self.start_byte.wrapping_add_signed(shift.bytes);
+        self.start_char = self.start_char.wrapping_add_signed(shift.chars);
+        self.start_utf16_codepoint = self.start_utf16_codepoint.wrapping_add_signed(shift.utf16);
+        self.shift_end(shift);
+    }
+
+    fn shift_end(&mut self, shift: Shift) {
+        self.end_byte = self.end_byte.wrapping_add_signed(shift.bytes);
+        self.end_char = self.end_char.wrapping_add_signed(shift.chars);
+        self.end_utf16_codepoint = self.end_utf16_codepoint.wrapping_add_signed(shift.utf16);
+    }
+
     pub fn new(src: &str, span: &Span, is_ascii: bool) -> Range {
-        let start_byte = span.lo.0 as usize - 1;
-        let end_byte = span.hi.0 as usize - 1;
+        Range::from_bytes(
+            src,
+            span.lo.0 as usize - 1,
+            span.hi.0 as usize - 1,
+            is_ascii,
+        )
+    }
+
+    pub(crate) fn from_bytes(
+        src: &str,
+        start_byte: usize,
+        end_byte: usize,
+        is_ascii: bool,
+    ) -> Range {
         if is_ascii {
             // For ASCII sources, byte/char/utf16 offsets are all identical.
             Range {
@@ -189,6 +401,13 @@ fn test_basic_example() {
             start_utf16_codepoint: 16,
             end_utf16_codepoint: 27,
         },
+        raw_contents: "Hello!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("default".into()),
     };
     assert_eq!(output, vec![expected]);
 }
@@ -239,6 +458,13 @@ fn test_expression_position() {
             start_utf16_codepoint: 28,
             end_utf16_codepoint: 39,
         },
+        raw_contents: "Hello!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("tpl".into()),
     }];
 
     assert_eq!(output, expected);
@@ -294,6 +520,13 @@ fn test_inside_class_body() {
             start_utf16_codepoint: 65,
             end_utf16_codepoint: 76,
         },
+        raw_contents: "Hello!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("A".into()),
     }];
 
     assert_eq!(output, expected);
@@ -349,6 +582,13 @@ fn test_multibyte_character_inside_template() {
             start_utf16_codepoint: 66,
             end_utf16_codepoint: 77,
         },
+        raw_contents: "Hell😀!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("A".into()),
     }];
 
     assert_eq!(output, expected);
@@ -406,6 +646,13 @@ fn test_preceded_by_a_slash_character() {
             start_utf16_codepoint: 81,
             end_utf16_codepoint: 92,
         },
+        raw_contents: "Hello!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("default".into()),
     }];
 
     assert_eq!(output, expected);
@@ -460,6 +707,13 @@ fn test_template_inside_a_regexp() {
             start_utf16_codepoint: 83,
             end_utf16_codepoint: 94,
         },
+        raw_contents: "Hello!".into(),
+        strip_indent: Default::default(),
+        line_offsets: vec![LineOffset {
+            original_line: 0,
+            original_column: 0,
+        }],
+        enclosing_name: Some("default".into()),
     }];
 
     assert_eq!(output, expected);
@@ -509,6 +763,12 @@ fn test_inner_expression() {
                 start_utf16_codepoint: 28,
                 end_utf16_codepoint: 39
             },
+            raw_contents: "Hello".into(),
+            strip_indent: Default::default(),
+            line_offsets: vec![LineOffset {
+                original_line: 0,
+                original_column: 0,
+            }],
             start_range: Range {
                 start_byte: 13,
                 end_byte: 23,
@@ -518,7 +778,114 @@ fn test_inner_expression() {
                 end_utf16_codepoint: 23
             },
             tag_name: "template".into(),
-            kind: ContentTagKind::Expression
+            kind: ContentTagKind::Expression,
+            enclosing_name: Some("x".into()),
         }]
     );
 }
+
+#[test]
+fn test_reports_indent_strip() {
+    let p = Preprocessor::new();
+    let src = "class A {\n  <template>\n    <div>\n      hi\n    </div>\n  </template>\n}";
+    let output = p.parse(src, Default::default()).unwrap();
+
+    assert_eq!(
+        output[0].strip_indent,
+        IndentStrip {
+            leading_lines: 1,
+            trailing_lines: 1,
+            indent: 4,
+        }
+    );
+}
+
+#[test]
+fn test_reports_indent_strip_for_policy() {
+    let src = "<template>\n\t  <div>\n  \t  hi\n\t  </div>\n</template>";
+
+    let output = Preprocessor::new().parse(src, Default::default()).unwrap();
+    assert_eq!(output[0].strip_indent.indent, 0);
+
+    let output = Preprocessor::new()
+        .parse(
+            src,
+            crate::Options {
+                strip_indent: StripIndent::DedentWithTabWidth(2),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(output[0].strip_indent.indent, 4);
+}
+
+#[test]
+fn test_raw_contents_and_line_offsets() {
+    let p = Preprocessor::new();
+    let src =
+        "class A {\n  <template>\n    <div>`${x}`</div>\n\n      <p>\\n</p>\n  </template>\n}";
+    let output = p.parse(src, Default::default()).unwrap();
+
+    assert_eq!(
+        output[0].raw_contents,
+        "<div>\\`\\${x}\\`</div>\n\n  <p>\\\\n</p>"
+    );
+    assert_eq!(
+        output[0].line_offsets,
+        vec![
+            LineOffset {
+                original_line: 1,
+                original_column: 4,
+            },
+            LineOffset {
+                original_line: 2,
+                original_column: 0,
+            },
+            LineOffset {
+                original_line: 3,
+                original_column: 4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_enclosing_names() {
+    let p = Preprocessor::new();
+    let src = r#"
+        class Foo {
+          <template>Foo</template>
+        }
+        const Bar = class {
+          <template>Bar</template>
+        };
+        export const baz = { qux: <template>Baz</template> };
+        export default <template>Default</template>;
+        doIt(<template>Anonymous</template>);
+    "#;
+    let output = p.parse(src, Default::default()).unwrap();
+    let names: Vec<_> = output.iter().map(|o| o.enclosing_name()).collect();
+    assert_eq!(
+        names,
+        vec![Some("Foo"), Some("Bar"), Some("baz"), Some("default"), None]
+    );
+}
+
+#[test]
+fn test_byte_range() {
+    use swc_common::{BytePos, FileName, DUMMY_SP};
+
+    let source_map = SourceMap::default();
+    source_map.new_source_file(FileName::Anon.into(), "let a;".to_string());
+    let second = source_map.new_source_file(FileName::Anon.into(), "let b = 1;".to_string());
+    let at = |lo: u32, hi: u32| {
+        Span::new(
+            second.start_pos + BytePos(lo),
+            second.start_pos + BytePos(hi),
+        )
+    };
+    assert_eq!(byte_range(&source_map, at(4, 5)), Some(4..5));
+    assert_eq!(byte_range(&source_map, at(10, 10)), Some(10..10));
+    assert_eq!(byte_range(&source_map, at(4, 11)), None);
+    assert_eq!(byte_range(&source_map, DUMMY_SP), None);
+}
@@ -0,0 +1,609 @@
+// A language server for `.gjs` and `.gts` files, speaking JSON-RPC over stdio
+// as the `content-tag-lsp` binary does. It only knows about the `<template>`
+// tags, so it's meant to run next to a regular JS/TS language server.
+//
+// Besides the standard requests it sends a `contentTag/embeddedRegions`
+// notification whenever a document is parsed, listing where the template
+// contents are so editors can highlight them as Handlebars.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+use serde_json::{json, Value};
+use swc_common::Spanned;
+
+use crate::locate::{ContentTagKind, Occurrence, Range};
+use crate::Document;
+
+pub const EMBEDDED_REGIONS_NOTIFICATION: &str = "contentTag/embeddedRegions";
+pub const EMBEDDED_LANGUAGE_ID: &str = "handlebars";
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+// LSP `TextDocumentSyncKind::Incremental`
+const SYNC_INCREMENTAL: u32 = 2;
+
+// LSP `SymbolKind`s
+const SYMBOL_KIND_CLASS: u32 = 5;
+const SYMBOL_KIND_CONSTANT: u32 = 14;
+
+// LSP `DiagnosticSeverity::Error`
+const SEVERITY_ERROR: u32 = 1;
+
+// Converts byte offsets into LSP positions, whose characters count UTF-16
+// code units.
+struct LineIndex<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { src, line_starts }
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let offset = offset.min(self.src.len());
+        let line = self.line(offset);
+        let character = self.src[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+        json!({ "line": line, "character": character })
+    }
+
+    // The byte offset of an LSP position. A character past the end of its
+    // line means the end of the line, as the spec asks.
+    fn offset(&self, position: &Value) -> Option<usize> {
+        let line = usize::try_from(position["line"].as_u64()?).ok()?;
+        let mut character = position["character"].as_u64()?;
+        let Some(&start) = self.line_starts.get(line) else {
+            return Some(self.src.len());
+        };
+        let line_end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.src.len(), |next| next - 1);
+        let mut offset = start;
+        for c in self.src[start..line_end].chars() {
+            let width = c.len_utf16() as u64;
+            if character < width {
+                break;
+            }
+            character -= width;
+            offset += c.len_utf8();
+        }
+        Some(offset)
+    }
+
+    fn range(&self, start: usize, end: usize) -> Value {
+        json!({ "start": self.position(start), "end": self.position(end) })
+    }
+
+    fn occurrence_range(&self, range: &Range) -> Value {
+        self.range(range.start_byte(), range.end_byte())
+    }
+}
+
+// The occurrences of the document's current source, which has none when it
+// doesn't parse.
+fn occurrences(document: &Document) -> &[Occurrence] {
+    if document.parses() {
+        document.occurrences()
+    } else {
+        &[]
+    }
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    initialized: bool,
+    shutting_down: bool,
+    exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // Whether the client has sent `exit`.
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    // The process exit code the LSP spec asks for once `exit` has been
+    // received.
+    pub fn exit_code(&self) -> i32 {
+        if self.shutting_down {
+            0
+        } else {
+            1
+        }
+    }
+
+    // Handles one incoming message and returns the messages to send back.
+    pub fn handle(&mut self, message: Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        let params = &message["params"];
+        let Some(id) = message.get("id").cloned() else {
+            // notifications before `initialize` are dropped, except `exit`
+            if !self.initialized && method != "exit" {
+                return vec![];
+            }
+            return self.notification(&method, params);
+        };
+        if method.is_empty() {
+            // a response to a request of ours, and we don't send any
+            return vec![];
+        }
+        let result = if !self.initialized && method != "initialize" {
+            Err((
+                SERVER_NOT_INITIALIZED,
+                "the server is not initialized".to_string(),
+            ))
+        } else if self.shutting_down && method != "shutdown" {
+            Err((INVALID_REQUEST, "the server is shutting down".to_string()))
+        } else {
+            self.request(&method, params)
+        };
+        vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        }]
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => {
+                self.initialized = true;
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": SYNC_INCREMENTAL },
+                        "documentSymbolProvider": true,
+                        "foldingRangeProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": "content-tag-lsp",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }))
+            }
+            "shutdown" => {
+                self.shutting_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/documentSymbol" => {
+                let document = self.document(params)?;
+                Ok(document_symbols(document).into())
+            }
+            "textDocument/foldingRange" => {
+                let document = self.document(params)?;
+                Ok(folding_ranges(document).into())
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {method}"))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "exit" => {
+                self.exited = true;
+                vec![]
+            }
+            "textDocument/didOpen" => {
+                let src = params["textDocument"]["text"].as_str().unwrap_or_default();
+                // an empty source always parses, and the real one may not
+                let mut document = Document::new(String::new()).unwrap();
+                let result = document.replace(src.to_string()).map(|_| ());
+                self.update(uri, document, result)
+            }
+            "textDocument/didChange" => {
+                let (Some(mut document), Some(changes)) = (
+                    self.documents.remove(uri),
+                    params["contentChanges"].as_array(),
+                ) else {
+                    return vec![];
+                };
+                // each change applies to the source the previous one left,
+                // and only the last result says whether the source parses
+                let mut result = Ok(());
+                for change in changes {
+                    let text = change["text"].as_str().unwrap_or_default();
+                    result = match change.get("range") {
+                        Some(range) => {
+                            let index = LineIndex::new(document.src());
+                            let (Some(start), Some(end)) =
+                                (index.offset(&range["start"]), index.offset(&range["end"]))
+                            else {
+                                continue;
+                            };
+                            let edit = start.min(end)..end.max(start);
+                            document.apply_edit(edit, text).map(|_| ())
+                        }
+                        None => document.replace(text.to_string()).map(|_| ()),
+                    };
+                }
+                self.update(uri, document, result)
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, vec![])]
+            }
+            // `initialized`, `$/cancelRequest` and anything else we don't
+            // support can be ignored
+            _ => vec![],
+        }
+    }
+
+    fn document(&self, params: &Value) -> Result<&Document, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        self.documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("{uri} is not open")))
+    }
+
+    fn update(
+        &mut self,
+        uri: &str,
+        document: Document,
+        result: Result<(), swc_ecma_parser::error::Error>,
+    ) -> Vec<Value> {
+        let diagnostics = match result {
+            Ok(()) => vec![],
+            Err(err) => vec![diagnostic(&document, &err)],
+        };
+        let messages = vec![
+            publish_diagnostics(uri, diagnostics),
+            notification(
+                EMBEDDED_REGIONS_NOTIFICATION,
+                json!({ "uri": uri, "regions": embedded_regions(&document) }),
+            ),
+        ];
+        self.documents.insert(uri.to_string(), document);
+        messages
+    }
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    notification(
+        "textDocument/publishDiagnostics",
+        json!({ "uri": uri, "diagnostics": diagnostics }),
+    )
+}
+
+fn diagnostic(document: &Document, err: &swc_ecma_parser::error::Error) -> Value {
+    let index = LineIndex::new(document.src());
+    // an error without a position goes at the start of the document
+    let range = document.byte_range(err.span()).unwrap_or(0..0);
+    json!({
+        "range": index.range(range.start, range.end),
+        "severity": SEVERITY_ERROR,
+        "source": "content-tag",
+        "message": err.kind().msg(),
+    })
+}
+
+fn document_symbols(document: &Document) -> Vec<Value> {
+    let index = LineIndex::new(document.src());
+    occurrences(document)
+        .iter()
+        .map(|occurrence| {
+            let kind = match occurrence.kind() {
+                ContentTagKind::ClassMember => SYMBOL_KIND_CLASS,
+                ContentTagKind::Expression => SYMBOL_KIND_CONSTANT,
+            };
+            json!({
+                "name": occurrence.enclosing_name().unwrap_or("<template>"),
+                "detail": "<template>",
+                "kind": kind,
+                "range": index.occurrence_range(occurrence.range()),
+                "selectionRange": index.occurrence_range(occurrence.start_range()),
+            })
+        })
+        .collect()
+}
+
+// Folds from the opening tag to the line before the closing tag, so the
+// closing tag stays visible like it does for HTML elements.
+fn folding_ranges(document: &Document) -> Vec<Value> {
+    let index = LineIndex::new(document.src());
+    occurrences(document)
+        .iter()
+        .filter_map(|occurrence| {
+            let start_line = index.line(occurrence.range().start_byte());
+            let end_line = index.line(occurrence.end_range().start_byte());
+            (end_line > start_line + 1).then(|| {
+                json!({
+                    "startLine": start_line,
+                    "endLine": end_line - 1,
+                    "kind": "region",
+                })
+            })
+        })
+        .collect()
+}
+
+fn embedded_regions(document: &Document) -> Vec<Value> {
+    let index = LineIndex::new(document.src());
+    occurrences(document)
+        .iter()
+        .map(|occurrence| {
+            json!({
+                "languageId": EMBEDDED_LANGUAGE_ID,
+                "range": index.occurrence_range(occurrence.content_range()),
+            })
+        })
+        .collect()
+}
+
+// Reads one `Content-Length` framed message, or `None` at the end of the
+// input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+// Serves until the client sends `exit` or closes the input, and returns the
+// process exit code.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<i32> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut input)? {
+        for reply in server.handle(message) {
+            write_message(&mut output, &reply)?;
+        }
+        if server.exited() {
+            return Ok(server.exit_code());
+        }
+    }
+    Ok(1)
+}
+
+#[cfg(test)]
+fn initialized_server() -> Server {
+    let mut server = Server::new();
+    server.handle(json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} }));
+    server
+}
+
+#[cfg(test)]
+fn change(server: &mut Server, changes: Value) -> Vec<Value> {
+    server.handle(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": "file:///a.gjs", "version": 2 },
+            "contentChanges": changes,
+        },
+    }))
+}
+
+#[cfg(test)]
+fn open(server: &mut Server, src: &str) -> Vec<Value> {
+    server.handle(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": "file:///a.gjs", "languageId": "glimmer-js", "version": 1, "text": src },
+        },
+    }))
+}
+
+#[cfg(test)]
+fn request(server: &mut Server, method: &str) -> Value {
+    let mut replies = server.handle(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": { "textDocument": { "uri": "file:///a.gjs" } },
+    }));
+    assert_eq!(replies.len(), 1);
+    replies.remove(0)["result"].take()
+}
+
+#[test]
+fn symbols_and_folding_ranges() {
+    let mut server = initialized_server();
+    open(
+        &mut server,
+        "const Greeting = <template>😀 Hi</template>;\n\nexport default class Foo {\n  <template>\n    <Greeting />\n  </template>\n}\n",
+    );
+
+    let symbols = request(&mut server, "textDocument/documentSymbol");
+    assert_eq!(symbols[0]["name"], "Greeting");
+    assert_eq!(symbols[0]["kind"], SYMBOL_KIND_CONSTANT);
+    assert_eq!(
+        symbols[0]["range"],
+        json!({ "start": { "line": 0, "character": 17 }, "end": { "line": 0, "character": 43 } })
+    );
+    assert_eq!(symbols[1]["name"], "Foo");
+    assert_eq!(symbols[1]["kind"], SYMBOL_KIND_CLASS);
+    assert_eq!(
+        symbols[1]["selectionRange"],
+        json!({ "start": { "line": 3, "character": 2 }, "end": { "line": 3, "character": 12 } })
+    );
+
+    let folds = request(&mut server, "textDocument/foldingRange");
+    assert_eq!(
+        folds,
+        json!([{ "startLine": 3, "endLine": 4, "kind": "region" }])
+    );
+}
+
+#[test]
+fn publishes_diagnostics_and_regions() {
+    let mut server = initialized_server();
+    let messages = open(&mut server, "let x = <template>a</template>;\nlet y = ;");
+    assert_eq!(messages[0]["method"], "textDocument/publishDiagnostics");
+    let diagnostics = &messages[0]["params"]["diagnostics"];
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 1, "character": 8 }, "end": { "line": 1, "character": 9 } })
+    );
+    assert_eq!(diagnostics[0]["severity"], SEVERITY_ERROR);
+    assert_eq!(messages[1]["params"]["regions"], json!([]));
+
+    let messages = change(
+        &mut server,
+        json!([{ "text": "let x = <template>a</template>;" }]),
+    );
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+    assert_eq!(messages[1]["method"], EMBEDDED_REGIONS_NOTIFICATION);
+    assert_eq!(
+        messages[1]["params"]["regions"],
+        json!([{
+            "languageId": "handlebars",
+            "range": { "start": { "line": 0, "character": 18 }, "end": { "line": 0, "character": 19 } },
+        }])
+    );
+}
+
+#[test]
+fn applies_incremental_changes() {
+    let mut server = initialized_server();
+    open(
+        &mut server,
+        "let x = <template>😀 a</template>;\nlet y = 1;",
+    );
+
+    // inside the template, after the emoji's two UTF-16 code units
+    let messages = change(
+        &mut server,
+        json!([{
+            "range": { "start": { "line": 0, "character": 21 }, "end": { "line": 0, "character": 22 } },
+            "text": "bc",
+        }]),
+    );
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+    assert_eq!(
+        messages[1]["params"]["regions"][0]["range"],
+        json!({ "start": { "line": 0, "character": 18 }, "end": { "line": 0, "character": 23 } })
+    );
+
+    // breaks the JS, then fixes it again in the same notification
+    let messages = change(
+        &mut server,
+        json!([
+            {
+                "range": { "start": { "line": 1, "character": 8 }, "end": { "line": 1, "character": 9 } },
+                "text": "",
+            },
+            {
+                "range": { "start": { "line": 1, "character": 8 }, "end": { "line": 1, "character": 8 } },
+                "text": "<template>c</template>",
+            },
+        ]),
+    );
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+    assert_eq!(
+        messages[1]["params"]["regions"].as_array().unwrap().len(),
+        2
+    );
+
+    let messages = change(
+        &mut server,
+        json!([{
+            "range": { "start": { "line": 1, "character": 8 }, "end": { "line": 1, "character": 30 } },
+            "text": "",
+        }]),
+    );
+    assert_eq!(
+        messages[0]["params"]["diagnostics"][0]["range"]["start"],
+        json!({ "line": 1, "character": 8 })
+    );
+    assert_eq!(messages[1]["params"]["regions"], json!([]));
+}
+
+#[test]
+fn rejects_requests_before_initialize() {
+    let mut server = Server::new();
+    assert!(open(&mut server, "<template>a</template>").is_empty());
+    let replies = server.handle(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/documentSymbol",
+        "params": { "textDocument": { "uri": "file:///a.gjs" } },
+    }));
+    assert_eq!(replies[0]["error"]["code"], SERVER_NOT_INITIALIZED);
+
+    server.handle(json!({ "jsonrpc": "2.0", "id": 2, "method": "initialize", "params": {} }));
+    open(&mut server, "<template>a</template>");
+    let symbols = request(&mut server, "textDocument/documentSymbol");
+    assert_eq!(symbols.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn runs_over_framed_messages() {
+    let mut input = vec![];
+    for message in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "nope", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ] {
+        write_message(&mut input, &message).unwrap();
+    }
+    let mut output = vec![];
+    assert_eq!(run(&input[..], &mut output).unwrap(), 0);
+
+    let mut output = &output[..];
+    let initialized = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(
+        initialized["result"]["capabilities"]["documentSymbolProvider"],
+        true
+    );
+    let unknown = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    let shutdown = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(shutdown["result"], Value::Null);
+    assert!(read_message(&mut output).unwrap().is_none());
+}
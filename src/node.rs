@@ -0,0 +1,315 @@
+// A native Node-API binding with the same surface as the wasm binding in
+// `bindings.rs`. It avoids copying strings through UTF-16 and converts `parse`
+// results straight into JS values instead of going through `JSON.parse`.
+
+use napi::{Env, JsObject, JsUnknown, Status, ValueType};
+use napi_derive::napi;
+use swc_common::{sync::Lrc, SourceMap};
+use swc_error_reporters::GraphicalTheme;
+
+use crate::js_options::{options_from, parse_error_detail, parse_error_message, OptionsObject};
+use crate::lint_blocks::{self, LintBlock};
+use crate::template_format::{self, FormatOptions};
+use crate::template_lint::{LintConfig, Registry};
+use crate::{Options, Preprocessor as CorePreprocessor};
+
+impl OptionsObject for JsObject {
+    fn string(&self, key: &str) -> Option<String> {
+        let value = self.get::<_, JsUnknown>(key).ok()??;
+        match value.get_type() {
+            Ok(ValueType::String) => value
+                .coerce_to_string()
+                .and_then(|s| s.into_utf8())
+                .and_then(|s| s.into_owned())
+                .ok(),
+            _ => None,
+        }
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        let value = self.get::<_, JsUnknown>(key).ok()??;
+        match value.get_type() {
+            Ok(ValueType::Number) => value.coerce_to_number().and_then(|n| n.get_double()).ok(),
+            _ => None,
+        }
+    }
+
+    fn truthy(&self, key: &str) -> Option<bool> {
+        let value = self.get::<_, JsUnknown>(key).ok()??;
+        match value.get_type() {
+            Ok(ValueType::Undefined) => None,
+            _ => value.coerce_to_bool().and_then(|b| b.get_value()).ok(),
+        }
+    }
+
+    fn coerced_string(&self, key: &str) -> Option<String> {
+        if self.truthy(key) != Some(true) {
+            return None;
+        }
+        let value = self.get::<_, JsUnknown>(key).ok()??;
+        value
+            .coerce_to_string()
+            .and_then(|s| s.into_utf8())
+            .and_then(|s| s.into_owned())
+            .ok()
+    }
+}
+
+fn read_options(options: Option<JsObject>) -> napi::Result<Options> {
+    match options {
+        Some(options) => options_from(&options)
+            .map_err(|err| napi::Error::new(Status::InvalidArg, err.to_string())),
+        None => Ok(Default::default()),
+    }
+}
+
+// Throws the same `Error` as the wasm binding, with `source_code` and
+// `source_code_color` attached.
+fn throw_parse_error(
+    env: &Env,
+    err: swc_ecma_parser::error::Error,
+    source_map: Lrc<SourceMap>,
+) -> napi::Error {
+    let short_desc = parse_error_message(&err, &source_map);
+    let thrown = env
+        .create_error(napi::Error::from_reason(short_desc.clone()))
+        .and_then(|mut js_err| {
+            js_err.set_named_property(
+                "source_code",
+                env.create_string(&parse_error_detail(
+                    err.clone(),
+                    source_map.clone(),
+                    GraphicalTheme::unicode_nocolor(),
+                ))?,
+            )?;
+            js_err.set_named_property(
+                "source_code_color",
+                env.create_string(&parse_error_detail(
+                    err,
+                    source_map,
+                    GraphicalTheme::unicode(),
+                ))?,
+            )?;
+            env.throw(js_err)
+        });
+    match thrown {
+        // already thrown, so napi must not throw another error for this one
+        Ok(()) => napi::Error::new(Status::PendingException, short_desc),
+        Err(err) => err,
+    }
+}
+
+#[napi(object)]
+pub struct CodeMapPair {
+    pub code: String,
+    pub map: String,
+}
+
+#[napi]
+pub struct Preprocessor {}
+
+#[napi]
+impl Preprocessor {
+    #[napi(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    #[napi(ts_args_type = "src: string, options?: PreprocessorOptions")]
+    pub fn process(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<CodeMapPair> {
+        // a fresh preprocessor per call, for the same reason as in `bindings.rs`
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.process(&src, read_options(options)?) {
+            Ok(output) => Ok(CodeMapPair {
+                code: output.code,
+                map: output.map,
+            }),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "Parsed[]"
+    )]
+    pub fn parse(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, read_options(options)?) {
+            Ok(parsed) => env.to_js_value(&parsed),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "EstreeProgram"
+    )]
+    pub fn parse_estree(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse_estree(&src, read_options(options)?) {
+            Ok(program) => env.to_js_value(&program),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "ImportUsage"
+    )]
+    pub fn import_usage(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.import_usage(&src, read_options(options)?) {
+            Ok(usage) => env.to_js_value(&usage),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "CrossReferences"
+    )]
+    pub fn cross_references(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.cross_references(&src, read_options(options)?) {
+            Ok(xrefs) => env.to_js_value(&xrefs),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "SemanticTokens"
+    )]
+    pub fn semantic_tokens(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.semantic_tokens(&src, read_options(options)?) {
+            Ok(tokens) => env.to_js_value(&tokens),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, config?: TemplateLintConfig, options?: PreprocessorOptions",
+        ts_return_type = "TemplateDiagnostic[]"
+    )]
+    pub fn lint_templates(
+        &self,
+        env: Env,
+        src: String,
+        config: Option<JsUnknown>,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let config: LintConfig = match config {
+            Some(config)
+                if !matches!(config.get_type()?, ValueType::Undefined | ValueType::Null) =>
+            {
+                env.from_js_value(config)?
+            }
+            _ => Default::default(),
+        };
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, read_options(options)?) {
+            Ok(parsed) => match Registry::default().lint(&src, &parsed, &config) {
+                Ok(diagnostics) => env.to_js_value(&diagnostics),
+                Err(err) => Err(napi::Error::new(Status::InvalidArg, err.to_string())),
+            },
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, formatOptions?: TemplateFormatOptions, options?: PreprocessorOptions",
+        ts_return_type = "string"
+    )]
+    pub fn format_templates(
+        &self,
+        env: Env,
+        src: String,
+        format_options: Option<JsUnknown>,
+        options: Option<JsObject>,
+    ) -> napi::Result<String> {
+        let format_options: FormatOptions = match format_options {
+            Some(format_options)
+                if !matches!(
+                    format_options.get_type()?,
+                    ValueType::Undefined | ValueType::Null
+                ) =>
+            {
+                env.from_js_value(format_options)?
+            }
+            _ => Default::default(),
+        };
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, read_options(options)?) {
+            Ok(parsed) => Ok(template_format::format(&src, &parsed, &format_options)),
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "src: string, options?: PreprocessorOptions",
+        ts_return_type = "LintBlock[]"
+    )]
+    pub fn preprocess(
+        &self,
+        env: Env,
+        src: String,
+        options: Option<JsObject>,
+    ) -> napi::Result<JsUnknown> {
+        let options = read_options(options)?;
+        let filename = options.filename.clone();
+        let preprocessor = CorePreprocessor::new();
+        match preprocessor.parse(&src, options) {
+            Ok(parsed) => {
+                env.to_js_value(&lint_blocks::preprocess(&src, &parsed, filename.as_deref()))
+            }
+            Err(err) => Err(throw_parse_error(&env, err, preprocessor.source_map())),
+        }
+    }
+
+    #[napi(
+        ts_args_type = "blocks: LintBlock[], messages: LintMessage[][]",
+        ts_return_type = "LintMessage[]"
+    )]
+    pub fn postprocess(
+        &self,
+        env: Env,
+        blocks: JsUnknown,
+        messages: JsUnknown,
+    ) -> napi::Result<JsUnknown> {
+        let blocks: Vec<LintBlock> = env.from_js_value(blocks)?;
+        let messages = env.from_js_value(messages)?;
+        env.to_js_value(&lint_blocks::postprocess(&blocks, messages))
+    }
+}
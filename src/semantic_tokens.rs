@@ -0,0 +1,462 @@
+// LSP semantic tokens for a whole `.gjs` or `.gts` file, so that editors can
+// highlight templates without loading an embedded Handlebars grammar.
+//
+// JS tokens are the ones the lexer produced while parsing, classified by their
+// text like estree.rs does. Template tokens come from template_syntax.rs. The
+// result is in LSP's encoding: five numbers per token, with positions relative
+// to the previous token in UTF-16 code units, and the type as an index into
+// `TOKEN_TYPES`.
+
+use serde::Serialize;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{BytePos, Span};
+use swc_ecma_ast::{ClassMember, Expr, Module, Regex, TplElement};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::estree::{LineIndex, KEYWORDS};
+use crate::template_refs::is_keyword;
+use crate::template_syntax::{
+    is_component_name, path_head, tokenize, AttributeValue, Mustache, MustacheKind, Node, Slice,
+    StartTag,
+};
+
+// The legend. These are LSP's standard token types, except for `tag`, which
+// is used for HTML elements.
+pub const TOKEN_TYPES: &[&str] = &[
+    "keyword",
+    "variable",
+    "property",
+    "parameter",
+    "function",
+    "class",
+    "tag",
+    "string",
+    "number",
+    "regexp",
+    "comment",
+];
+
+// in the same order as `TOKEN_TYPES`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenType {
+    Keyword,
+    Variable,
+    Property,
+    Parameter,
+    Function,
+    Class,
+    Tag,
+    String,
+    Number,
+    Regexp,
+    Comment,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokens {
+    pub token_types: &'static [&'static str],
+    // `deltaLine, deltaStartChar, length, tokenType, tokenModifiers` for each
+    // token, like LSP's `SemanticTokens.data`
+    pub data: Vec<u32>,
+}
+
+// `tokens` are the spans of the tokens the lexer produced.
+pub(crate) fn semantic_tokens(
+    src: &str,
+    start: BytePos,
+    module: &Module,
+    comments: &SingleThreadedComments,
+    tokens: &[Span],
+) -> SemanticTokens {
+    let byte = |pos: BytePos| (pos - start).0 as usize;
+    let mut regions = Regions::default();
+    module.visit_with(&mut regions);
+
+    let mut all = Tokens::default();
+
+    // the lexer's tokens inside of templates and template literals aren't
+    // JS tokens
+    let covered = |span: &Span| {
+        regions
+            .templates
+            .iter()
+            .map(|template| template.span)
+            .chain(regions.quasis.iter().copied())
+            .any(|outer| outer.lo <= span.lo && span.hi <= outer.hi)
+    };
+    let mut previous = "";
+    for span in tokens.iter().filter(|span| !covered(span)) {
+        let text = &src[byte(span.lo)..byte(span.hi)];
+        let kind = js_token_type(text, previous, regions.regexes.contains(span));
+        if let Some(kind) = kind {
+            all.push(byte(span.lo), byte(span.hi), kind);
+        }
+        previous = text;
+    }
+    for quasi in &regions.quasis {
+        all.push(byte(quasi.lo), byte(quasi.hi), TokenType::String);
+    }
+
+    let (leading, trailing) = comments.borrow_all();
+    for comment in leading.values().chain(trailing.values()).flatten() {
+        all.push(
+            byte(comment.span.lo),
+            byte(comment.span.hi),
+            TokenType::Comment,
+        );
+    }
+
+    for template in &regions.templates {
+        // the `template` in `<template>` and `</template>`
+        let opening = byte(template.opening.lo) + 1;
+        let closing = byte(template.closing.lo) + 2;
+        all.push(opening, opening + "template".len(), TokenType::Tag);
+        all.push(closing, closing + "template".len(), TokenType::Tag);
+        all.template(&template.contents, byte(template.contents_start));
+    }
+
+    SemanticTokens {
+        token_types: TOKEN_TYPES,
+        data: all.encode(src),
+    }
+}
+
+fn js_token_type(text: &str, previous: &str, is_regex: bool) -> Option<TokenType> {
+    if is_regex {
+        return Some(TokenType::Regexp);
+    }
+    let after_dot = matches!(previous, "." | "?.");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some('"' | '\'') => Some(TokenType::String),
+        Some('0'..='9') => Some(TokenType::Number),
+        Some('.') if chars.next().is_some_and(|c| c.is_ascii_digit()) => Some(TokenType::Number),
+        Some('#') if text.len() > 1 => Some(TokenType::Property),
+        Some(c) if c == '_' || c == '$' || c == '\\' || c.is_alphabetic() => {
+            if after_dot {
+                Some(TokenType::Property)
+            } else if matches!(text, "true" | "false" | "null") || KEYWORDS.contains(&text) {
+                Some(TokenType::Keyword)
+            } else {
+                Some(TokenType::Variable)
+            }
+        }
+        _ => None,
+    }
+}
+
+// Tokens as byte ranges into the whole file.
+#[derive(Default)]
+struct Tokens(Vec<(usize, usize, TokenType)>);
+
+impl Tokens {
+    fn push(&mut self, start: usize, end: usize, kind: TokenType) {
+        if start < end {
+            self.0.push((start, end, kind));
+        }
+    }
+
+    fn slice(&mut self, base: usize, slice: Slice<'_>, kind: TokenType) {
+        self.push(base + slice.start, base + slice.end, kind);
+    }
+
+    // `contents` starts at `base` in the file.
+    fn template(&mut self, contents: &str, base: usize) {
+        for node in tokenize(contents) {
+            match node {
+                Node::Text(_) => {}
+                Node::Comment(comment) => self.slice(base, comment, TokenType::Comment),
+                Node::StartTag(tag) => self.start_tag(contents, base, &tag),
+                Node::EndTag { name, .. } => self.slice(base, name, tag_type(name.text)),
+                Node::Mustache(mustache) => self.mustache(contents, base, &mustache, false),
+            }
+        }
+    }
+
+    fn start_tag(&mut self, contents: &str, base: usize, tag: &StartTag<'_>) {
+        self.slice(base, tag.name, tag_type(tag.name.text));
+        for attribute in &tag.attributes {
+            let kind = match attribute.name.text {
+                "...attributes" => TokenType::Keyword,
+                name if name.starts_with('@') => TokenType::Parameter,
+                _ => TokenType::Property,
+            };
+            self.slice(base, attribute.name, kind);
+            match &attribute.value {
+                AttributeValue::None => {}
+                AttributeValue::Text(text) => self.slice(base, *text, TokenType::String),
+                AttributeValue::Dynamic(mustaches) => {
+                    for mustache in mustaches {
+                        self.mustache(contents, base, mustache, false);
+                    }
+                }
+            }
+        }
+        for modifier in &tag.modifiers {
+            self.mustache(contents, base, modifier, true);
+        }
+        for param in &tag.block_params {
+            self.slice(base, *param, TokenType::Parameter);
+        }
+    }
+
+    fn mustache(&mut self, contents: &str, base: usize, mustache: &Mustache<'_>, modifier: bool) {
+        if mustache.kind == MustacheKind::Inverse {
+            let text = &contents[mustache.start..mustache.end];
+            if let Some(offset) = text.find("else") {
+                let start = base + mustache.start + offset;
+                self.push(start, start + "else".len(), TokenType::Keyword);
+            }
+        }
+        if mustache.kind == MustacheKind::BlockClose {
+            if let Some(name) = mustache.name {
+                self.path(base, name, TokenType::Variable);
+            }
+        }
+        for (i, path) in mustache.paths.iter().enumerate() {
+            // the head of a modifier is the modifier itself
+            let kind = if modifier && i == 0 {
+                TokenType::Function
+            } else {
+                TokenType::Variable
+            };
+            self.path(base, *path, kind);
+        }
+        for arg in &mustache.args {
+            self.path(base, *arg, TokenType::Parameter);
+        }
+        for param in &mustache.block_params {
+            self.slice(base, *param, TokenType::Parameter);
+        }
+    }
+
+    // The head of `path` as `kind`, or as a keyword if it is one, and the
+    // rest as properties.
+    fn path(&mut self, base: usize, path: Slice<'_>, kind: TokenType) {
+        let head = path_head(path);
+        let kind = if is_keyword(head.text) {
+            TokenType::Keyword
+        } else {
+            kind
+        };
+        self.slice(base, head, kind);
+        let mut start = head.end + 1;
+        for segment in path.text[head.text.len()..].split('.').skip(1) {
+            self.push(
+                base + start,
+                base + start + segment.len(),
+                TokenType::Property,
+            );
+            start += segment.len() + 1;
+        }
+    }
+
+    fn encode(mut self, src: &str) -> Vec<u32> {
+        self.0.sort_by_key(|(start, end, _)| (*start, *end));
+        let index = LineIndex::new(src);
+        let mut data = vec![];
+        let (mut previous_line, mut previous_column) = (0, 0);
+        let mut previous_end = 0;
+        for (start, end, kind) in self.0 {
+            // a comment can be both trailing one token and leading the next
+            if start < previous_end {
+                continue;
+            }
+            previous_end = end;
+            for (start, end) in split_lines(src, start, end) {
+                let (line, column) = index.line_column(src, start);
+                let delta_column = if line == previous_line {
+                    column - previous_column
+                } else {
+                    column
+                };
+                let length = index.utf16(src, end) - index.utf16(src, start);
+                data.extend([
+                    (line - previous_line) as u32,
+                    delta_column as u32,
+                    length as u32,
+                    kind as u32,
+                    0,
+                ]);
+                (previous_line, previous_column) = (line, column);
+            }
+        }
+        data
+    }
+}
+
+fn tag_type(name: &str) -> TokenType {
+    if name.starts_with('@') {
+        TokenType::Parameter
+    } else if is_component_name(name) {
+        TokenType::Class
+    } else {
+        TokenType::Tag
+    }
+}
+
+// LSP tokens can't span lines, so multi-line comments, strings and template
+// literals become one token per line.
+fn split_lines(src: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut pieces = vec![];
+    let mut piece_start = start;
+    for (i, c) in src[start..end].char_indices() {
+        if matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}') {
+            pieces.push((piece_start, start + i));
+            piece_start = start + i + c.len_utf8();
+        }
+    }
+    pieces.push((piece_start, end));
+    pieces.retain(|(start, end)| start < end);
+    pieces
+}
+
+struct Template {
+    span: Span,
+    opening: Span,
+    closing: Span,
+    contents_start: BytePos,
+    contents: String,
+}
+
+// The parts of the module that the lexer's tokens don't describe well on
+// their own.
+#[derive(Default)]
+struct Regions {
+    regexes: Vec<Span>,
+    quasis: Vec<Span>,
+    templates: Vec<Template>,
+}
+
+impl Visit for Regions {
+    fn visit_regex(&mut self, n: &Regex) {
+        self.regexes.push(n.span);
+    }
+
+    fn visit_tpl_element(&mut self, n: &TplElement) {
+        self.quasis.push(n.span);
+    }
+
+    fn visit_expr(&mut self, n: &Expr) {
+        if let Expr::ContentTagExpression(tag) = n {
+            self.templates.push(Template {
+                span: tag.span,
+                opening: tag.opening.span,
+                closing: tag.closing.span,
+                contents_start: tag.contents.span.lo,
+                contents: tag.contents.value.to_string(),
+            });
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_class_member(&mut self, n: &ClassMember) {
+        if let ClassMember::ContentTagMember(tag) = n {
+            self.templates.push(Template {
+                span: tag.span,
+                opening: tag.opening.span,
+                closing: tag.closing.span,
+                contents_start: tag.contents.span.lo,
+                contents: tag.contents.value.to_string(),
+            });
+        }
+        n.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+fn decode(src: &str, tokens: &SemanticTokens) -> Vec<(String, &'static str)> {
+    // only for ASCII sources, where UTF-16 columns are byte columns
+    let lines: Vec<&str> = src.lines().collect();
+    let (mut line, mut column) = (0, 0);
+    tokens
+        .data
+        .chunks(5)
+        .map(|token| {
+            if token[0] > 0 {
+                line += token[0] as usize;
+                column = token[1] as usize;
+            } else {
+                column += token[1] as usize;
+            }
+            let text = &lines[line][column..column + token[2] as usize];
+            (text.to_string(), TOKEN_TYPES[token[3] as usize])
+        })
+        .collect()
+}
+
+#[test]
+fn covers_js_and_templates() {
+    let src = r#"import { on } from "@ember/modifier";
+// greet
+export default class Hello {
+  name = `x`;
+  <template>
+    {{! hi }}
+    <button {{on "click" this.go}} class="a">{{#if @items.length}}{{yield}}{{/if}}</button>
+    <Foo as |f| />
+  </template>
+}"#;
+    let tokens = crate::Preprocessor::new()
+        .semantic_tokens(src, Default::default())
+        .unwrap();
+    let decoded = decode(src, &tokens);
+    let decoded: Vec<_> = decoded.iter().map(|(t, k)| (t.as_str(), *k)).collect();
+    assert_eq!(
+        decoded,
+        vec![
+            ("import", "keyword"),
+            ("on", "variable"),
+            ("from", "variable"),
+            ("\"@ember/modifier\"", "string"),
+            ("// greet", "comment"),
+            ("export", "keyword"),
+            ("default", "keyword"),
+            ("class", "keyword"),
+            ("Hello", "variable"),
+            ("name", "variable"),
+            ("x", "string"),
+            ("template", "tag"),
+            ("{{! hi }}", "comment"),
+            ("button", "tag"),
+            ("on", "function"),
+            ("this", "keyword"),
+            ("go", "property"),
+            ("class", "property"),
+            ("a", "string"),
+            ("if", "keyword"),
+            ("@items", "parameter"),
+            ("length", "property"),
+            ("yield", "keyword"),
+            ("if", "keyword"),
+            ("button", "tag"),
+            ("Foo", "class"),
+            ("f", "parameter"),
+            ("template", "tag"),
+        ]
+    );
+}
+
+#[test]
+fn splits_multiline_tokens() {
+    let src = "/* a\n  b */ const s = \"😀\";\n<template>{{!--\n  x\n--}}</template>";
+    let tokens = crate::Preprocessor::new()
+        .semantic_tokens(src, Default::default())
+        .unwrap();
+    assert_eq!(
+        tokens.data,
+        vec![
+            0, 0, 4, 10, 0, // `/* a`
+            1, 0, 6, 10, 0, // `  b */`
+            0, 7, 5, 0, 0, // `const`
+            0, 6, 1, 1, 0, // `s`
+            0, 4, 4, 7, 0, // `"😀"`, two UTF-16 code units
+            1, 1, 8, 6, 0, // `template`
+            0, 9, 5, 10, 0, // `{{!--`
+            1, 0, 3, 10, 0, // `  x`
+            1, 0, 4, 10, 0, // `--}}`
+            0, 6, 8, 6, 0, // `template`
+        ]
+    );
+}
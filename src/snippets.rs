@@ -1,11 +1,15 @@
 use swc_common::comments::SingleThreadedComments;
 use swc_common::Span;
 use swc_common::{self, sync::Lrc, FileName, SourceMap};
-use swc_ecma_ast::{Expr, Module};
+use swc_ecma_ast::{
+    Expr, Ident, IdentName, KeyValueProp, Module, ModuleItem, Prop, PropName, PropOrSpread, Stmt,
+};
 use swc_ecma_parser::EsSyntax;
 use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
 use swc_ecma_visit::{VisitMut, VisitMutWith};
 
+use crate::hot_reload::HotHelpers;
+
 lazy_static! {
     static ref SCOPE_PARAMS: Module = parse(r#"({ eval() { return eval(arguments[0]); } })"#);
 }
@@ -36,6 +40,18 @@ fn parse(src: &str) -> Module {
     parser.parse_module().unwrap()
 }
 
+struct IdentReplacer<'a> {
+    placeholder: &'a str,
+    ident: &'a Ident,
+}
+impl<'a> VisitMut for IdentReplacer<'a> {
+    fn visit_mut_ident(&mut self, n: &mut Ident) {
+        if &*n.sym == self.placeholder {
+            *n = self.ident.clone();
+        }
+    }
+}
+
 struct SpanReplacer {
     span: Span,
 }
@@ -70,3 +86,195 @@ pub fn scope_params(span: Span) -> Box<Expr> {
 pub fn scope_params_with_this(span: Span) -> Box<Expr> {
     generate_expression(span, &(*SCOPE_PARAMS_WITH_THIS))
 }
+
+pub fn scope_params_with_component(span: Span, component: Box<Expr>) -> Box<Expr> {
+    let mut params = scope_params(span);
+    if let Expr::Object(object) = &mut *params {
+        object.props.insert(
+            0,
+            PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(IdentName::new("component".into(), span)),
+                value: component,
+            }))),
+        );
+    }
+    params
+}
+
+pub fn precompile_template_params(span: Span, scope: &[String]) -> Box<Expr> {
+    let src = format!(
+        "({{ strictMode: true, scope: () => ({{ {} }}) }})",
+        scope.join(", ")
+    );
+    generate_expression(span, &parse(&src))
+}
+
+// `const { target_specifier: local } = require("target_module");`
+pub fn require_declaration(
+    span: Span,
+    target_module: &str,
+    target_specifier: &str,
+    local: &Ident,
+) -> ModuleItem {
+    let src = format!(
+        "const {{ {}: __local__ }} = require({});",
+        target_specifier,
+        serde_json::to_string(target_module).unwrap()
+    );
+    let mut module = parse(&src);
+    module.visit_mut_with(&mut SpanReplacer { span });
+    module.visit_mut_with(&mut IdentReplacer {
+        placeholder: "__local__",
+        ident: local,
+    });
+    module.body.remove(0)
+}
+
+lazy_static! {
+    static ref ES_MODULE_MARKER: Module =
+        parse(r#"Object.defineProperty(exports, "__esModule", { value: true });"#);
+}
+
+// `Object.defineProperty(exports, "__esModule", { value: true });`
+pub fn es_module_marker() -> ModuleItem {
+    let mut module = ES_MODULE_MARKER.clone();
+    module.visit_mut_with(&mut SpanReplacer {
+        span: Default::default(),
+    });
+    module.body.remove(0)
+}
+
+struct ExprReplacer<'a> {
+    placeholder: &'a str,
+    expr: &'a Expr,
+}
+impl<'a> VisitMut for ExprReplacer<'a> {
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        match n {
+            Expr::Ident(ident) if &*ident.sym == self.placeholder => *n = self.expr.clone(),
+            _ => n.visit_mut_children_with(self),
+        }
+    }
+}
+
+// Swaps the placeholders in a snippet of hot reloading code for the
+// identifiers of `helpers`.
+struct HotHelperReplacer<'a> {
+    helpers: &'a HotHelpers,
+}
+impl VisitMut for HotHelperReplacer<'_> {
+    fn visit_mut_ident(&mut self, n: &mut Ident) {
+        let helpers = self.helpers;
+        let helper = match &*n.sym {
+            "__hot_slot__" => &helpers.slot,
+            "__hot_class__" => &helpers.class,
+            "__hot_template__" => &helpers.template,
+            "__get_component_template__" => &helpers.get_component_template,
+            "__set_component_template__" => &helpers.set_component_template,
+            "__template_only__" => &helpers.template_only,
+            _ => return,
+        };
+        *n = helper.clone();
+    }
+}
+
+// Sets a class-member template on the class when hot-reloading:
+//
+//   contentTagHotClass(import.meta.hot, id, this, template(...));
+//
+// The class gets its template slot's indirection, and the slot is pointed at
+// `template`, see `hot_reload_hook`.
+pub fn hot_class_template(span: Span, id: &str, helpers: &HotHelpers, template: Expr) -> Stmt {
+    let src = format!(
+        "__hot_class__(import.meta.hot, {}, this, __template__);",
+        serde_json::to_string(id).unwrap()
+    );
+    let mut module = parse(&src);
+    module.visit_mut_with(&mut SpanReplacer { span });
+    module.visit_mut_with(&mut HotHelperReplacer { helpers });
+    module.visit_mut_with(&mut ExprReplacer {
+        placeholder: "__template__",
+        expr: &template,
+    });
+    module.body[0].as_stmt().unwrap().clone()
+}
+
+// A module-level expression template when hot-reloading:
+//
+//   contentTagHotTemplate(import.meta.hot, id, template(...))
+//
+// evaluates to the same template-only component on every load of the module,
+// which renders whatever its slot points at, see `hot_reload_hook`.
+pub fn hot_template(span: Span, id: &str, helpers: &HotHelpers, template: Expr) -> Expr {
+    let src = format!(
+        "(__hot_template__(import.meta.hot, {}, __template__))",
+        serde_json::to_string(id).unwrap()
+    );
+    let mut expr = generate_expression(span, &parse(&src));
+    expr.visit_mut_with(&mut HotHelperReplacer { helpers });
+    expr.visit_mut_with(&mut ExprReplacer {
+        placeholder: "__template__",
+        expr: &template,
+    });
+    *expr
+}
+
+// The helpers used by `hot_class_template` and `hot_template`, and the
+// `import.meta.hot` hook that accepts updates to the module.
+//
+// Ember lets a class have its template set only once, so a reload can't set
+// the changed template on the classes the app already holds. Instead every
+// template slot (the filename and index part of a template's identity) has a
+// template factory that defers to the factory of the slot's latest template.
+// Classes get that factory, and a module-level expression template becomes a
+// template-only component with it, made once per slot. Ember keeps what it
+// knows about a template as properties of its factory, so the slot's factory
+// forwards each property that the first factory has to the latest one, without
+// depending on which properties those are. `hot.data` keeps the slots across
+// updates, so a reloaded module points the slots that the app's components
+// already use at its templates. When only templates changed the module
+// accepts the update; otherwise it invalidates, so its importers are reloaded.
+// Imports are hoisted, so they can come last like the rest.
+pub fn hot_reload_hook(js_hash: &str, helpers: &HotHelpers) -> Vec<ModuleItem> {
+    let src = format!(
+        r#"import {{ getComponentTemplate as __get_component_template__, setComponentTemplate as __set_component_template__ }} from "@ember/component";
+           import __template_only__ from "@ember/component/template-only";
+           function __hot_slot__(hot, id, compiled) {{
+             const slots = hot ? hot.data.contentTagSlots || (hot.data.contentTagSlots = {{}}) : {{}};
+             const name = id.slice(0, id.lastIndexOf(":"));
+             const current = __get_component_template__(compiled);
+             let slot = slots[name];
+             if (!slot) {{
+               const template = (owner) => slot.current(owner);
+               for (const key of Object.keys(current)) {{
+                 Object.defineProperty(template, key, {{ get: () => slot.current[key] }});
+               }}
+               slot = slots[name] = {{ template, current, component: undefined }};
+             }}
+             slot.current = current;
+             return slot;
+           }}
+           function __hot_class__(hot, id, component, compiled) {{
+             __set_component_template__(__hot_slot__(hot, id, compiled).template, component);
+           }}
+           function __hot_template__(hot, id, compiled) {{
+             const slot = __hot_slot__(hot, id, compiled);
+             return slot.component || (slot.component = __set_component_template__(slot.template, __template_only__()));
+           }}
+           if (import.meta.hot) {{
+             const js = {js_hash};
+             if (import.meta.hot.data.contentTagJs && import.meta.hot.data.contentTagJs !== js) {{
+               import.meta.hot.invalidate();
+             }}
+             import.meta.hot.data.contentTagJs = js;
+             import.meta.hot.accept();
+           }}"#,
+        js_hash = serde_json::to_string(js_hash).unwrap()
+    );
+    let mut module = parse(&src);
+    module.visit_mut_with(&mut SpanReplacer {
+        span: Default::default(),
+    });
+    module.visit_mut_with(&mut HotHelperReplacer { helpers });
+    module.body
+}
@@ -0,0 +1,684 @@
+// Reformats the markup inside each `<template>` in a file, leaving everything
+// else as it was.
+//
+// Whitespace between nodes in a template is rendered, so the formatter only
+// moves things where the source already had whitespace, and keeps the kind of
+// whitespace it found: where there was a line break there still is one, and
+// nodes separated by spaces fill lines up to the print width. Nodes that touch
+// stay touching. Within that, tags are normalized and wrapped, and everything
+// is reindented one level in from the line the `<template>` starts on.
+//
+// The compiler dedents template contents with `strip_indent`, so the
+// continuation lines of anything kept exactly, like `<pre>` and multi-line
+// comments, are moved along with the new indentation. That way the compiler
+// still sees them exactly as before.
+
+use serde::Deserialize;
+
+use crate::locate::Occurrence;
+use crate::template_syntax::{
+    tokenize, AttributeValue, MustacheKind, Node, StartTag, VOID_ELEMENTS,
+};
+use crate::transform::strip_indent;
+use crate::StripIndent;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttributeWrapping {
+    // one attribute per line when the tag doesn't fit
+    #[default]
+    Auto,
+    // one attribute per line whenever there is more than one
+    OnePerLine,
+    // tags stay on one line
+    Never,
+}
+
+// How void elements like `<input>` are closed. Other elements are left alone,
+// because `<Foo />` and `<Foo></Foo>` aren't the same: only the second has a
+// block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelfClosing {
+    #[default]
+    Preserve,
+    // `<input />`
+    Always,
+    // `<input>`
+    Never,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatOptions {
+    pub print_width: usize,
+    // spaces per level, unless the file indents with tabs
+    pub indent_width: usize,
+    pub attribute_wrapping: AttributeWrapping,
+    pub self_closing: SelfClosing,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            print_width: 80,
+            indent_width: 2,
+            attribute_wrapping: Default::default(),
+            self_closing: Default::default(),
+        }
+    }
+}
+
+// `occurrences` must come from parsing `src`. Templates whose tags or blocks
+// don't match up are left as they are.
+pub fn format(src: &str, occurrences: &[Occurrence], options: &FormatOptions) -> String {
+    let mut templates: Vec<_> = occurrences
+        .iter()
+        .map(|o| {
+            (
+                o.start_range().start_byte(),
+                o.content_range().start_byte(),
+                o.content_range().end_byte(),
+            )
+        })
+        .collect();
+    templates.sort();
+
+    let mut formatted = String::with_capacity(src.len());
+    let mut last = 0;
+    for (tag_start, start, end) in templates {
+        formatted.push_str(&src[last..start]);
+        match format_template(src, tag_start, start, end, options) {
+            Some(contents) => formatted.push_str(&contents),
+            None => formatted.push_str(&src[start..end]),
+        }
+        last = end;
+    }
+    formatted.push_str(&src[last..]);
+    formatted
+}
+
+fn format_template(
+    src: &str,
+    tag_start: usize,
+    start: usize,
+    end: usize,
+    options: &FormatOptions,
+) -> Option<String> {
+    let contents = &src[start..end];
+    if contents.trim().is_empty() {
+        return None;
+    }
+    let items = parse(contents)?;
+
+    let line_start = src[..tag_start].rfind('\n').map_or(0, |i| i + 1);
+    let base: String = src[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let unit = if base.contains('\t') {
+        "\t".to_string()
+    } else {
+        " ".repeat(options.indent_width)
+    };
+    let mut printer = Printer {
+        options,
+        contents,
+        prefix: format!("{base}{unit}"),
+        unit,
+        old_indent: strip_indent(contents, StripIndent::Dedent).strip.indent,
+        lines: vec![],
+        line: String::new(),
+    };
+
+    let mut root = run(&items);
+    // a template that was on one line stays there if it fits
+    if !contents.contains('\n') {
+        if let Some(flat) = printer.flat_run(&root) {
+            let column = width(&src[line_start..start]);
+            if column + width(&flat) + "</template>".len() <= options.print_width {
+                return Some(flat);
+            }
+        }
+    }
+
+    // the contents go between lines of their own
+    root.gaps[0] = Gap::None;
+    *root.gaps.last_mut().unwrap() = Gap::None;
+    printer.line = printer.indent(0);
+    printer.run(&root, 0, 0);
+    printer.lines.push(printer.line);
+    Some(format!("\n{}\n{}", printer.lines.join("\n"), base))
+}
+
+// elements whose contents are whitespace sensitive or not markup
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+enum Item<'a> {
+    Text(&'a str),
+    Comment(&'a str),
+    Mustache(&'a str),
+    // a whole `<pre>` and the like, kept exactly
+    Verbatim(&'a str),
+    Element {
+        tag: StartTag<'a>,
+        // `None` for void and self-closing elements
+        children: Option<Vec<Item<'a>>>,
+    },
+    Block {
+        // the opening mustache and each `{{else}}`, with what follows them
+        sections: Vec<(&'a str, Vec<Item<'a>>)>,
+        close: &'a str,
+    },
+}
+
+enum Frame<'a> {
+    Element {
+        tag: StartTag<'a>,
+        children: Vec<Item<'a>>,
+    },
+    Block {
+        name: &'a str,
+        sections: Vec<(&'a str, Vec<Item<'a>>)>,
+    },
+}
+
+// Matches up the template's tags and blocks, or returns `None` if they don't.
+fn parse(contents: &str) -> Option<Vec<Item<'_>>> {
+    let mut root = vec![];
+    let mut stack: Vec<Frame> = vec![];
+    let mut nodes = tokenize(contents).into_iter();
+    while let Some(node) = nodes.next() {
+        let item = match node {
+            Node::Text(text) => Item::Text(text.text),
+            Node::Comment(comment) => Item::Comment(comment.text),
+            Node::Mustache(mustache) => {
+                let raw = &contents[mustache.start..mustache.end];
+                match mustache.kind {
+                    MustacheKind::Inline => Item::Mustache(raw),
+                    MustacheKind::BlockOpen => {
+                        stack.push(Frame::Block {
+                            name: mustache.name?.text,
+                            sections: vec![(raw, vec![])],
+                        });
+                        continue;
+                    }
+                    MustacheKind::Inverse => {
+                        let Some(Frame::Block { sections, .. }) = stack.last_mut() else {
+                            return None;
+                        };
+                        sections.push((raw, vec![]));
+                        continue;
+                    }
+                    MustacheKind::BlockClose => {
+                        let Some(Frame::Block { name, sections }) = stack.pop() else {
+                            return None;
+                        };
+                        if mustache.name.map(|n| n.text) != Some(name) {
+                            return None;
+                        }
+                        Item::Block {
+                            sections,
+                            close: raw,
+                        }
+                    }
+                }
+            }
+            Node::StartTag(tag)
+                if !tag.self_closing && VERBATIM_ELEMENTS.contains(&tag.name.text) =>
+            {
+                let end = nodes.by_ref().find_map(|node| match node {
+                    Node::EndTag { name, end, .. } if name.text == tag.name.text => Some(end),
+                    _ => None,
+                })?;
+                Item::Verbatim(&contents[tag.start..end])
+            }
+            Node::StartTag(tag) if !tag.has_end_tag() => Item::Element {
+                tag,
+                children: None,
+            },
+            Node::StartTag(tag) => {
+                stack.push(Frame::Element {
+                    tag,
+                    children: vec![],
+                });
+                continue;
+            }
+            Node::EndTag { name, .. } => {
+                let Some(Frame::Element { tag, children }) = stack.pop() else {
+                    return None;
+                };
+                if tag.name.text != name.text {
+                    return None;
+                }
+                Item::Element {
+                    tag,
+                    children: Some(children),
+                }
+            }
+        };
+        match stack.last_mut() {
+            Some(Frame::Element { children, .. }) => children.push(item),
+            Some(Frame::Block { sections, .. }) => sections.last_mut().unwrap().1.push(item),
+            None => root.push(item),
+        }
+    }
+    stack.is_empty().then_some(root)
+}
+
+// The whitespace between two nodes, which decides how they can be laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Gap {
+    None,
+    Space,
+    Line,
+    BlankLine,
+}
+
+fn gap(whitespace: &str) -> Gap {
+    match whitespace.matches('\n').count() {
+        0 if whitespace.is_empty() => Gap::None,
+        0 => Gap::Space,
+        1 => Gap::Line,
+        _ => Gap::BlankLine,
+    }
+}
+
+enum Piece<'i, 'a> {
+    Word(&'a str),
+    Item(&'i Item<'a>),
+}
+
+// The children of an element or block section, with text split into words.
+struct Run<'i, 'a> {
+    pieces: Vec<Piece<'i, 'a>>,
+    // the gap before each piece, and the one after the last
+    gaps: Vec<Gap>,
+}
+
+fn run<'i, 'a>(items: &'i [Item<'a>]) -> Run<'i, 'a> {
+    let mut run = Run {
+        pieces: vec![],
+        gaps: vec![Gap::None],
+    };
+    for item in items {
+        let Item::Text(text) = item else {
+            run.pieces.push(Piece::Item(item));
+            run.gaps.push(Gap::None);
+            continue;
+        };
+        let mut rest = *text;
+        loop {
+            let word_start = rest
+                .find(|c: char| !c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            let last = run.gaps.last_mut().unwrap();
+            *last = (*last).max(gap(&rest[..word_start]));
+            rest = &rest[word_start..];
+            if rest.is_empty() {
+                break;
+            }
+            let word_end = rest
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            run.pieces.push(Piece::Word(&rest[..word_end]));
+            run.gaps.push(Gap::None);
+            rest = &rest[word_end..];
+        }
+    }
+    run
+}
+
+struct Printer<'o, 'a> {
+    options: &'o FormatOptions,
+    contents: &'a str,
+    // the indentation of the template's top level, and of one level more
+    prefix: String,
+    unit: String,
+    // the indentation that `strip_indent` removes from the original contents
+    old_indent: usize,
+    lines: Vec<String>,
+    line: String,
+}
+
+impl Printer<'_, '_> {
+    fn indent(&self, level: usize) -> String {
+        format!("{}{}", self.prefix, self.unit.repeat(level))
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        width(&self.line) + width(text) <= self.options.print_width
+    }
+
+    fn newline(&mut self, level: usize, blank: bool) {
+        let indent = self.indent(level);
+        let line = std::mem::replace(&mut self.line, indent);
+        self.lines.push(line);
+        if blank {
+            self.lines.push(String::new());
+        }
+    }
+
+    // Continuation lines of `text` are kept as the compiler sees them, after
+    // `strip_indent`, under the new indentation.
+    fn write(&mut self, text: &str) {
+        let mut lines = text.split('\n');
+        self.line.push_str(lines.next().unwrap_or_default());
+        for line in lines {
+            let line = strip_columns(line, self.old_indent);
+            let next = if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", self.prefix, line)
+            };
+            self.lines.push(std::mem::replace(&mut self.line, next));
+        }
+    }
+
+    // `level` is the indentation of the pieces, and `outer` of whatever
+    // follows them.
+    fn run(&mut self, run: &Run, level: usize, outer: usize) {
+        for (i, gap) in run.gaps.iter().enumerate() {
+            let piece = run.pieces.get(i);
+            let level = if piece.is_some() { level } else { outer };
+            match gap {
+                Gap::None => {}
+                Gap::Space => match piece.and_then(|piece| self.flat(piece)) {
+                    Some(flat) if self.fits(&format!(" {flat}")) => self.write(" "),
+                    _ => self.newline(level, false),
+                },
+                Gap::Line => self.newline(level, false),
+                Gap::BlankLine => self.newline(level, true),
+            }
+            if let Some(piece) = piece {
+                self.piece(piece, level);
+            }
+        }
+    }
+
+    fn piece(&mut self, piece: &Piece, level: usize) {
+        let item = match piece {
+            Piece::Word(word) => return self.write(word),
+            Piece::Item(item) => item,
+        };
+        if let Some(flat) = self.flat_item(item) {
+            if self.fits(&flat) {
+                return self.write(&flat);
+            }
+        }
+        match item {
+            Item::Element { tag, children } => {
+                self.start_tag(tag, level);
+                if let Some(children) = children {
+                    self.run(&run(children), level + 1, level);
+                    self.write(&format!("</{}>", tag.name.text));
+                }
+            }
+            Item::Block { sections, close } => {
+                for (head, children) in sections {
+                    self.write(&collapse_whitespace(head));
+                    self.run(&run(children), level + 1, level);
+                }
+                self.write(&collapse_whitespace(close));
+            }
+            Item::Mustache(raw) => self.write(&collapse_whitespace(raw)),
+            Item::Text(text) | Item::Comment(text) | Item::Verbatim(text) => self.write(text),
+        }
+    }
+
+    fn start_tag(&mut self, tag: &StartTag, level: usize) {
+        let parts = self.tag_parts(tag);
+        let one_line = format!("<{}{}", tag.name.text, parts.concat());
+        let wrapping = self.options.attribute_wrapping;
+        let fits = !one_line.contains('\n') && self.fits(&one_line);
+        let wrap = match wrapping {
+            AttributeWrapping::Never => false,
+            AttributeWrapping::Auto => !fits,
+            AttributeWrapping::OnePerLine => !fits || parts.len() > 2,
+        };
+        if !wrap || parts.len() < 2 {
+            return self.write(&one_line);
+        }
+        // `parts` ends with how the tag closes
+        let (close, attributes) = parts.split_last().unwrap();
+        self.write(&format!("<{}", tag.name.text));
+        for attribute in attributes {
+            self.newline(level + 1, false);
+            self.write(attribute.trim_start());
+        }
+        self.newline(level, false);
+        self.write(close.trim_start());
+    }
+
+    // What follows the name in a start tag, each with the space before it,
+    // and then how the tag closes.
+    fn tag_parts(&self, tag: &StartTag) -> Vec<String> {
+        let mut parts: Vec<(usize, String)> = vec![];
+        for attribute in &tag.attributes {
+            let text = match attribute.value {
+                AttributeValue::None => attribute.name.text.to_string(),
+                _ => {
+                    let raw = &self.contents[attribute.name.end..attribute.end];
+                    let value = raw.trim_start().strip_prefix('=').unwrap_or(raw);
+                    format!("{}={}", attribute.name.text, value.trim_start())
+                }
+            };
+            parts.push((attribute.name.start, text));
+        }
+        for modifier in &tag.modifiers {
+            let raw = &self.contents[modifier.start..modifier.end];
+            parts.push((modifier.start, collapse_whitespace(raw)));
+        }
+        for comment in &tag.comments {
+            parts.push((comment.start, comment.text.to_string()));
+        }
+        if let Some(first) = tag.block_params.first() {
+            let names: Vec<_> = tag.block_params.iter().map(|p| p.text).collect();
+            parts.push((first.start, format!("as |{}|", names.join(" "))));
+        }
+        parts.sort_by_key(|(start, _)| *start);
+
+        let is_void = VOID_ELEMENTS.contains(&tag.name.text);
+        let self_closing = match self.options.self_closing {
+            SelfClosing::Always if is_void => true,
+            SelfClosing::Never if is_void => false,
+            _ => tag.self_closing,
+        };
+        let mut parts: Vec<_> = parts
+            .into_iter()
+            .map(|(_, text)| format!(" {text}"))
+            .collect();
+        parts.push(if self_closing { " />" } else { ">" }.to_string());
+        parts
+    }
+
+    // The piece on a single line, if it has no line breaks that matter.
+    fn flat(&self, piece: &Piece) -> Option<String> {
+        match piece {
+            Piece::Word(word) => Some(word.to_string()),
+            Piece::Item(item) => self.flat_item(item),
+        }
+    }
+
+    fn flat_item(&self, item: &Item) -> Option<String> {
+        let text = match item {
+            Item::Text(text) => text.split_ascii_whitespace().collect::<Vec<_>>().join(" "),
+            Item::Comment(raw) | Item::Verbatim(raw) => raw.to_string(),
+            Item::Mustache(raw) => collapse_whitespace(raw),
+            Item::Element { tag, children } => {
+                let parts = self.tag_parts(tag);
+                if self.options.attribute_wrapping == AttributeWrapping::OnePerLine
+                    && parts.len() > 2
+                {
+                    return None;
+                }
+                let mut text = format!("<{}{}", tag.name.text, parts.concat());
+                if let Some(children) = children {
+                    text += &self.flat_run(&run(children))?;
+                    text += &format!("</{}>", tag.name.text);
+                }
+                text
+            }
+            Item::Block { sections, close } => {
+                let mut text = String::new();
+                for (head, children) in sections {
+                    text += &collapse_whitespace(head);
+                    text += &self.flat_run(&run(children))?;
+                }
+                text + &collapse_whitespace(close)
+            }
+        };
+        (!text.contains('\n')).then_some(text)
+    }
+
+    fn flat_run(&self, run: &Run) -> Option<String> {
+        let mut text = String::new();
+        for (i, gap) in run.gaps.iter().enumerate() {
+            match gap {
+                Gap::None => {}
+                Gap::Space => text.push(' '),
+                Gap::Line | Gap::BlankLine => return None,
+            }
+            if let Some(piece) = run.pieces.get(i) {
+                text += &self.flat(piece)?;
+            }
+        }
+        Some(text)
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+// Removes up to `columns` of indentation, like `strip_indent` does.
+fn strip_columns(line: &str, columns: usize) -> &str {
+    let indent = line
+        .chars()
+        .take(columns)
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .count();
+    &line[indent..]
+}
+
+// Turns every run of whitespace outside of strings into a single space, for
+// mustaches that were spread over several lines.
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut quote = None;
+    let mut space = false;
+    for c in text.chars() {
+        match quote {
+            Some(q) => {
+                collapsed.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c.is_ascii_whitespace() => space = true,
+            None => {
+                if space {
+                    collapsed.push(' ');
+                    space = false;
+                }
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                collapsed.push(c);
+            }
+        }
+    }
+    collapsed
+}
+
+#[cfg(test)]
+fn format_with(src: &str, options: &FormatOptions) -> String {
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    format(src, &occurrences, options)
+}
+
+#[test]
+fn reindents_and_wraps() {
+    let src = r#"import Foo from "./foo";
+
+export default class Hello {
+  <template>
+      <div class="greeting"   id='x'>Hello, {{@name}}!</div>
+
+  <Foo @title="a long title that will not fit" @subtitle="and another one" as |f| />
+         {{#if @show}}<p>
+      shown</p>{{else}}hidden{{/if}}
+</template>
+}
+
+const inline = <template><b>{{x}}</b></template>;
+"#;
+    assert_eq!(
+        format_with(src, &Default::default()),
+        r#"import Foo from "./foo";
+
+export default class Hello {
+  <template>
+    <div class="greeting" id='x'>Hello, {{@name}}!</div>
+
+    <Foo
+      @title="a long title that will not fit"
+      @subtitle="and another one"
+      as |f|
+    />
+    {{#if @show}}<p>
+        shown</p>{{else}}hidden{{/if}}
+  </template>
+}
+
+const inline = <template><b>{{x}}</b></template>;
+"#
+    );
+}
+
+#[test]
+fn keeps_whitespace_sensitive_contents() {
+    let src = "<template>\n    {{!-- a\n      comment --}}\n    <pre>\n  kept\n    as is</pre>\n        <p>{{!-- inline --}}</p>\n</template>";
+    let formatted = format_with(src, &Default::default());
+    assert_eq!(
+        formatted,
+        "<template>\n  {{!-- a\n      comment --}}\n  <pre>\n  kept\n    as is</pre>\n  <p>{{!-- inline --}}</p>\n</template>"
+    );
+
+    // the compiler sees the `<pre>` as before
+    let before = strip_indent(&src[10..src.len() - 11], StripIndent::Dedent).text;
+    let after = strip_indent(&formatted[10..formatted.len() - 11], StripIndent::Dedent).text;
+    let pre =
+        |text: &str| text[text.find("<pre>").unwrap()..text.find("</pre>").unwrap()].to_string();
+    assert_eq!(pre(&before), pre(&after));
+}
+
+#[test]
+fn follows_options() {
+    let src = "<template>\n<input type=\"text\" disabled>\n<br/>\n<Foo @a={{1}} />\n</template>";
+    let options = FormatOptions {
+        attribute_wrapping: AttributeWrapping::OnePerLine,
+        self_closing: SelfClosing::Always,
+        indent_width: 4,
+        ..Default::default()
+    };
+    assert_eq!(
+        format_with(src, &options),
+        "<template>\n    <input\n        type=\"text\"\n        disabled\n    />\n    <br />\n    <Foo @a={{1}} />\n</template>"
+    );
+
+    let options = FormatOptions {
+        self_closing: SelfClosing::Never,
+        ..Default::default()
+    };
+    assert_eq!(
+        format_with(src, &options),
+        "<template>\n  <input type=\"text\" disabled>\n  <br>\n  <Foo @a={{1}} />\n</template>"
+    );
+
+    // unbalanced tags are left alone
+    let src = "<template>\n<div>\n</template>";
+    assert_eq!(format_with(src, &Default::default()), src);
+}
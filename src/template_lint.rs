@@ -0,0 +1,585 @@
+// Lints the contents of each `<template>` in a file with a set of rules, in
+// the spirit of ember-template-lint but without leaving Rust.
+//
+// A rule looks at one template at a time, through the nodes that
+// template_syntax.rs splits it into, and reports violations in offsets into
+// the template's contents. The registry runs the rules that the config
+// enables and moves what they report into the coordinates of the whole file.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::locate::{Occurrence, Range};
+use crate::template_syntax::{
+    path_head, tokenize, AttributeValue, Mustache, MustacheKind, Node, Slice, StartTag,
+};
+
+pub struct Template<'a> {
+    pub contents: &'a str,
+    pub nodes: Vec<Node<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub message: String,
+    // byte offsets into the template contents
+    pub start: usize,
+    pub end: usize,
+}
+
+pub trait Rule {
+    // the name that configs and diagnostics use, like `no-bare-strings`
+    fn name(&self) -> &'static str;
+
+    fn check(&self, template: &Template, violations: &mut Vec<Violation>);
+
+    // Whether the rule runs, as an error, when the config doesn't mention it.
+    fn recommended(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Warn,
+    Error,
+}
+
+// Which rules run, and how severe their reports are. Rules that aren't
+// mentioned run as errors if they are recommended, like the built-in
+// `no-unused-block-params` and `require-button-type`, and are off otherwise:
+//
+//   { "rules": { "no-bare-strings": "error", "require-button-type": "warn" } }
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownRule(pub String);
+
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown template lint rule `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownRule {}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateDiagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    // in the whole file
+    pub range: Range,
+}
+
+pub struct Registry {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        registry.register(NoBareStrings);
+        registry.register(NoUnusedBlockParams);
+        registry.register(RequireButtonType);
+        registry
+    }
+}
+
+impl Registry {
+    // An empty registry. `Registry::default()` has the built-in rules.
+    pub fn new() -> Self {
+        Registry { rules: vec![] }
+    }
+
+    // Adds a rule, replacing any rule of the same name.
+    pub fn register(&mut self, rule: impl Rule + 'static) {
+        self.rules.retain(|r| r.name() != rule.name());
+        self.rules.push(Box::new(rule));
+    }
+
+    pub fn rule_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|rule| rule.name())
+    }
+
+    // `occurrences` must come from parsing `src`. Diagnostics are sorted by
+    // where they start.
+    pub fn lint(
+        &self,
+        src: &str,
+        occurrences: &[Occurrence],
+        config: &LintConfig,
+    ) -> Result<Vec<TemplateDiagnostic>, UnknownRule> {
+        if let Some(unknown) = config
+            .rules
+            .keys()
+            .find(|name| !self.rule_names().any(|rule| rule == name.as_str()))
+        {
+            return Err(UnknownRule(unknown.clone()));
+        }
+        let enabled: Vec<_> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                let severity = match config.rules.get(rule.name()) {
+                    Some(RuleLevel::Off) => return None,
+                    Some(RuleLevel::Warn) => Severity::Warn,
+                    Some(RuleLevel::Error) => Severity::Error,
+                    None if rule.recommended() => Severity::Error,
+                    None => return None,
+                };
+                Some((rule, severity))
+            })
+            .collect();
+
+        let is_ascii = src.is_ascii();
+        let mut diagnostics = vec![];
+        for occurrence in occurrences {
+            let offset = occurrence.content_range().start_byte();
+            let contents = &src[offset..occurrence.content_range().end_byte()];
+            let template = Template {
+                contents,
+                nodes: tokenize(contents),
+            };
+            for (rule, severity) in &enabled {
+                let mut violations = vec![];
+                rule.check(&template, &mut violations);
+                diagnostics.extend(violations.into_iter().map(|v| TemplateDiagnostic {
+                    rule: rule.name(),
+                    severity: *severity,
+                    message: v.message,
+                    range: Range::from_bytes(src, offset + v.start, offset + v.end, is_ascii),
+                }));
+            }
+        }
+        diagnostics.sort_by_key(|d| (d.range.start_byte(), d.range.end_byte()));
+        Ok(diagnostics)
+    }
+}
+
+// Text that a user would read, which should come from a translation instead.
+// Text made only of whitespace, punctuation and character references is
+// fine, and so are the contents of `<script>`, `<style>` and `<pre>`.
+pub struct NoBareStrings;
+
+const ALLOWED_CHARS: &[char] = &[
+    '(', ')', ',', '.', '&', '+', '-', '=', '*', '/', '#', '%', '!', '?', ':', '[', ']', '{', '}',
+    '<', '>', '•', '—', '|', ';', '"', '\'', '…', '·', '×',
+];
+
+const CHECKED_ATTRIBUTES: &[&str] = &["title", "aria-label", "alt", "placeholder"];
+
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "pre"];
+
+fn is_bare(text: &str) -> bool {
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            // `&nbsp;`, `&#8212;`, `&#x2014;`
+            if let Some(end) = rest.find(';') {
+                let name = &rest[1..end];
+                if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#') {
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if !c.is_whitespace() && !ALLOWED_CHARS.contains(&c) {
+            return true;
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    false
+}
+
+// `slice` without its leading and trailing whitespace.
+fn trimmed(slice: Slice<'_>) -> Slice<'_> {
+    let text = slice.text.trim_start();
+    let start = slice.start + slice.text.len() - text.len();
+    let text = text.trim_end();
+    Slice {
+        text,
+        start,
+        end: start + text.len(),
+    }
+}
+
+impl Rule for NoBareStrings {
+    fn name(&self) -> &'static str {
+        "no-bare-strings"
+    }
+
+    fn check(&self, template: &Template, violations: &mut Vec<Violation>) {
+        let mut raw_text: Option<&str> = None;
+        for node in &template.nodes {
+            match node {
+                Node::StartTag(tag) => {
+                    if raw_text.is_none()
+                        && !tag.self_closing
+                        && RAW_TEXT_ELEMENTS.contains(&tag.name.text)
+                    {
+                        raw_text = Some(tag.name.text);
+                    }
+                    for attribute in &tag.attributes {
+                        if let AttributeValue::Text(value) = attribute.value {
+                            if CHECKED_ATTRIBUTES.contains(&attribute.name.text)
+                                && is_bare(value.text)
+                            {
+                                violations.push(Violation {
+                                    message: format!(
+                                        "Non-translated string used for `{}` attribute",
+                                        attribute.name.text
+                                    ),
+                                    start: value.start,
+                                    end: value.end,
+                                });
+                            }
+                        }
+                    }
+                }
+                Node::EndTag { name, .. } if raw_text == Some(name.text) => raw_text = None,
+                Node::Text(text) if raw_text.is_none() && is_bare(text.text) => {
+                    let text = trimmed(*text);
+                    violations.push(Violation {
+                        message: format!("Non-translated string used: `{}`", text.text),
+                        start: text.start,
+                        end: text.end,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Block params that nothing in the block uses. Params are positional, so one
+// is only reported when no param after it is used either.
+pub struct NoUnusedBlockParams;
+
+struct Scope<'a> {
+    // the block or element that opened the scope
+    opener: &'a str,
+    is_element: bool,
+    params: Vec<(Slice<'a>, bool)>,
+}
+
+impl Scope<'_> {
+    fn report(self, violations: &mut Vec<Violation>) {
+        let used = self.params.iter().rposition(|(_, used)| *used);
+        let first_unused = used.map_or(0, |last| last + 1);
+        violations.extend(
+            self.params[first_unused..]
+                .iter()
+                .map(|(param, _)| Violation {
+                    message: format!("'{}' is defined but never used", param.text),
+                    start: param.start,
+                    end: param.end,
+                }),
+        );
+    }
+}
+
+// Closes the innermost open scope that `is_opener` matches, along with any
+// scopes inside it that were left open.
+fn close_scope(
+    scopes: &mut Vec<Scope<'_>>,
+    violations: &mut Vec<Violation>,
+    is_opener: impl Fn(&Scope<'_>) -> bool,
+) {
+    if let Some(index) = scopes.iter().rposition(is_opener) {
+        for scope in scopes.drain(index..).rev() {
+            scope.report(violations);
+        }
+    }
+}
+
+fn mark_used(scopes: &mut [Scope<'_>], name: &str) {
+    for scope in scopes.iter_mut().rev() {
+        if let Some((_, used)) = scope.params.iter_mut().find(|(p, _)| p.text == name) {
+            *used = true;
+            return;
+        }
+    }
+}
+
+fn mark_mustache_used(scopes: &mut [Scope<'_>], mustache: &Mustache) {
+    for path in &mustache.paths {
+        mark_used(scopes, path_head(*path).text);
+    }
+}
+
+fn mark_tag_used(scopes: &mut [Scope<'_>], tag: &StartTag) {
+    if tag.is_component() {
+        mark_used(scopes, path_head(tag.name).text);
+    }
+    for attribute in &tag.attributes {
+        if let AttributeValue::Dynamic(mustaches) = &attribute.value {
+            for mustache in mustaches {
+                mark_mustache_used(scopes, mustache);
+            }
+        }
+    }
+    for modifier in &tag.modifiers {
+        mark_mustache_used(scopes, modifier);
+    }
+}
+
+impl Rule for NoUnusedBlockParams {
+    fn name(&self) -> &'static str {
+        "no-unused-block-params"
+    }
+
+    fn recommended(&self) -> bool {
+        true
+    }
+
+    fn check(&self, template: &Template, violations: &mut Vec<Violation>) {
+        let mut scopes: Vec<Scope> = vec![];
+        for node in &template.nodes {
+            match node {
+                Node::StartTag(tag) => {
+                    mark_tag_used(&mut scopes, tag);
+                    // every element gets a scope, even without params, so
+                    // that a nested element of the same name can't close it
+                    if tag.has_end_tag() {
+                        scopes.push(Scope {
+                            opener: tag.name.text,
+                            is_element: true,
+                            params: tag.block_params.iter().map(|p| (*p, false)).collect(),
+                        });
+                    }
+                }
+                Node::EndTag { name, .. } => close_scope(&mut scopes, violations, |s| {
+                    s.is_element && s.opener == name.text
+                }),
+                Node::Mustache(mustache) => {
+                    mark_mustache_used(&mut scopes, mustache);
+                    let name = mustache.name.map(|n| n.text).unwrap_or_default();
+                    match mustache.kind {
+                        MustacheKind::BlockOpen => scopes.push(Scope {
+                            opener: name,
+                            is_element: false,
+                            params: mustache.block_params.iter().map(|p| (*p, false)).collect(),
+                        }),
+                        MustacheKind::BlockClose => close_scope(&mut scopes, violations, |s| {
+                            !s.is_element && s.opener == name
+                        }),
+                        MustacheKind::Inline | MustacheKind::Inverse => {}
+                    }
+                }
+                Node::Text(_) | Node::Comment(_) => {}
+            }
+        }
+        // blocks that are never closed still declare their params
+        while let Some(scope) = scopes.pop() {
+            scope.report(violations);
+        }
+    }
+}
+
+// `<button>` without a `type`, which makes it submit the form it's in.
+pub struct RequireButtonType;
+
+const BUTTON_TYPES: &[&str] = &["button", "submit", "reset"];
+
+impl Rule for RequireButtonType {
+    fn name(&self) -> &'static str {
+        "require-button-type"
+    }
+
+    fn recommended(&self) -> bool {
+        true
+    }
+
+    fn check(&self, template: &Template, violations: &mut Vec<Violation>) {
+        for node in &template.nodes {
+            let Node::StartTag(tag) = node else {
+                continue;
+            };
+            if tag.name.text != "button" {
+                continue;
+            }
+            let valid = match tag.attribute("type").map(|a| &a.value) {
+                Some(AttributeValue::Text(value)) => BUTTON_TYPES.contains(&value.text),
+                Some(AttributeValue::Dynamic(_)) => true,
+                Some(AttributeValue::None) | None => false,
+            };
+            if !valid {
+                violations.push(Violation {
+                    message: "All `<button>` elements should have a valid `type` attribute"
+                        .to_string(),
+                    start: tag.start,
+                    end: tag.end,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn lint(src: &str, config: &str) -> Vec<(&'static str, Severity, String, String)> {
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    let config: LintConfig = serde_json::from_str(config).unwrap();
+    Registry::default()
+        .lint(src, &occurrences, &config)
+        .unwrap()
+        .into_iter()
+        .map(|d| {
+            let text = src[d.range.start_byte()..d.range.end_byte()].to_string();
+            (d.rule, d.severity, d.message, text)
+        })
+        .collect()
+}
+
+#[test]
+fn reports_each_rule_in_file_coordinates() {
+    let src = r#"const a = "😀";
+export default <template>
+  <h1 title="Welcome">{{t "hello"}} &nbsp;—</h1>
+  Hello there
+  {{#each @items as |item index|}}<button>{{item}}</button>{{/each}}
+  <Foo as |x y|><button type={{this.type}} {{on "click" y}}></button></Foo>
+  <style>p { color: red }</style>
+</template>"#;
+    assert_eq!(
+        lint(
+            src,
+            r#"{ "rules": { "no-bare-strings": "error", "require-button-type": "warn" } }"#
+        ),
+        vec![
+            (
+                "no-bare-strings",
+                Severity::Error,
+                "Non-translated string used for `title` attribute".to_string(),
+                "Welcome".to_string()
+            ),
+            (
+                "no-bare-strings",
+                Severity::Error,
+                "Non-translated string used: `Hello there`".to_string(),
+                "Hello there".to_string()
+            ),
+            (
+                "no-unused-block-params",
+                Severity::Error,
+                "'index' is defined but never used".to_string(),
+                "index".to_string()
+            ),
+            (
+                "require-button-type",
+                Severity::Warn,
+                "All `<button>` elements should have a valid `type` attribute".to_string(),
+                "<button>".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn nested_elements_of_the_same_name_keep_their_block_params() {
+    let src = "<template><Foo as |f g|><Foo></Foo>{{f}}</Foo></template>";
+    assert_eq!(
+        lint(src, "{}"),
+        vec![(
+            "no-unused-block-params",
+            Severity::Error,
+            "'g' is defined but never used".to_string(),
+            "g".to_string()
+        )]
+    );
+}
+
+#[test]
+fn runs_only_recommended_rules_by_default() {
+    let src = "<template>Hello {{#each @items as |item|}}<button>x</button>{{/each}}</template>";
+    let rules: Vec<_> = lint(src, "{}")
+        .into_iter()
+        .map(|(rule, severity, _, _)| (rule, severity))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            ("no-unused-block-params", Severity::Error),
+            ("require-button-type", Severity::Error),
+        ]
+    );
+}
+
+#[test]
+fn config_turns_rules_off_and_rejects_unknown_rules() {
+    let src = "<template><button>Go</button></template>";
+    assert_eq!(
+        lint(
+            src,
+            r#"{ "rules": { "no-bare-strings": "off", "require-button-type": "off" } }"#
+        ),
+        vec![]
+    );
+
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    let config: LintConfig =
+        serde_json::from_str(r#"{ "rules": { "no-such-rule": "warn" } }"#).unwrap();
+    assert_eq!(
+        Registry::default()
+            .lint(src, &occurrences, &config)
+            .unwrap_err(),
+        UnknownRule("no-such-rule".to_string())
+    );
+}
+
+#[test]
+fn runs_registered_rules() {
+    struct NoMarquee;
+    impl Rule for NoMarquee {
+        fn name(&self) -> &'static str {
+            "no-marquee"
+        }
+
+        fn check(&self, template: &Template, violations: &mut Vec<Violation>) {
+            for node in &template.nodes {
+                if let Node::StartTag(tag) = node {
+                    if tag.name.text == "marquee" {
+                        violations.push(Violation {
+                            message: "No marquees".to_string(),
+                            start: tag.name.start,
+                            end: tag.name.end,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let src = "<template><marquee>{{@news}}</marquee></template>";
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    let mut registry = Registry::new();
+    registry.register(NoMarquee);
+    let config: LintConfig =
+        serde_json::from_str(r#"{ "rules": { "no-marquee": "error" } }"#).unwrap();
+    let diagnostics = registry.lint(src, &occurrences, &config).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "no-marquee");
+    assert_eq!(diagnostics[0].range.start_byte(), 11);
+}
@@ -0,0 +1,272 @@
+// Finds the identifiers a template body refers to: angle-bracket component
+// names, mustache and sub-expression path heads, and modifier names. It walks
+// the nodes of template_syntax.rs and only adds which block params are in
+// scope.
+
+use serde::Serialize;
+
+use crate::template_syntax::{
+    path_head, tokenize, AttributeValue, Mustache, MustacheKind, Node, Slice, StartTag,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    // `<Foo />`, `<foo.bar />`
+    Component,
+    // `{{on "click" this.go}}` in element-modifier position
+    Modifier,
+    // every other path head: `{{foo}}`, `{{#if (bar)}}`, `{{helper baz}}`
+    Path,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateReference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    // byte offsets into the template contents
+    pub start: usize,
+    pub end: usize,
+}
+
+const KEYWORDS: &[&str] = &[
+    "component",
+    "debugger",
+    "each",
+    "each-in",
+    "else",
+    "false",
+    "has-block",
+    "has-block-params",
+    "helper",
+    "if",
+    "in-element",
+    "let",
+    "log",
+    "modifier",
+    "mount",
+    "mut",
+    "null",
+    "outlet",
+    "readonly",
+    "this",
+    "true",
+    "unbound",
+    "undefined",
+    "unless",
+    "with",
+    "yield",
+];
+
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+pub fn is_js_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+pub fn template_references(contents: &str) -> Vec<TemplateReference> {
+    let mut collector = Collector {
+        src: contents,
+        frames: vec![],
+        references: vec![],
+    };
+    for node in tokenize(contents) {
+        match node {
+            Node::StartTag(tag) => collector.start_tag(&tag),
+            Node::EndTag { name, .. } => collector.end_tag(name.text),
+            Node::Mustache(mustache) => collector.mustache(&mustache),
+            Node::Text(_) | Node::Comment(_) => {}
+        }
+    }
+    collector.references
+}
+
+enum Block<'a> {
+    Element(&'a str),
+    Mustache(&'a str),
+}
+
+struct Collector<'a> {
+    src: &'a str,
+    // one frame for every open element and block, whether or not it has
+    // block params, so that an end tag only ever closes its own element
+    frames: Vec<(Block<'a>, Vec<&'a str>)>,
+    references: Vec<TemplateReference>,
+}
+
+impl<'a> Collector<'a> {
+    fn is_local(&self, name: &str) -> bool {
+        self.frames.iter().any(|(_, names)| names.contains(&name))
+    }
+
+    fn add_reference(&mut self, name: Slice<'_>, kind: ReferenceKind) {
+        if is_keyword(name.text) || !is_js_identifier(name.text) || self.is_local(name.text) {
+            return;
+        }
+        self.references.push(TemplateReference {
+            name: name.text.to_string(),
+            kind,
+            start: name.start,
+            end: name.end,
+        });
+    }
+
+    // `head_kind` is the kind of the mustache's head, like `on` in
+    // `{{on "click" this.go}}`; arguments and sub-expression heads are paths.
+    fn add_paths(&mut self, mustache: &Mustache<'_>, head_kind: ReferenceKind) {
+        for (index, path) in mustache.paths.iter().enumerate() {
+            let is_head = index == 0
+                && self.src[mustache.start..path.start]
+                    .trim_start_matches(['{', '~', '#', '&', '^', '>'])
+                    .trim()
+                    .is_empty();
+            let kind = if is_head {
+                head_kind
+            } else {
+                ReferenceKind::Path
+            };
+            self.add_reference(path_head(*path), kind);
+        }
+    }
+
+    fn start_tag(&mut self, tag: &StartTag<'a>) {
+        if tag.is_component() {
+            self.add_reference(path_head(tag.name), ReferenceKind::Component);
+        }
+
+        // modifiers and attribute values, in the order they appear in the tag
+        let mut mustaches: Vec<_> = tag
+            .modifiers
+            .iter()
+            .map(|m| (m, ReferenceKind::Modifier))
+            .collect();
+        for attribute in &tag.attributes {
+            if let AttributeValue::Dynamic(values) = &attribute.value {
+                mustaches.extend(values.iter().map(|m| (m, ReferenceKind::Path)));
+            }
+        }
+        mustaches.sort_by_key(|(m, _)| m.start);
+        for (mustache, head_kind) in mustaches {
+            self.add_paths(mustache, head_kind);
+        }
+
+        if tag.has_end_tag() {
+            let names = tag.block_params.iter().map(|p| p.text).collect();
+            self.frames.push((Block::Element(tag.name.text), names));
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        // an end tag also closes any elements inside it that were left open
+        if let Some(index) = self
+            .frames
+            .iter()
+            .rposition(|(block, _)| matches!(block, Block::Element(open) if *open == name))
+        {
+            self.frames.truncate(index);
+        }
+    }
+
+    fn mustache(&mut self, mustache: &Mustache<'a>) {
+        let name = mustache.name.map_or("", |name| name.text);
+        match mustache.kind {
+            MustacheKind::BlockClose => {
+                if let Some((Block::Mustache(open), _)) = self.frames.last() {
+                    if *open == name {
+                        self.frames.pop();
+                    }
+                }
+            }
+            MustacheKind::BlockOpen => {
+                self.add_paths(mustache, ReferenceKind::Path);
+                let names = mustache.block_params.iter().map(|p| p.text).collect();
+                self.frames.push((Block::Mustache(name), names));
+            }
+            MustacheKind::Inline | MustacheKind::Inverse => {
+                self.add_paths(mustache, ReferenceKind::Path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn names(contents: &str) -> Vec<(String, ReferenceKind)> {
+    template_references(contents)
+        .into_iter()
+        .map(|r| (r.name, r.kind))
+        .collect()
+}
+
+#[test]
+fn finds_components_helpers_and_modifiers() {
+    assert_eq!(
+        names(r#"<Foo @x={{bar this.y}} {{on "click" go}} class="a {{cls}}" />"#),
+        vec![
+            ("Foo".to_string(), ReferenceKind::Component),
+            ("bar".to_string(), ReferenceKind::Path),
+            ("on".to_string(), ReferenceKind::Modifier),
+            ("go".to_string(), ReferenceKind::Path),
+            ("cls".to_string(), ReferenceKind::Path),
+        ]
+    );
+}
+
+#[test]
+fn skips_keywords_comments_and_hash_keys() {
+    assert_eq!(
+        names(r#"{{!-- <Nope /> --}}{{#if (eq a "b")}}{{yield (hash key=val)}}{{/if}}"#),
+        vec![
+            ("eq".to_string(), ReferenceKind::Path),
+            ("a".to_string(), ReferenceKind::Path),
+            ("hash".to_string(), ReferenceKind::Path),
+            ("val".to_string(), ReferenceKind::Path),
+        ]
+    );
+}
+
+#[test]
+fn respects_block_params() {
+    assert_eq!(
+        names(
+            r#"{{#each items as |item|}}{{item}}{{/each}}{{item}}<Foo as |f|><f.Bar /></Foo><f.Baz />"#
+        ),
+        vec![
+            ("items".to_string(), ReferenceKind::Path),
+            ("item".to_string(), ReferenceKind::Path),
+            ("Foo".to_string(), ReferenceKind::Component),
+            ("f".to_string(), ReferenceKind::Component),
+        ]
+    );
+}
+
+#[test]
+fn nested_elements_of_the_same_name_keep_their_block_params() {
+    assert_eq!(
+        names(r#"<Foo as |f|><Foo></Foo><br>{{f}}</Foo>{{f}}"#),
+        vec![
+            ("Foo".to_string(), ReferenceKind::Component),
+            ("Foo".to_string(), ReferenceKind::Component),
+            ("f".to_string(), ReferenceKind::Path),
+        ]
+    );
+}
+
+#[test]
+fn only_the_head_of_a_modifier_is_a_modifier() {
+    assert_eq!(
+        names(r#"<button {{on "click" (fn go)}} {{~autofocus}}></button>"#),
+        vec![
+            ("on".to_string(), ReferenceKind::Modifier),
+            ("fn".to_string(), ReferenceKind::Path),
+            ("go".to_string(), ReferenceKind::Path),
+            ("autofocus".to_string(), ReferenceKind::Modifier),
+        ]
+    );
+}
@@ -0,0 +1,126 @@
+// Decides which JS binding, if any, each name in a template refers to.
+//
+// Every template is swapped for JS that mentions each name the template refers
+// to, at the position of the name in the template: an array of identifiers in
+// expression position, and a static block holding one in a class body. swc's
+// resolver then decides which binding each identifier refers to, with all of
+// JS's scoping rules, so a name that a function parameter shadows, or that is
+// only declared in some other function, isn't mistaken for a binding the
+// template can see.
+
+use std::collections::{HashMap, HashSet};
+
+use swc_common::{BytePos, Mark, Span};
+use swc_ecma_ast::{
+    ArrayLit, BlockStmt, ClassMember, ContentTagContent, Expr, ExprOrSpread, ExprStmt, Ident,
+    Module, StaticBlock, Stmt,
+};
+use swc_ecma_transforms::resolver;
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+use crate::template_refs::{template_references, ReferenceKind};
+
+// Swaps each template in `module` for identifiers at the positions of the
+// names it refers to, as described above, and returns the kind of each
+// reference by where it starts. Once `resolve` has run over the result, an
+// identifier that starts at one of those positions is a template reference,
+// and its syntax context says what it resolves to.
+pub(crate) fn expose_template_references(module: &mut Module) -> HashMap<BytePos, ReferenceKind> {
+    let mut exposer = ExposeTemplateReferences::default();
+    module.visit_mut_with(&mut exposer);
+    exposer.kinds
+}
+
+// Runs swc's resolver over `module`, so that every identifier's syntax
+// context says which binding it refers to. Returns the mark of identifiers
+// that refer to no binding.
+pub(crate) fn resolve(module: &mut Module) -> Mark {
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
+    unresolved_mark
+}
+
+// Where each template reference in `module` that resolves to a JS binding
+// starts, for deciding what a template's scope has to include.
+pub(crate) fn bound_template_references(module: &Module) -> HashSet<BytePos> {
+    let mut module = module.clone();
+    let kinds = expose_template_references(&mut module);
+    let unresolved_mark = resolve(&mut module);
+
+    let mut collector = BoundReferences {
+        kinds: &kinds,
+        unresolved_mark,
+        starts: Default::default(),
+    };
+    module.visit_with(&mut collector);
+    collector.starts
+}
+
+#[derive(Default)]
+struct ExposeTemplateReferences {
+    // the kind of each template reference, by where it starts
+    kinds: HashMap<BytePos, ReferenceKind>,
+}
+
+impl ExposeTemplateReferences {
+    fn references(&mut self, span: Span, contents: &ContentTagContent) -> Expr {
+        let elems = template_references(&contents.value)
+            .into_iter()
+            .map(|reference| {
+                let span = Span::new(
+                    contents.span.lo + BytePos(reference.start as u32),
+                    contents.span.lo + BytePos(reference.end as u32),
+                );
+                self.kinds.insert(span.lo, reference.kind);
+                Some(ExprOrSpread {
+                    spread: None,
+                    expr: Box::new(Expr::Ident(Ident::new_no_ctxt(reference.name.into(), span))),
+                })
+            })
+            .collect();
+        Expr::Array(ArrayLit { span, elems })
+    }
+}
+
+impl VisitMut for ExposeTemplateReferences {
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        if let Expr::ContentTagExpression(tag) = n {
+            *n = self.references(tag.span, &tag.contents);
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class_member(&mut self, n: &mut ClassMember) {
+        if let ClassMember::ContentTagMember(tag) = n {
+            let span = tag.span;
+            let references = self.references(span, &tag.contents);
+            *n = ClassMember::StaticBlock(StaticBlock {
+                span,
+                body: BlockStmt {
+                    span,
+                    stmts: vec![Stmt::Expr(ExprStmt {
+                        span,
+                        expr: Box::new(references),
+                    })],
+                    ..Default::default()
+                },
+            });
+        }
+        n.visit_mut_children_with(self);
+    }
+}
+
+struct BoundReferences<'a> {
+    kinds: &'a HashMap<BytePos, ReferenceKind>,
+    unresolved_mark: Mark,
+    starts: HashSet<BytePos>,
+}
+
+impl Visit for BoundReferences<'_> {
+    fn visit_ident(&mut self, n: &Ident) {
+        if self.kinds.contains_key(&n.span.lo) && n.ctxt.outer() != self.unresolved_mark {
+            self.starts.insert(n.span.lo);
+        }
+    }
+}
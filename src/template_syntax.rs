@@ -0,0 +1,573 @@
+// A flat, forgiving tokenizer for template contents. It splits a template into
+// text, comments, start and end tags, and mustaches, and records the parts of
+// each that tools care about: attributes, modifiers, block params and the
+// paths a mustache refers to. It does not build a tree or validate anything;
+// tags and blocks are matched up by whoever walks the nodes, like
+// template_refs.rs does to know which block params are in scope.
+//
+// All offsets are byte offsets into the template contents.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slice<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MustacheKind {
+    // `{{foo}}`, `{{{foo}}}`, `{{foo bar}}`
+    Inline,
+    // `{{#if foo}}`
+    BlockOpen,
+    // `{{else}}`, `{{else if foo}}`, `{{^}}`
+    Inverse,
+    // `{{/if}}`
+    BlockClose,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mustache<'a> {
+    pub kind: MustacheKind,
+    // the block's name for `BlockOpen` and `BlockClose`, like `if`
+    pub name: Option<Slice<'a>>,
+    // every path in the mustache and its sub-expressions, including the head,
+    // but not strings, numbers, hash keys or `@args`
+    pub paths: Vec<Slice<'a>>,
+    // `@args`, like `@items.length`
+    pub args: Vec<Slice<'a>>,
+    pub block_params: Vec<Slice<'a>>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue<'a> {
+    // `<input disabled>`
+    None,
+    // `class="a"`, `class=a`; the slice excludes the quotes
+    Text(Slice<'a>),
+    // `class={{a}}`, `class="a {{b}}"`
+    Dynamic(Vec<Mustache<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub name: Slice<'a>,
+    pub value: AttributeValue<'a>,
+    // where the value ends, or the name for `AttributeValue::None`
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartTag<'a> {
+    pub name: Slice<'a>,
+    pub attributes: Vec<Attribute<'a>>,
+    // `{{on "click" this.go}}` directly in the tag
+    pub modifiers: Vec<Mustache<'a>>,
+    pub block_params: Vec<Slice<'a>>,
+    // `{{! }}` and `{{!-- --}}` directly in the tag
+    pub comments: Vec<Slice<'a>>,
+    pub self_closing: bool,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node<'a> {
+    Text(Slice<'a>),
+    // `<!-- -->`, `{{! }}` and `{{!-- --}}`
+    Comment(Slice<'a>),
+    StartTag(StartTag<'a>),
+    EndTag {
+        name: Slice<'a>,
+        start: usize,
+        end: usize,
+    },
+    Mustache(Mustache<'a>),
+}
+
+impl StartTag<'_> {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'_>> {
+        self.attributes.iter().find(|a| a.name.text == name)
+    }
+
+    pub fn is_component(&self) -> bool {
+        is_component_name(self.name.text)
+    }
+
+    // Whether an end tag closes this element later, that is, it is neither
+    // self-closing nor a void element like `<input>`.
+    pub fn has_end_tag(&self) -> bool {
+        !self.self_closing && !VOID_ELEMENTS.contains(&self.name.text)
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+pub const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// `<Foo />` and `<foo.bar />` are components, `<div>` and `<@arg />` are not.
+pub fn is_component_name(name: &str) -> bool {
+    !name.starts_with('@')
+        && !name.starts_with(':')
+        && (name.contains('.') || name.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+// The part of a path before the first `.`, like `item` in `item.name`.
+pub fn path_head(path: Slice<'_>) -> Slice<'_> {
+    let len = path.text.find('.').unwrap_or(path.text.len());
+    Slice {
+        text: &path.text[..len],
+        start: path.start,
+        end: path.start + len,
+    }
+}
+
+pub fn tokenize(contents: &str) -> Vec<Node<'_>> {
+    let mut tokenizer = Tokenizer {
+        src: contents,
+        pos: 0,
+        nodes: vec![],
+    };
+    tokenizer.tokenize();
+    tokenizer.nodes
+}
+
+struct Tokenizer<'a> {
+    src: &'a str,
+    pos: usize,
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn slice(&self, start: usize) -> Slice<'a> {
+        Slice {
+            text: &self.src[start..self.pos],
+            start,
+            end: self.pos,
+        }
+    }
+
+    fn skip_past(&mut self, terminator: &str) {
+        match self.rest().find(terminator) {
+            Some(offset) => self.pos += offset + terminator.len(),
+            None => self.pos = self.src.len(),
+        }
+    }
+
+    fn advance_char(&mut self) {
+        self.pos += self.rest().chars().next().map_or(1, char::len_utf8);
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn read_while(&mut self, pred: impl Fn(u8) -> bool) -> Slice<'a> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if pred(c)) {
+            self.pos += 1;
+        }
+        self.slice(start)
+    }
+
+    fn comment(&mut self, terminator: &str) {
+        let start = self.pos;
+        self.skip_past(terminator);
+        self.nodes.push(Node::Comment(self.slice(start)));
+    }
+
+    fn tokenize(&mut self) {
+        let mut text_start = 0;
+        while self.pos < self.src.len() {
+            let rest = self.rest();
+            let starts_node = rest.starts_with("{{")
+                || rest.starts_with("<!--")
+                || rest.starts_with("</")
+                || (rest.starts_with('<')
+                    && matches!(rest.as_bytes().get(1), Some(c) if is_tag_start(*c)));
+            if !starts_node {
+                self.advance_char();
+                continue;
+            }
+
+            if text_start < self.pos {
+                self.nodes.push(Node::Text(Slice {
+                    text: &self.src[text_start..self.pos],
+                    start: text_start,
+                    end: self.pos,
+                }));
+            }
+            if rest.starts_with("{{!--") {
+                self.comment("--}}");
+            } else if rest.starts_with("{{!") {
+                self.comment("}}");
+            } else if rest.starts_with("<!--") {
+                self.comment("-->");
+            } else if rest.starts_with("{{") {
+                let mustache = self.mustache();
+                self.nodes.push(Node::Mustache(mustache));
+            } else if rest.starts_with("</") {
+                self.end_tag();
+            } else {
+                self.start_tag();
+            }
+            text_start = self.pos;
+        }
+        if text_start < self.pos {
+            self.nodes.push(Node::Text(self.slice(text_start)));
+        }
+    }
+
+    fn end_tag(&mut self) {
+        let start = self.pos;
+        self.pos += 2;
+        let name = self.read_while(is_tag_name_char);
+        self.skip_past(">");
+        self.nodes.push(Node::EndTag {
+            name,
+            start,
+            end: self.pos,
+        });
+    }
+
+    fn start_tag(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        let mut tag = StartTag {
+            name: self.read_while(is_tag_name_char),
+            attributes: vec![],
+            modifiers: vec![],
+            block_params: vec![],
+            comments: vec![],
+            self_closing: false,
+            start,
+            end: start,
+        };
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with("/>") {
+                self.pos += 2;
+                tag.self_closing = true;
+                break;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("{{!") {
+                let start = self.pos;
+                self.skip_past(if rest.starts_with("{{!--") {
+                    "--}}"
+                } else {
+                    "}}"
+                });
+                tag.comments.push(self.slice(start));
+            } else if rest.starts_with("{{") {
+                let modifier = self.mustache();
+                tag.modifiers.push(modifier);
+            } else if rest.starts_with("as |") || rest.starts_with("as|") {
+                self.pos += 2;
+                tag.block_params = self.block_params();
+            } else if let Some(attribute) = self.attribute() {
+                tag.attributes.push(attribute);
+            }
+        }
+        tag.end = self.pos;
+        self.nodes.push(Node::StartTag(tag));
+    }
+
+    fn attribute(&mut self) -> Option<Attribute<'a>> {
+        let name =
+            self.read_while(|c| !c.is_ascii_whitespace() && !matches!(c, b'=' | b'>' | b'/'));
+        if name.text.is_empty() {
+            // a stray "/" or "=" that doesn't start an attribute
+            self.advance_char();
+            return None;
+        }
+        let before_value = self.pos;
+        self.skip_whitespace();
+        if self.peek() != Some(b'=') {
+            self.pos = before_value;
+            return Some(Attribute {
+                name,
+                value: AttributeValue::None,
+                end: before_value,
+            });
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ (b'"' | b'\'')) => {
+                self.pos += 1;
+                let start = self.pos;
+                let mut mustaches = vec![];
+                while let Some(c) = self.peek() {
+                    if c == quote {
+                        break;
+                    } else if self.rest().starts_with("{{") {
+                        let mustache = self.mustache();
+                        mustaches.push(mustache);
+                    } else {
+                        self.advance_char();
+                    }
+                }
+                let text = self.slice(start);
+                if self.peek().is_some() {
+                    self.pos += 1;
+                }
+                if mustaches.is_empty() {
+                    AttributeValue::Text(text)
+                } else {
+                    AttributeValue::Dynamic(mustaches)
+                }
+            }
+            Some(_) if self.rest().starts_with("{{") => {
+                AttributeValue::Dynamic(vec![self.mustache()])
+            }
+            _ => AttributeValue::Text(self.read_while(|c| !c.is_ascii_whitespace() && c != b'>')),
+        };
+        Some(Attribute {
+            name,
+            value,
+            end: self.pos,
+        })
+    }
+
+    fn block_params(&mut self) -> Vec<Slice<'a>> {
+        self.skip_whitespace();
+        if self.peek() != Some(b'|') {
+            return vec![];
+        }
+        self.pos += 1;
+        let mut names = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(b'|') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => names.push(self.read_while(|c| !c.is_ascii_whitespace() && c != b'|')),
+            }
+        }
+        names
+    }
+
+    fn mustache(&mut self) -> Mustache<'a> {
+        let start = self.pos;
+        self.pos += 2;
+        while matches!(self.peek(), Some(b'{' | b'~')) {
+            self.pos += 1;
+        }
+
+        let mut mustache = Mustache {
+            kind: MustacheKind::Inline,
+            name: None,
+            paths: vec![],
+            args: vec![],
+            block_params: vec![],
+            start,
+            end: start,
+        };
+        match self.peek() {
+            Some(b'/') => {
+                self.pos += 1;
+                mustache.kind = MustacheKind::BlockClose;
+                mustache.name = Some(self.read_while(is_path_char));
+                self.skip_past("}}");
+                mustache.end = self.pos;
+                return mustache;
+            }
+            Some(b'#') => {
+                self.pos += 1;
+                mustache.kind = MustacheKind::BlockOpen;
+                let name = self.read_while(is_path_char);
+                self.pos = name.start;
+                mustache.name = Some(name);
+            }
+            Some(b'^') => {
+                self.pos += 1;
+                mustache.kind = MustacheKind::Inverse;
+            }
+            Some(b'&' | b'>') => self.pos += 1,
+            _ => {}
+        }
+        self.skip_whitespace();
+        if self.rest().starts_with("else")
+            && !matches!(self.src.as_bytes().get(self.pos + 4), Some(c) if is_path_char(*c))
+        {
+            self.pos += 4;
+            mustache.kind = MustacheKind::Inverse;
+        }
+
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with("}}") || rest.starts_with("~}}") {
+                self.skip_past("}}");
+                while self.peek() == Some(b'}') {
+                    self.pos += 1;
+                }
+                break;
+            }
+            match self.peek().unwrap() {
+                b'(' | b')' => self.pos += 1,
+                quote @ (b'"' | b'\'') => {
+                    self.pos += 1;
+                    self.read_while(|c| c != quote);
+                    if self.peek().is_some() {
+                        self.pos += 1;
+                    }
+                }
+                b'@' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.read_while(is_path_char);
+                    mustache.args.push(self.slice(start));
+                }
+                _ if rest.starts_with("as |") || rest.starts_with("as|") => {
+                    self.pos += 2;
+                    mustache.block_params = self.block_params();
+                }
+                c if is_path_char(c) => {
+                    let path = self.read_while(is_path_char);
+                    if self.peek() == Some(b'=') {
+                        // hash key
+                        self.pos += 1;
+                    } else if !path
+                        .text
+                        .starts_with(|c: char| c.is_ascii_digit() || c == '-')
+                    {
+                        mustache.paths.push(path);
+                    }
+                }
+                _ => self.advance_char(),
+            }
+        }
+        mustache.end = self.pos;
+        mustache
+    }
+}
+
+fn is_tag_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'@' || c == b':' || c == b'_' || c == b'$'
+}
+
+fn is_tag_name_char(c: u8) -> bool {
+    !c.is_ascii_whitespace() && !matches!(c, b'/' | b'>' | b'{')
+}
+
+fn is_path_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'$' | b'-' | b'.') || c >= 0x80
+}
+
+#[cfg(test)]
+fn texts<'a>(slices: &[Slice<'a>]) -> Vec<&'a str> {
+    slices.iter().map(|s| s.text).collect()
+}
+
+#[test]
+fn splits_text_tags_and_mustaches() {
+    let src = r#"Hi <b class="x {{y}}">{{#each (list a b) key="@index" as |item i|}}{{item.name}}{{else}}none{{/each}}</b><!-- c -->"#;
+    let nodes = tokenize(src);
+    let kinds: Vec<_> = nodes
+        .iter()
+        .map(|n| match n {
+            Node::Text(s) => format!("text {}", s.text),
+            Node::Comment(s) => format!("comment {}", s.text),
+            Node::StartTag(t) => format!("start {}", t.name.text),
+            Node::EndTag { name, .. } => format!("end {}", name.text),
+            Node::Mustache(m) => format!("{:?} {}", m.kind, &src[m.start..m.end]),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "text Hi ",
+            "start b",
+            r#"BlockOpen {{#each (list a b) key="@index" as |item i|}}"#,
+            "Inline {{item.name}}",
+            "Inverse {{else}}",
+            "text none",
+            "BlockClose {{/each}}",
+            "end b",
+            "comment <!-- c -->",
+        ]
+    );
+
+    let Node::StartTag(tag) = &nodes[1] else {
+        panic!()
+    };
+    let AttributeValue::Dynamic(mustaches) = &tag.attribute("class").unwrap().value else {
+        panic!()
+    };
+    assert_eq!(texts(&mustaches[0].paths), vec!["y"]);
+
+    let Node::Mustache(each) = &nodes[2] else {
+        panic!()
+    };
+    assert_eq!(each.name.unwrap().text, "each");
+    assert_eq!(texts(&each.paths), vec!["each", "list", "a", "b"]);
+    assert_eq!(texts(&each.block_params), vec!["item", "i"]);
+    assert_eq!(path_head(each.paths[0]).text, "each");
+}
+
+#[test]
+fn reads_attributes_modifiers_and_block_params() {
+    let src = r#"<Foo @x={{bar}} type=button disabled {{on "click" this.go}} title='a' as |f| />"#;
+    let nodes = tokenize(src);
+    assert_eq!(nodes.len(), 1);
+    let Node::StartTag(tag) = &nodes[0] else {
+        panic!()
+    };
+    assert!(tag.is_component());
+    assert!(tag.self_closing);
+    assert_eq!(tag.end, src.len());
+    let attributes: Vec<_> = tag
+        .attributes
+        .iter()
+        .map(|a| {
+            let value = match &a.value {
+                AttributeValue::None => "none".to_string(),
+                AttributeValue::Text(text) => format!("text {}", text.text),
+                AttributeValue::Dynamic(m) => format!("dynamic {:?}", texts(&m[0].paths)),
+            };
+            (a.name.text, value)
+        })
+        .collect();
+    assert_eq!(
+        attributes,
+        vec![
+            ("@x", r#"dynamic ["bar"]"#.to_string()),
+            ("type", "text button".to_string()),
+            ("disabled", "none".to_string()),
+            ("title", "text a".to_string()),
+        ]
+    );
+    assert_eq!(texts(&tag.modifiers[0].paths), vec!["on", "this.go"]);
+    assert_eq!(texts(&tag.block_params), vec!["f"]);
+
+    let Node::StartTag(tag) = &tokenize("<b {{m @x.y z=@z}} {{! c }}>")[0] else {
+        panic!()
+    };
+    assert_eq!(texts(&tag.modifiers[0].args), vec!["@x.y", "@z"]);
+    assert_eq!(texts(&tag.comments), vec!["{{! c }}"]);
+}
@@ -5,13 +5,28 @@ use swc_ecma_codegen::Emitter;
 use swc_ecma_parser::TsSyntax;
 use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
 
-use crate::Preprocessor;
-use crate::IMPORT_ALIAS;
+use crate::{Options, Preprocessor};
+use crate::{
+    IMPORT_ALIAS, PRECOMPILE_TEMPLATE_ALIAS, SET_COMPONENT_TEMPLATE_ALIAS, TEMPLATE_ONLY_ALIAS,
+};
 
 pub fn testcase(input: &str, expected: &str) -> Result<(), swc_ecma_parser::error::Error> {
+    testcase_with_options(input, Default::default(), expected)
+}
+
+pub fn testcase_with_options(
+    input: &str,
+    options: Options,
+    expected: &str,
+) -> Result<(), swc_ecma_parser::error::Error> {
     let p = Preprocessor::new();
-    let actual = p.process(input, Default::default())?;
-    let actual_santized = actual.code.replace(IMPORT_ALIAS, "template_UUID");
+    let actual = p.process(input, options)?;
+    let actual_santized = actual
+        .code
+        .replace(IMPORT_ALIAS, "template_UUID")
+        .replace(PRECOMPILE_TEMPLATE_ALIAS, "precompileTemplate_UUID")
+        .replace(SET_COMPONENT_TEMPLATE_ALIAS, "setComponentTemplate_UUID")
+        .replace(TEMPLATE_ONLY_ALIAS, "templateOnly_UUID");
     let normalized_expected = normalize(expected);
     if actual_santized != normalized_expected {
         panic!(
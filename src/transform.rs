@@ -1,8 +1,13 @@
-use swc_common::Spanned;
+use std::collections::HashSet;
+
+use serde::Serialize;
+use swc_common::util::take::Take;
+use swc_common::{BytePos, Spanned};
 use swc_core::ecma::{
     ast::{
-        BlockStmt, CallExpr, Callee, ClassMember, ContentTagExpression, ContentTagMember, Expr,
-        ExprStmt, Ident, StaticBlock, Stmt,
+        BlockStmt, CallExpr, Callee, Class, ClassDecl, ClassMember, ContentTagExpression,
+        ContentTagMember, Decl, DefaultDecl, ExportDecl, ExportDefaultDecl, Expr, ExprStmt, Ident,
+        StaticBlock, Stmt,
     },
     visit::VisitMut,
     visit::VisitMutWith,
@@ -11,15 +16,41 @@ use swc_core::ecma::{
 #[cfg(test)]
 use swc_ecma_ast::Pass;
 use swc_ecma_ast::{
-    ContentTagContent, ExportDefaultExpr, ExprOrSpread, ModuleDecl, ModuleItem, Tpl, TplElement,
-    TsSatisfiesExpr, TsType,
+    AssignExpr, AssignOp, AssignTarget, ContentTagContent, ExportDefaultExpr, ExprOrSpread,
+    IdentName, MemberExpr, MemberProp, Module, ModuleDecl, ModuleItem, SimpleAssignTarget, Tpl,
+    TplElement, TsSatisfiesExpr, TsType,
 };
 
 use swc_atoms::Atom;
 
+use crate::hot_reload::{HotHelpers, HotReload};
+use crate::template_refs::template_references;
+use crate::template_scope::bound_template_references;
+#[cfg(test)]
+use crate::OutputFlavor;
+use crate::{ClassMemberLowering, ModuleFormat, Options, StripIndent};
+
 pub struct TransformVisitor<'a> {
     template_identifier: Ident,
     found_it: Option<&'a mut bool>,
+    class_members_outside_class: bool,
+    strip_indent: StripIndent,
+    commonjs_exports: bool,
+    precompile_template: Option<PrecompileTemplate<'a>>,
+    hot_reload: Option<HotReload>,
+}
+
+// State for the legacy `precompileTemplate` + `setComponentTemplate` lowering.
+struct PrecompileTemplate<'a> {
+    set_component_template_identifier: Ident,
+    found_it: Option<&'a mut bool>,
+    // `templateOnly` from `@ember/component/template-only`, which gives an
+    // expression template a component to be set on
+    template_only_identifier: Ident,
+    template_only_found_it: Option<&'a mut bool>,
+    // where each template reference that resolves to a JS binding starts,
+    // which decides what belongs in `scope`
+    bound_references: HashSet<BytePos>,
 }
 
 impl<'a> TransformVisitor<'a> {
@@ -27,14 +58,137 @@ impl<'a> TransformVisitor<'a> {
         TransformVisitor {
             template_identifier: id.clone(),
             found_it,
+            class_members_outside_class: false,
+            strip_indent: Default::default(),
+            commonjs_exports: false,
+            precompile_template: None,
+            hot_reload: None,
+        }
+    }
+
+    pub fn with_strip_indent(mut self, policy: StripIndent) -> Self {
+        self.strip_indent = policy;
+        self
+    }
+
+    // Applies the parts of `options` that don't need anything from the caller,
+    // which leaves the flavor's imports and hot reloading.
+    pub fn with_options(mut self, options: &Options) -> Self {
+        self = self.with_strip_indent(options.strip_indent);
+        if options.class_member_lowering == ClassMemberLowering::OutsideClass {
+            self = self.with_class_members_outside_class();
+        }
+        if options.module_format == ModuleFormat::CommonJs {
+            self = self.with_commonjs_exports();
         }
+        self
+    }
+
+    // Lower class-member templates without `static {}` blocks. Class
+    // declarations are followed by a `template(..., { component: Name })`
+    // statement, and class expressions are wrapped in the `template()` call,
+    // which returns the component it was given.
+    pub fn with_class_members_outside_class(mut self) -> Self {
+        self.class_members_outside_class = true;
+        self
     }
+
+    // Export a top-level template, or an anonymous default class that has to
+    // be wrapped, with `exports.default = ...` instead of `export default ...`.
+    // See `default_export`.
+    pub fn with_commonjs_exports(mut self) -> Self {
+        self.commonjs_exports = true;
+        self
+    }
+
+    // Emit `precompileTemplate(..., { strictMode, scope })` calls instead of
+    // `template()`. The identifier passed to `new` is then the local name of
+    // `precompileTemplate`. Class-member templates are attached with
+    // `setComponentTemplate` outside of the class body, and expression
+    // templates become `setComponentTemplate(..., templateOnly())`, since a
+    // bare `precompileTemplate()` is a template factory and not a component.
+    pub fn with_precompile_template(
+        mut self,
+        set_component_template: &Ident,
+        found_it: Option<&'a mut bool>,
+        template_only: &Ident,
+        template_only_found_it: Option<&'a mut bool>,
+    ) -> Self {
+        self.precompile_template = Some(PrecompileTemplate {
+            set_component_template_identifier: set_component_template.clone(),
+            found_it,
+            template_only_identifier: template_only.clone(),
+            template_only_found_it,
+            bound_references: Default::default(),
+        });
+        self
+    }
+
+    // Give the templates that hot_reload.rs picks an identity, register them
+    // through their slots, and add the `import.meta.hot` hook that swaps the
+    // templates when they change. See `snippets::hot_reload_hook`.
+    pub fn with_hot_reload(mut self, hot_reload: HotReload) -> Self {
+        self.hot_reload = Some(hot_reload);
+        self
+    }
+
     fn set_found_it(&mut self) {
         match self.found_it.as_mut() {
             Some(flag) => **flag = true,
             None => {}
         }
     }
+
+    // The implied default export of a top-level template. CommonJS output
+    // marks `exports` with `__esModule` first, the shape that Babel and
+    // TypeScript emit, so that their interop hands `exports.default` to a
+    // default import instead of the whole `exports` object.
+    fn default_export(&self, span: swc_common::Span, expr: Expr) -> Vec<ModuleItem> {
+        if self.commonjs_exports {
+            let assignment = ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                span,
+                expr: Box::new(Expr::Assign(AssignExpr {
+                    span,
+                    op: AssignOp::Assign,
+                    left: AssignTarget::Simple(SimpleAssignTarget::Member(MemberExpr {
+                        span,
+                        obj: Box::new(Expr::Ident(Ident::new_no_ctxt("exports".into(), span))),
+                        prop: MemberProp::Ident(IdentName::new("default".into(), span)),
+                    })),
+                    right: Box::new(expr),
+                })),
+            }));
+            // the marker has no span of its own, so comments around the
+            // template stay with the assignment
+            vec![crate::snippets::es_module_marker(), assignment]
+        } else {
+            vec![ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+                ExportDefaultExpr {
+                    span,
+                    expr: Box::new(expr),
+                },
+            ))]
+        }
+    }
+
+    fn lowers_class_members_outside_class(&self) -> bool {
+        self.class_members_outside_class || self.precompile_template.is_some()
+    }
+
+    fn template_call(
+        &self,
+        span: swc_common::Span,
+        contents: &Box<ContentTagContent>,
+        params: Box<Expr>,
+    ) -> Expr {
+        Expr::Call(CallExpr {
+            span,
+            callee: Callee::Expr(Box::new(Expr::Ident(self.template_identifier.clone()))),
+            args: vec![self.content_literal(contents), params.into()],
+            ..Default::default()
+        })
+    }
+
     fn transform_tag_expression(&mut self, expr: &ContentTagExpression) -> Expr {
         let ContentTagExpression {
             span,
@@ -43,19 +197,148 @@ impl<'a> TransformVisitor<'a> {
             ..
         } = expr;
 
+        let Some(precompile) = self.precompile_template.as_mut() else {
+            let template =
+                self.template_call(*span, contents, crate::snippets::scope_params(closing.span));
+            return match self.hot_id(*span) {
+                Some(id) => crate::snippets::hot_template(*span, &id, self.hot_helpers(), template),
+                None => template,
+            };
+        };
+
+        for flag in [
+            &mut precompile.found_it,
+            &mut precompile.template_only_found_it,
+        ] {
+            if let Some(flag) = flag.as_mut() {
+                **flag = true;
+            }
+        }
+        let set_component_template = precompile.set_component_template_identifier.clone();
+        let template_only = Expr::Call(CallExpr {
+            span: *span,
+            callee: Callee::Expr(Box::new(Expr::Ident(
+                precompile.template_only_identifier.clone(),
+            ))),
+            args: vec![],
+            ..Default::default()
+        });
+        let params = crate::snippets::precompile_template_params(
+            closing.span,
+            &precompile.scope_for(contents),
+        );
+        let template = self.template_call(*span, contents, params);
+
         Expr::Call(CallExpr {
             span: *span,
+            callee: Callee::Expr(Box::new(Expr::Ident(set_component_template))),
+            args: vec![Box::new(template).into(), Box::new(template_only).into()],
+            ..Default::default()
+        })
+    }
+
+    // Builds the expression that associates a class-member template with
+    // `component` when it is lowered outside of the class body.
+    fn class_member_call(&mut self, member: &ContentTagMember, component: Box<Expr>) -> Expr {
+        let ContentTagMember {
+            span,
+            contents,
+            closing,
+            ..
+        } = member;
+
+        self.set_found_it();
+        let Some(precompile) = self.precompile_template.as_mut() else {
+            return self.template_call(
+                *span,
+                contents,
+                crate::snippets::scope_params_with_component(closing.span, component),
+            );
+        };
+
+        if let Some(flag) = precompile.found_it.as_mut() {
+            **flag = true;
+        }
+        let set_component_template = precompile.set_component_template_identifier.clone();
+        let params = crate::snippets::precompile_template_params(
+            closing.span,
+            &precompile.scope_for(contents),
+        );
+        let template = self.template_call(*span, contents, params);
+
+        Expr::Call(CallExpr {
+            span: *span,
+            callee: Callee::Expr(Box::new(Expr::Ident(set_component_template))),
+            args: vec![Box::new(template).into(), component.into()],
+            ..Default::default()
+        })
+    }
+
+    // Lowers the class-member templates of a class declaration into
+    // statements that follow the declaration.
+    fn class_decl_followups(&mut self, decl: &mut ClassDecl) -> Vec<Stmt> {
+        take_content_tag_members(&mut decl.class)
+            .iter()
+            .map(|member| {
+                let component = Box::new(Expr::Ident(decl.ident.clone()));
+                Stmt::Expr(ExprStmt {
+                    span: member.span,
+                    expr: Box::new(self.class_member_call(member, component)),
+                })
+            })
+            .collect()
+    }
+
+    // Lowers the class-member templates of a class expression by wrapping
+    // the expression itself.
+    fn wrap_class_expr(&mut self, mut class: Box<Class>, ident: Option<Ident>) -> Expr {
+        let members = take_content_tag_members(&mut class);
+        let mut expr = Expr::Class(swc_ecma_ast::ClassExpr { ident, class });
+        for member in &members {
+            expr = self.class_member_call(member, Box::new(expr));
+        }
+        expr
+    }
+
+    // The `template()` call that a static block holds for a class-member template.
+    fn class_member_statement(
+        &self,
+        span: swc_common::Span,
+        contents: &Box<ContentTagContent>,
+        closing: swc_common::Span,
+    ) -> ExprStmt {
+        let call_expr = Expr::Call(CallExpr {
+            span,
             callee: Callee::Expr(Box::new(Expr::Ident(self.template_identifier.clone()))),
             args: vec![
                 self.content_literal(contents),
-                crate::snippets::scope_params(closing.span).into(),
+                crate::snippets::scope_params_with_this(closing).into(),
             ],
             ..Default::default()
-        })
+        });
+        ExprStmt {
+            span,
+            expr: Box::new(call_expr),
+        }
+    }
+
+    // The identity of the template that `span` covers, when hot reloading
+    // gives it one. See hot_reload.rs.
+    fn hot_id(&mut self, span: swc_common::Span) -> Option<String> {
+        self.hot_reload
+            .as_mut()
+            .and_then(|hot_reload| hot_reload.id(span.lo))
+            .map(str::to_owned)
+    }
+
+    // The bindings that hot reloading adds, for a template that `hot_id` gave
+    // an identity.
+    fn hot_helpers(&self) -> &HotHelpers {
+        self.hot_reload.as_ref().unwrap().helpers()
     }
 
     fn content_literal(&self, contents: &Box<ContentTagContent>) -> ExprOrSpread {
-        let stripped_content = strip_indent(&contents.value);
+        let stripped_content = strip_indent(&contents.value, self.strip_indent).text;
         Box::new(Expr::Tpl(Tpl {
             span: contents.span,
             exprs: vec![],
@@ -70,7 +353,44 @@ impl<'a> TransformVisitor<'a> {
     }
 }
 
-fn escape_template_literal(input: &Atom) -> Atom {
+impl<'a> PrecompileTemplate<'a> {
+    // The template references that resolve to JS bindings, deduplicated and
+    // in order of first use.
+    fn scope_for(&self, contents: &ContentTagContent) -> Vec<String> {
+        let mut scope: Vec<String> = vec![];
+        for reference in template_references(&contents.value) {
+            let start = contents.span.lo + BytePos(reference.start as u32);
+            if self.bound_references.contains(&start) && !scope.contains(&reference.name) {
+                scope.push(reference.name);
+            }
+        }
+        scope
+    }
+}
+
+fn take_content_tag_members(class: &mut Class) -> Vec<ContentTagMember> {
+    let (members, rest): (Vec<_>, Vec<_>) = class
+        .body
+        .drain(..)
+        .partition(|member| matches!(member, ClassMember::ContentTagMember(_)));
+    class.body = rest;
+    members
+        .into_iter()
+        .filter_map(|member| match member {
+            ClassMember::ContentTagMember(member) => Some(member),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_content_tag_members(class: &Class) -> bool {
+    class
+        .body
+        .iter()
+        .any(|member| matches!(member, ClassMember::ContentTagMember(_)))
+}
+
+pub fn escape_template_literal(input: &Atom) -> Atom {
     input
         .replace("\\", "\\\\")
         .replace("`", "\\`")
@@ -78,11 +398,51 @@ fn escape_template_literal(input: &Atom) -> Atom {
         .into()
 }
 
-fn strip_indent(input: &str) -> String {
+// What `strip_indent` removed from a template, so that tooling can reproduce
+// the compiler's view of the template without reimplementing the policy.
+#[derive(Serialize, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndentStrip {
+    // blank lines removed from the start and end of the template
+    pub leading_lines: usize,
+    pub trailing_lines: usize,
+    // columns of common indentation removed from every remaining line
+    pub indent: usize,
+}
+
+// Where a line of the stripped text came from in the original contents.
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineOffset {
+    // zero-based line within the original contents
+    pub original_line: usize,
+    // the character in that line that became the first character of the
+    // stripped line. When a tab straddles the removed indentation, this is
+    // the tab, and the stripped line starts with spaces standing in for it.
+    pub original_column: usize,
+}
+
+pub struct Stripped {
+    pub text: String,
+    pub strip: IndentStrip,
+    // one entry per line of `text`
+    pub line_offsets: Vec<LineOffset>,
+}
+
+pub fn strip_indent(input: &str, policy: StripIndent) -> Stripped {
     let lines: Vec<&str> = input.lines().collect();
 
-    if lines.len() <= 1 {
-        return input.to_string();
+    if policy == StripIndent::Off || lines.len() <= 1 {
+        return Stripped {
+            text: input.to_string(),
+            strip: Default::default(),
+            line_offsets: (0..input.split('\n').count())
+                .map(|original_line| LineOffset {
+                    original_line,
+                    original_column: 0,
+                })
+                .collect(),
+        };
     }
 
     let start = lines
@@ -96,11 +456,67 @@ fn strip_indent(input: &str) -> String {
         .unwrap_or(0);
 
     if start >= end {
-        return String::new();
+        return Stripped {
+            text: String::new(),
+            strip: IndentStrip {
+                leading_lines: lines.len(),
+                ..Default::default()
+            },
+            line_offsets: vec![],
+        };
     }
 
+    let mut strip = IndentStrip {
+        leading_lines: start,
+        trailing_lines: lines.len() - end,
+        indent: 0,
+    };
     let lines = &lines[start..end];
 
+    let tab_width = match policy {
+        StripIndent::DedentWithTabWidth(width) => Some(width),
+        StripIndent::Dedent => None,
+        StripIndent::Off | StripIndent::TrimOnly => {
+            return Stripped {
+                text: lines.join("\n"),
+                strip,
+                line_offsets: (start..end)
+                    .map(|original_line| LineOffset {
+                        original_line,
+                        original_column: 0,
+                    })
+                    .collect(),
+            }
+        }
+    };
+
+    strip.indent = common_indent(lines, tab_width);
+
+    let mut line_offsets = Vec::with_capacity(lines.len());
+    let text = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let (stripped, original_column) = remove_indent(line, strip.indent, tab_width);
+            line_offsets.push(LineOffset {
+                original_line: start + index,
+                original_column,
+            });
+            stripped
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Stripped {
+        text,
+        strip,
+        line_offsets,
+    }
+}
+
+// The indentation shared by every non-blank line. Without a tab width, a mix
+// of tabs and spaces can't be compared, so nothing is considered common.
+fn common_indent(lines: &[&str], tab_width: Option<usize>) -> usize {
     let mut min_indent: Option<usize> = None;
     let mut has_spaces = false;
     let mut has_tabs = false;
@@ -114,71 +530,158 @@ fn strip_indent(input: &str) -> String {
         let indent_size = line.len() - content.len();
         let indent_chars = &line[..indent_size];
 
-        has_spaces |= indent_chars.contains(' ');
-        has_tabs |= indent_chars.contains('\t');
+        let indent_size = match tab_width {
+            Some(width) => indent_columns(indent_chars, width),
+            None => {
+                has_spaces |= indent_chars.contains(' ');
+                has_tabs |= indent_chars.contains('\t');
 
-        if has_spaces && has_tabs {
-            return lines.join("\n");
-        }
+                if has_spaces && has_tabs {
+                    return 0;
+                }
+                indent_size
+            }
+        };
 
         min_indent = Some(min_indent.map_or(indent_size, |current| current.min(indent_size)));
     }
 
-    let min_indent = min_indent.unwrap_or(0);
+    min_indent.unwrap_or(0)
+}
 
-    if min_indent == 0 {
-        return lines.join("\n");
+fn indent_columns(indent_chars: &str, tab_width: usize) -> usize {
+    indent_chars
+        .chars()
+        .fold(0, |column, c| advance_column(column, c, tab_width))
+}
+
+fn advance_column(column: usize, c: char, tab_width: usize) -> usize {
+    if c == '\t' && tab_width > 0 {
+        (column / tab_width + 1) * tab_width
+    } else {
+        column + 1
     }
+}
 
-    lines
-        .iter()
-        .map(|line| {
-            if line.len() >= min_indent {
-                &line[min_indent..]
+// Returns the line without its indentation, along with how many characters
+// were removed from the front of it.
+fn remove_indent(line: &str, indent: usize, tab_width: Option<usize>) -> (String, usize) {
+    if indent == 0 {
+        return (line.to_string(), 0);
+    }
+
+    let Some(width) = tab_width else {
+        return if line.len() >= indent {
+            (line[indent..].to_string(), line[..indent].chars().count())
+        } else {
+            (line.to_string(), 0)
+        };
+    };
+
+    let mut column = 0;
+    for (char_index, (offset, c)) in line.char_indices().enumerate() {
+        if column >= indent || !c.is_whitespace() {
+            // a tab that straddles the cut leaves its remaining columns as spaces
+            let removed = if column > indent {
+                char_index - 1
             } else {
-                line
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+                char_index
+            };
+            return (
+                format!("{}{}", " ".repeat(column - indent), &line[offset..]),
+                removed,
+            );
+        }
+        column = advance_column(column, c, width);
+    }
+    // a whitespace-only line that is shorter than the indent
+    (String::new(), line.chars().count())
 }
 
 impl<'a> VisitMut for TransformVisitor<'a> {
+    fn visit_mut_module(&mut self, n: &mut Module) {
+        if let Some(precompile) = self.precompile_template.as_mut() {
+            precompile.bound_references = bound_template_references(n);
+        }
+        n.visit_mut_children_with(self);
+        if let Some(hot_reload) = &self.hot_reload {
+            if hot_reload.used() {
+                n.body.extend(crate::snippets::hot_reload_hook(
+                    hot_reload.js_hash(),
+                    hot_reload.helpers(),
+                ));
+            }
+        }
+    }
+
     fn visit_mut_expr(&mut self, n: &mut Expr) {
         n.visit_mut_children_with(self);
         if let Expr::ContentTagExpression(expr) = n {
             *n = self.transform_tag_expression(expr);
             self.set_found_it();
+        } else if let Expr::Class(class_expr) = n {
+            if self.lowers_class_members_outside_class()
+                && has_content_tag_members(&class_expr.class)
+            {
+                let class = class_expr.class.take();
+                let ident = class_expr.ident.take();
+                *n = self.wrap_class_expr(class, ident);
+            }
         }
     }
 
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+        if !self.lowers_class_members_outside_class() {
+            return;
+        }
+
+        let mut stmts_updated = Vec::with_capacity(stmts.len());
+        for mut stmt in stmts.drain(..) {
+            let followups = match &mut stmt {
+                Stmt::Decl(Decl::Class(decl)) => self.class_decl_followups(decl),
+                _ => vec![],
+            };
+            stmts_updated.push(stmt);
+            stmts_updated.extend(followups);
+        }
+        *stmts = stmts_updated;
+    }
+
     fn visit_mut_class_member(&mut self, n: &mut ClassMember) {
         n.visit_mut_children_with(self);
+        if self.lowers_class_members_outside_class() {
+            // handled by whatever holds the class, see `visit_mut_stmts`,
+            // `visit_mut_expr` and `lower_class_declarations`
+            return;
+        }
         if let ClassMember::ContentTagMember(ContentTagMember {
             span,
-            opening,
             contents,
             closing,
+            ..
         }) = n
         {
-            let call_expr = Expr::Call(CallExpr {
-                span: *span,
-                callee: Callee::Expr(Box::new(Expr::Ident(self.template_identifier.clone()))),
-                args: vec![
-                    self.content_literal(contents),
-                    crate::snippets::scope_params_with_this(closing.span).into(),
-                ],
-                ..Default::default()
-            });
-            let call_statement = ExprStmt {
-                span: *span,
-                expr: Box::new(call_expr),
+            let statement = match self.hot_id(*span) {
+                Some(id) => crate::snippets::hot_class_template(
+                    *span,
+                    &id,
+                    self.hot_helpers(),
+                    self.template_call(
+                        *span,
+                        contents,
+                        crate::snippets::scope_params(closing.span),
+                    ),
+                ),
+                None => Stmt::Expr(self.class_member_statement(*span, contents, closing.span)),
             };
+            // the static block spans the whole member, so comments keyed to
+            // either end of the template stay attached to it
             *n = ClassMember::StaticBlock(StaticBlock {
-                span: opening.span,
+                span: *span,
                 body: BlockStmt {
                     span: *span,
-                    stmts: vec![Stmt::Expr(call_statement)],
+                    stmts: vec![statement],
                     ..Default::default()
                 },
             });
@@ -189,25 +692,19 @@ impl<'a> VisitMut for TransformVisitor<'a> {
     fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
         let mut items_updated = Vec::with_capacity(items.len());
         for item in items.drain(..) {
+            // the export takes over the statement's span, so that comments
+            // around the statement stay around the export
             if let Some(content_tag) = content_tag_expression_statement(&item) {
-                items_updated.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
-                    ExportDefaultExpr {
-                        span: content_tag.span,
-                        expr: Box::new(self.transform_tag_expression(&content_tag)),
-                    },
-                )));
+                let expr = self.transform_tag_expression(&content_tag);
+                items_updated.extend(self.default_export(item.span(), expr));
                 self.set_found_it();
             } else if let Some(satisfies) = content_tag_satisfies_expression_statement(&item) {
-                items_updated.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
-                    ExportDefaultExpr {
-                        span: satisfies.template.span,
-                        expr: Box::new(Expr::TsSatisfies(TsSatisfiesExpr {
-                            expr: Box::new(self.transform_tag_expression(&satisfies.template)),
-                            type_ann: satisfies.ts_type.clone(),
-                            span: satisfies.ts_type.span(),
-                        })),
-                    },
-                )));
+                let expr = Expr::TsSatisfies(TsSatisfiesExpr {
+                    expr: Box::new(self.transform_tag_expression(&satisfies.template)),
+                    type_ann: satisfies.ts_type.clone(),
+                    span: satisfies.ts_type.span(),
+                });
+                items_updated.extend(self.default_export(item.span(), expr));
                 self.set_found_it();
             } else {
                 items_updated.push(item);
@@ -215,7 +712,53 @@ impl<'a> VisitMut for TransformVisitor<'a> {
         }
 
         *items = items_updated;
-        items.visit_mut_children_with(self)
+        items.visit_mut_children_with(self);
+
+        if self.lowers_class_members_outside_class() {
+            self.lower_class_declarations(items);
+        }
+    }
+}
+
+impl<'a> TransformVisitor<'a> {
+    fn lower_class_declarations(&mut self, items: &mut Vec<ModuleItem>) {
+        let mut items_updated = Vec::with_capacity(items.len());
+        for mut item in items.drain(..) {
+            let followups = match &mut item {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Class(decl)))
+                | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                    decl: Decl::Class(decl),
+                    ..
+                })) => self.class_decl_followups(decl),
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                    span,
+                    decl: DefaultDecl::Class(class_expr),
+                })) if has_content_tag_members(&class_expr.class) => match &class_expr.ident {
+                    Some(ident) => {
+                        let mut decl = ClassDecl {
+                            ident: ident.clone(),
+                            declare: false,
+                            class: class_expr.class.take(),
+                        };
+                        let followups = self.class_decl_followups(&mut decl);
+                        class_expr.class = decl.class;
+                        followups
+                    }
+                    None => {
+                        // an anonymous default export has no name to refer
+                        // to afterwards, so it becomes a wrapped expression
+                        let class = class_expr.class.take();
+                        let expr = self.wrap_class_expr(class, None);
+                        items_updated.extend(self.default_export(*span, expr));
+                        continue;
+                    }
+                },
+                _ => vec![],
+            };
+            items_updated.push(item);
+            items_updated.extend(followups.into_iter().map(ModuleItem::Stmt));
+        }
+        *items = items_updated;
     }
 }
 
@@ -265,20 +808,40 @@ impl<'a> TransformVisitor<'a> {
     }
 }
 
+// A visitor configured the way `process` would for `options`, with the
+// imports it needs under their plain names.
+#[cfg(test)]
+fn test_visitor(options: &Options) -> TransformVisitor<'static> {
+    let ident = |name: &str| Ident::new_no_ctxt(name.into(), Default::default());
+    match options.flavor {
+        OutputFlavor::Template => {
+            TransformVisitor::new(&ident("template"), None).with_options(options)
+        }
+        OutputFlavor::PrecompileTemplate => {
+            TransformVisitor::new(&ident("precompileTemplate"), None)
+                .with_options(options)
+                .with_precompile_template(
+                    &ident("setComponentTemplate"),
+                    None,
+                    &ident("templateOnly"),
+                    None,
+                )
+        }
+    }
+}
+
 macro_rules! test {
     ($test_name:ident, $input:expr, $expected:expr) => {
+        test!($test_name, Default::default(), $input, $expected);
+    };
+    ($test_name:ident, $options:expr, $input:expr, $expected:expr) => {
         #[test]
         fn $test_name() {
+            let options: Options = $options;
             swc_core::ecma::transforms::testing::test_inline_input_output(
                 Default::default(),
                 true.into(),
-                |_| {
-                    TransformVisitor::new(
-                        &Ident::new_no_ctxt("template".into(), Default::default()),
-                        None,
-                    )
-                    .into_pass()
-                },
+                |_| test_visitor(&options).into_pass(),
                 $input,
                 $expected,
             )
@@ -451,3 +1014,271 @@ test!(
       content here
     </pre>`, { eval() { return eval(arguments[0]) }})"#
 );
+
+test!(
+    precompile_template_expression,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"let x = <template>Hello</template>"#,
+    r#"let x = setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), templateOnly())"#
+);
+
+test!(
+    precompile_template_scope,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"import Foo from "./foo";
+    import { on } from "@ember/modifier";
+    function go() {}
+    let x = <template><Foo {{on "click" go}} @missing={{missing}} /></template>"#,
+    r#"import Foo from "./foo";
+    import { on } from "@ember/modifier";
+    function go() {}
+    let x = setComponentTemplate(precompileTemplate(`<Foo {{on "click" go}} @missing={{missing}} />`, { strictMode: true, scope: () => ({ Foo, on, go }) }), templateOnly())"#
+);
+
+test!(
+    precompile_template_scope_is_lexical,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"function unrelated() { let x = 1; return x; }
+    function f(y) {
+      return <template>{{x}}{{y}}</template>;
+    }"#,
+    r#"function unrelated() { let x = 1; return x; }
+    function f(y) {
+      return setComponentTemplate(precompileTemplate(`{{x}}{{y}}`, { strictMode: true, scope: () => ({ y }) }), templateOnly());
+    }"#
+);
+
+test!(
+    precompile_template_member,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"class X { <template>Hello</template> } "#,
+    r#"class X {
+    }
+    setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), X);"#
+);
+
+test!(
+    precompile_template_exported_member,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"export class X { <template>Hello</template> } "#,
+    r#"export class X {
+    }
+    setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), X);"#
+);
+
+test!(
+    precompile_template_nested_member,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"function f() {
+      class X { <template>Hello</template> }
+      return X;
+    }"#,
+    r#"function f() {
+      class X {
+      }
+      setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), X);
+      return X;
+    }"#
+);
+
+test!(
+    precompile_template_expression_inside_class_member,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"class X { thing = <template>Hello</template> } "#,
+    r#"class X {
+        thing = setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), templateOnly());
+    }"#
+);
+
+test!(
+    precompile_template_class_member_inside_expression,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"let x = class { <template>Hello</template> } "#,
+    r#"let x = setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), class {
+    });"#
+);
+
+test!(
+    precompile_template_export_default,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"<template>Hello</template>"#,
+    r#"export default setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), templateOnly());"#
+);
+
+test!(
+    precompile_template_anonymous_default_class,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"export default class extends Component { <template>Hello</template> }"#,
+    r#"export default setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), class extends Component {
+    });"#
+);
+
+test!(
+    precompile_template_named_default_class,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"export default class X extends Component { <template>Hello</template> }"#,
+    r#"export default class X extends Component {
+    }
+    setComponentTemplate(precompileTemplate(`Hello`, { strictMode: true, scope: () => ({}) }), X);"#
+);
+
+test!(
+    precompile_template_strips_indentation,
+    Options {
+        flavor: OutputFlavor::PrecompileTemplate,
+        ..Default::default()
+    },
+    r#"class X {
+  <template>
+    <span>Hello</span>
+  </template>
+}"#,
+    r#"class X {
+}
+setComponentTemplate(precompileTemplate(`<span>Hello</span>`, { strictMode: true, scope: () => ({}) }), X);"#
+);
+
+test!(
+    outside_class_member,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"class X { <template>Hello</template> } "#,
+    r#"class X {
+    }
+    template(`Hello`, { component: X, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_export_default_named,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"export default class X extends Component { <template>Hello</template> } "#,
+    r#"export default class X extends Component {
+    }
+    template(`Hello`, { component: X, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_export_default_anonymous,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"export default class extends Component { <template>Hello</template> } "#,
+    r#"export default template(`Hello`, { component: class extends Component {
+    }, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_export_default_anonymous_commonjs,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        module_format: ModuleFormat::CommonJs,
+        ..Default::default()
+    },
+    r#"export default class extends Component { <template>Hello</template> } "#,
+    r#"Object.defineProperty(exports, "__esModule", { value: true });
+    exports.default = template(`Hello`, { component: class extends Component {
+    }, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_member_inside_expression,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"let x = class { <template>Hello</template> } "#,
+    r#"let x = template(`Hello`, { component: class {
+    }, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_keeps_other_members,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"class X {
+  greeting = "hi";
+  <template>
+    {{this.greeting}}
+  </template>
+}"#,
+    r#"class X {
+  greeting = "hi";
+}
+template(`{{this.greeting}}`, { component: X, eval() { return eval(arguments[0]) }});"#
+);
+
+test!(
+    outside_class_expression_untouched,
+    Options {
+        class_member_lowering: ClassMemberLowering::OutsideClass,
+        ..Default::default()
+    },
+    r#"class X { thing = <template>Hello</template> } "#,
+    r#"class X {
+        thing = template(`Hello`, { eval() { return eval(arguments[0]) }},);
+    }"#
+);
+
+#[test]
+fn strips_mixed_indentation_with_tab_width() {
+    let stripped = strip_indent(
+        "\n\t  <div>\n  \t  hi\n\t   </div>\n",
+        StripIndent::DedentWithTabWidth(2),
+    );
+    assert_eq!(stripped.text, "<div>\n  hi\n </div>");
+    assert_eq!(
+        stripped.strip,
+        IndentStrip {
+            leading_lines: 1,
+            trailing_lines: 0,
+            indent: 4,
+        }
+    );
+}
+
+#[test]
+fn tab_straddling_the_indent_becomes_spaces() {
+    let stripped = strip_indent("\n  <div>\n\t\thi\n", StripIndent::DedentWithTabWidth(4));
+    assert_eq!(stripped.text, "<div>\n  \thi");
+}
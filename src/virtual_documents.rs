@@ -0,0 +1,206 @@
+// Splits a source file into virtual documents for editor tooling: the JS with
+// every `<template>` blanked out, and each template's contents on their own,
+// like a standalone `.hbs` file.
+//
+// The blanked script keeps every character where it was in bytes as well as
+// in UTF-16 code units: line breaks are kept, and every other character of a
+// template becomes whitespace of the same width in both, see `blank`. A
+// template document is a slice of the source. So both kinds map offsets in
+// either unit to the source by adding a fixed offset.
+
+use crate::locate::Occurrence;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualDocumentKind {
+    Script,
+    Template,
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualDocument {
+    pub kind: VirtualDocumentKind,
+    pub text: String,
+    // where `text` starts in the source, in bytes and in UTF-16 code units
+    start: usize,
+    start_utf16: usize,
+    // the length of `text` in UTF-16 code units
+    len_utf16: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualDocuments {
+    pub script: VirtualDocument,
+    // in the same order as the occurrences they were made from
+    pub templates: Vec<VirtualDocument>,
+}
+
+impl VirtualDocuments {
+    // `occurrences` must come from parsing `src`.
+    pub fn new(src: &str, occurrences: &[Occurrence]) -> Self {
+        let mut script = String::with_capacity(src.len());
+        let mut copied = 0;
+        for occurrence in occurrences {
+            let range = occurrence.range();
+            script.push_str(&src[copied..range.start_byte()]);
+            script.extend(src[range.start_byte()..range.end_byte()].chars().map(blank));
+            copied = range.end_byte();
+        }
+        script.push_str(&src[copied..]);
+
+        let templates = occurrences
+            .iter()
+            .map(|occurrence| {
+                let content = occurrence.content_range();
+                let text = &src[content.start_byte()..content.end_byte()];
+                VirtualDocument {
+                    kind: VirtualDocumentKind::Template,
+                    text: text.to_string(),
+                    start: content.start_byte(),
+                    start_utf16: src[..content.start_byte()].encode_utf16().count(),
+                    len_utf16: text.encode_utf16().count(),
+                }
+            })
+            .collect();
+
+        VirtualDocuments {
+            script: VirtualDocument {
+                kind: VirtualDocumentKind::Script,
+                len_utf16: script.encode_utf16().count(),
+                text: script,
+                start: 0,
+                start_utf16: 0,
+            },
+            templates,
+        }
+    }
+}
+
+impl VirtualDocument {
+    // The source offset for a byte offset into `text`, or `None` past its end.
+    pub fn to_source_byte(&self, offset: usize) -> Option<usize> {
+        (offset <= self.text.len()).then(|| self.start + offset)
+    }
+
+    // The offset into `text` for a byte offset into the source, or `None` when
+    // the source offset is outside of this document.
+    pub fn from_source_byte(&self, offset: usize) -> Option<usize> {
+        offset
+            .checked_sub(self.start)
+            .filter(|offset| *offset <= self.text.len())
+    }
+
+    // Like `to_source_byte`, for offsets in UTF-16 code units.
+    pub fn to_source_utf16(&self, offset: usize) -> Option<usize> {
+        (offset <= self.len_utf16).then(|| self.start_utf16 + offset)
+    }
+
+    // Like `from_source_byte`, for offsets in UTF-16 code units.
+    pub fn from_source_utf16(&self, offset: usize) -> Option<usize> {
+        offset
+            .checked_sub(self.start_utf16)
+            .filter(|offset| *offset <= self.len_utf16)
+    }
+}
+
+// Whitespace as wide as `c` in bytes and in UTF-16 code units, or `c` itself
+// for a line break, including the line and paragraph separators that JS also
+// ends lines at. Wider whitespace than a space is only needed for templates
+// that aren't ASCII, and all of it is whitespace to a JS parser.
+fn blank(c: char) -> &'static str {
+    match (c, c.len_utf8()) {
+        ('\n', _) => "\n",
+        ('\r', _) => "\r",
+        ('\u{2028}', _) => "\u{2028}",
+        ('\u{2029}', _) => "\u{2029}",
+        (_, 1) => " ",
+        // no-break space
+        (_, 2) => "\u{a0}",
+        // ideographic space
+        (_, 3) => "\u{3000}",
+        // two code units, so two characters
+        _ => "\u{a0}\u{a0}",
+    }
+}
+
+#[cfg(test)]
+fn documents(src: &str) -> VirtualDocuments {
+    let occurrences = crate::Preprocessor::new()
+        .parse(src, Default::default())
+        .unwrap();
+    VirtualDocuments::new(src, &occurrences)
+}
+
+#[cfg(test)]
+const SRC: &str = "const a = \"😀\";\nconst b = <template>\n  Hi 😀 {{a}}\n</template>;\nclass C {\n  <template>é</template>\n}\n";
+
+#[test]
+fn blanks_templates_in_the_script() {
+    let documents = documents(SRC);
+    assert_eq!(documents.script.text.len(), SRC.len());
+    assert_eq!(
+        documents.script.text.encode_utf16().count(),
+        SRC.encode_utf16().count()
+    );
+    assert_eq!(
+        documents.script.text.lines().collect::<Vec<_>>(),
+        vec![
+            "const a = \"😀\";".to_string(),
+            format!("const b = {}", " ".repeat(10)),
+            format!("{}\u{a0}\u{a0}{}", " ".repeat(5), " ".repeat(6)),
+            format!("{};", " ".repeat(11)),
+            "class C {".to_string(),
+            format!("{}\u{a0}{}", " ".repeat(12), " ".repeat(11)),
+            "}".to_string(),
+        ]
+    );
+    assert_eq!(blank('中').len(), '中'.len_utf8());
+    assert_eq!(blank('中').encode_utf16().count(), 1);
+    assert_eq!(blank('\u{2028}'), "\u{2028}");
+    assert_eq!(blank('\u{2029}'), "\u{2029}");
+    let templates: Vec<_> = documents.templates.iter().map(|t| &t.text).collect();
+    assert_eq!(templates, vec!["\n  Hi 😀 {{a}}\n", "é"]);
+}
+
+#[test]
+fn every_byte_offset_maps_into_the_source() {
+    let documents = documents(SRC);
+    for document in std::iter::once(&documents.script).chain(&documents.templates) {
+        for offset in 0..=document.text.len() {
+            let source = document.to_source_byte(offset).unwrap();
+            assert!(source <= SRC.len());
+            assert_eq!(document.from_source_byte(source), Some(offset));
+            if document.kind == VirtualDocumentKind::Template
+                && document.text.is_char_boundary(offset)
+            {
+                assert_eq!(
+                    &SRC[source..source + document.text.len() - offset],
+                    &document.text[offset..]
+                );
+            }
+        }
+        assert_eq!(document.to_source_byte(document.text.len() + 1), None);
+    }
+    assert_eq!(documents.templates[1].from_source_byte(0), None);
+}
+
+#[test]
+fn every_utf16_offset_maps_into_the_source() {
+    let documents = documents(SRC);
+    let source_len = SRC.encode_utf16().count();
+    for document in std::iter::once(&documents.script).chain(&documents.templates) {
+        let len = document.text.encode_utf16().count();
+        for offset in 0..=len {
+            let source = document.to_source_utf16(offset).unwrap();
+            assert!(source <= source_len);
+            assert_eq!(document.from_source_utf16(source), Some(offset));
+        }
+        assert_eq!(document.to_source_utf16(len + 1), None);
+    }
+
+    let template = &documents.templates[0];
+    let hi = SRC.find("Hi").unwrap();
+    assert_eq!(
+        template.to_source_utf16(3),
+        Some(SRC[..hi].encode_utf16().count())
+    );
+}
@@ -0,0 +1,90 @@
+// Runs against the native Node-API addon, which is built separately from the
+// wasm package:
+//
+//   cargo build --features node && pnpm run ci:native
+//
+// CONTENT_TAG_NATIVE can point at a library from another build profile.
+const path = require("path");
+const chai = require("chai");
+const { codeEquality } = require("code-equality-assertions/chai");
+
+chai.use(codeEquality);
+
+const { expect } = chai;
+
+function loadAddon() {
+  let library =
+    process.env.CONTENT_TAG_NATIVE ??
+    path.join(
+      __dirname,
+      "../../target/debug",
+      {
+        darwin: "libcontent_tag.dylib",
+        win32: "content_tag.dll",
+      }[process.platform] ?? "libcontent_tag.so",
+    );
+  // `require` only loads addons with a `.node` extension, `dlopen` takes any
+  let addon = { exports: {} };
+  process.dlopen(addon, library);
+  return addon.exports;
+}
+
+const { Preprocessor } = loadAddon();
+
+const p = new Preprocessor();
+
+describe("native addon", function () {
+  it("can call process", function () {
+    let output = p.process("<template>Hi</template>");
+
+    expect(output.code).to
+      .equalCode(`import { template } from "@ember/template-compiler";
+  export default template(\`Hi\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`);
+    expect(JSON.parse(output.map)).to.have.property("mappings");
+  });
+
+  it("can call parse", function () {
+    let output = p.parse("<template>Hello!</template>");
+
+    expect(output).to.have.length(1);
+    expect(output[0]).to.include({
+      type: "expression",
+      tagName: "template",
+      contents: "Hello!",
+    });
+  });
+
+  it("reads options", function () {
+    let output = p.process("<template>Hi</template>", {
+      module_format: "commonjs",
+    });
+
+    expect(output.code).to.match(/require\("@ember\/template-compiler"\)/);
+  });
+
+  it("throws for option values it doesn't accept", function () {
+    expect(function () {
+      p.process("<template>Hi</template>", { module_format: "cjs" });
+    }).to.throw(
+      'Invalid option `module_format`: expected one of "module", "commonjs"',
+    );
+  });
+
+  it("throws parse errors with source_code attached", function () {
+    let parseError;
+    try {
+      p.process(`class {`, { filename: "a.gjs" });
+    } catch (err) {
+      parseError = err;
+    }
+    expect(parseError).to.be.an("error");
+    expect(parseError.message).to.match(/^Parse Error at a\.gjs:1:/);
+    expect(parseError)
+      .to.have.property("source_code")
+      .matches(/Expected ident.*class \{/s);
+  });
+});
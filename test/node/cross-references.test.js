@@ -0,0 +1,42 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`crossReferences`, function () {
+  it("links template references to declarations", function () {
+    let src = `import Foo from "./foo";\nconst x = 1;\n<template><Foo @x={{x}} />{{missing}}<Foo /></template>`;
+    let xrefs = p.crossReferences(src);
+    let text = (range) => src.slice(range.startByte, range.endByte);
+
+    expect(
+      xrefs.references.map(({ name, kind, definition }) => ({
+        name,
+        kind,
+        definition: definition && definition.startByte,
+      })),
+    ).to.eql([
+      { name: "Foo", kind: "component", definition: 7 },
+      { name: "x", kind: "path", definition: 31 },
+      { name: "missing", kind: "path", definition: null },
+      { name: "Foo", kind: "component", definition: 7 },
+    ]);
+
+    expect(
+      xrefs.bindings.map(({ name, definition, templateReferences }) => ({
+        name,
+        definition: text(definition),
+        references: templateReferences.map((r) => r.startByte),
+      })),
+    ).to.eql([
+      { name: "Foo", definition: "Foo", references: [49, 76] },
+      { name: "x", definition: "x", references: [58] },
+    ]);
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.crossReferences("import ;")).to.throw("Parse Error");
+  });
+});
@@ -0,0 +1,52 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`parseEstree`, function () {
+  it("returns templates as GlimmerTemplate nodes", function () {
+    let src = `const a = "😀";\nexport default <template>Hi</template>;\nclass B {\n  <template>b</template>\n}\n`;
+    let program = p.parseEstree(src);
+
+    expect(program.type).to.equal("Program");
+    expect(program.body.map((n) => n.type)).to.eql([
+      "VariableDeclaration",
+      "ExportDefaultDeclaration",
+      "ClassDeclaration",
+    ]);
+
+    let template = program.body[1].declaration;
+    expect(template).to.eql({
+      type: "GlimmerTemplate",
+      contents: "Hi",
+      range: [31, 54],
+      loc: { start: { line: 2, column: 15 }, end: { line: 2, column: 38 } },
+    });
+    expect(src.slice(...template.range)).to.equal("<template>Hi</template>");
+
+    let member = program.body[2].body.body[0];
+    expect(member.type).to.equal("GlimmerTemplate");
+    expect(member.contents).to.equal("b");
+  });
+
+  it("includes comments and tokens", function () {
+    let program = p.parseEstree(`// a\nlet x = <template>x</template>;`);
+
+    expect(program.comments.map((c) => [c.type, c.value])).to.eql([
+      ["Line", " a"],
+    ]);
+    expect(program.tokens.map((t) => [t.type, t.value])).to.eql([
+      ["Identifier", "let"],
+      ["Identifier", "x"],
+      ["Punctuator", "="],
+      ["GlimmerTemplate", "<template>x</template>"],
+      ["Punctuator", ";"],
+    ]);
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.parseEstree("const a = ;")).to.throw("Parse Error");
+  });
+});
@@ -0,0 +1,27 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`importUsage`, function () {
+  it("counts uses in templates", function () {
+    let src = `import Foo from "./foo";\nimport { on, unused } from "./helpers";\n<template><Foo {{on "click" go}} /></template>`;
+    let usage = p.importUsage(src);
+
+    expect(
+      usage.unusedImports.map(({ local, source }) => ({ local, source })),
+    ).to.eql([{ local: "unused", source: "./helpers" }]);
+    let { range } = usage.unusedImports[0];
+    expect(src.slice(range.startByte, range.endByte)).to.equal("unused");
+
+    expect(
+      usage.unresolvedReferences.map(({ name, kind }) => ({ name, kind })),
+    ).to.eql([{ name: "go", kind: "path" }]);
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.importUsage("import ;")).to.throw("Parse Error");
+  });
+});
@@ -0,0 +1,96 @@
+import chai from "chai";
+import { Preprocessor, createLintProcessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`preprocess`, function () {
+  it("replaces templates with placeholders of the same length", function () {
+    let src = `const a = <template>Hi</template>;\nclass B {\n  <template>b</template>\n}\n`;
+    let blocks = p.preprocess(src, { filename: "a.gts" });
+
+    expect(blocks.map((b) => b.filename)).to.eql(["0.ts", "1.hbs", "2.hbs"]);
+    expect(blocks[0].text).to.equal(
+      "const a = `                     `;\nclass B {\n  static{/*          */}\n}\n",
+    );
+    expect(blocks[0].text.length).to.equal(src.length);
+    expect(blocks[1]).to.eql({
+      kind: "template",
+      text: "Hi",
+      filename: "1.hbs",
+      line: 1,
+      column: 21,
+      offset: 20,
+    });
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.preprocess("const a = ;")).to.throw("Parse Error");
+  });
+});
+
+describe(`postprocess`, function () {
+  it("translates template messages back to the file", function () {
+    let src = `let x = 1;\nconst a = <template>\n  <button>Hi</button>\n</template>;\n`;
+    let blocks = p.preprocess(src);
+    let messages = p.postprocess(blocks, [
+      [{ ruleId: "prefer-const", line: 1, column: 5, severity: 1 }],
+      [
+        {
+          ruleId: "require-button-type",
+          line: 2,
+          column: 3,
+          endLine: 2,
+          endColumn: 11,
+          fix: { range: [3, 10], text: '<button type="button">' },
+        },
+      ],
+    ]);
+
+    expect(messages).to.eql([
+      { ruleId: "prefer-const", line: 1, column: 5, severity: 1 },
+      {
+        ruleId: "require-button-type",
+        line: 3,
+        column: 3,
+        endLine: 3,
+        endColumn: 11,
+        fix: { range: [34, 41], text: '<button type="button">' },
+      },
+    ]);
+    expect(src.slice(34, 41)).to.equal("<button");
+  });
+});
+
+describe(`createLintProcessor`, function () {
+  it("keeps the blocks of each file until postprocess", function () {
+    // ESLint calls these without `this`
+    let { preprocess, postprocess, supportsAutofix } = createLintProcessor();
+    expect(supportsAutofix).to.equal(true);
+
+    let a = preprocess(`let x = 1;\n<template>a</template>\n`, "a.gjs");
+    let b = preprocess(`<template>\n  b\n</template>\n`, "b.gjs");
+    expect(a.map((block) => block.text)).to.eql([
+      "let x = 1;\n`                   `\n",
+      "a",
+    ]);
+
+    expect(
+      postprocess([[], [{ ruleId: "b", line: 2, column: 3 }]], "b.gjs"),
+    ).to.eql([{ ruleId: "b", line: 2, column: 3 }]);
+    expect(
+      postprocess(
+        [
+          [{ ruleId: "x", line: 1, column: 5 }],
+          [{ ruleId: "a", line: 1, column: 1 }],
+        ],
+        "a.gjs",
+      ),
+    ).to.eql([
+      { ruleId: "x", line: 1, column: 5 },
+      { ruleId: "a", line: 2, column: 11 },
+    ]);
+    expect(b).to.have.length(2);
+  });
+});
@@ -49,6 +49,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 16,
           endUtf16Codepoint: 27,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -93,6 +100,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 28,
           endUtf16Codepoint: 39,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -141,6 +155,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 41,
           endUtf16Codepoint: 52,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -191,6 +212,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 57,
           endUtf16Codepoint: 68,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -238,6 +266,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 59,
           endUtf16Codepoint: 70,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -300,6 +335,13 @@ describe(`parse`, function () {
           startUtf16Codepoint: 48,
           endUtf16Codepoint: 59,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
@@ -385,4 +427,39 @@ describe(`parse`, function () {
       ).to.eql(`💩`);
     }
   });
+
+  it("reports indentation stripping", function () {
+    let src = `class A {
+  <template>
+    <div>hi</div>
+  </template>
+}`;
+
+    expect(p.parse(src)[0].stripIndent).to.eql({
+      leadingLines: 1,
+      trailingLines: 1,
+      indent: 4,
+    });
+    expect(p.parse(src, { strip_indent: "trim-only" })[0].stripIndent).to.eql({
+      leadingLines: 1,
+      trailingLines: 1,
+      indent: 0,
+    });
+  });
+
+  it("throws for indentation options it doesn't accept", function () {
+    expect(function () {
+      p.parse("<template>Hi</template>", { strip_indent: "dedent-all" });
+    }).to.throw(
+      'Invalid option `strip_indent`: expected one of "off", "trim-only", "dedent"',
+    );
+    expect(function () {
+      p.parse("<template>Hi</template>", { tab_width: 0 });
+    }).to.throw("Invalid option `tab_width`: expected a positive whole number");
+    for (let strip_indent of ["off", "trim-only"]) {
+      expect(function () {
+        p.parse("<template>Hi</template>", { strip_indent, tab_width: 4 });
+      }).to.throw("Invalid option `tab_width`");
+    }
+  });
 });
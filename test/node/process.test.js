@@ -17,8 +17,8 @@ describe(`process`, function () {
     let output = p.process("<template>Hi</template>");
 
     expect(normalizeOutput(output.code)).to
-      .equalCode(`import { template as template_UUID } from "@ember/template-compiler";
-  export default template_UUID(\`Hi\`, {
+      .equalCode(`import { template } from "@ember/template-compiler";
+  export default template(\`Hi\`, {
       eval () {
           return eval(arguments[0]);
       }
@@ -37,11 +37,11 @@ describe(`process`, function () {
     let output = p.process(input);
 
     expect(normalizeOutput(output.code)).to.equalCode(
-      `import { template as template_UUID } from "@ember/template-compiler";
+      `import { template } from "@ember/template-compiler";
      class Foo extends Component {
          greeting = 'Hello';
          static{
-             template_UUID(\`{{this.greeting}}, \\\`lifeform\\\`!\`, {
+             template(\`{{this.greeting}}, \\\`lifeform\\\`!\`, {
                  component: this,
                  eval () {
                      return eval(arguments[0]);
@@ -52,6 +52,104 @@ describe(`process`, function () {
     );
   });
 
+  it("picks a collision-free import name", function () {
+    let output = p.process(
+      "const template = 1;\nexport default <template>Hi</template>",
+    );
+
+    expect(output.code).to.equalCode(
+      `import { template as template1 } from "@ember/template-compiler";
+  const template = 1;
+  export default template1(\`Hi\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`,
+    );
+  });
+
+  it("uses the fixed import name when asked to", function () {
+    let output = p.process("<template>Hi</template>", {
+      import_alias: "fixed",
+    });
+
+    expect(normalizeOutput(output.code)).to
+      .equalCode(`import { template as template_UUID } from "@ember/template-compiler";
+  export default template_UUID(\`Hi\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`);
+  });
+
+  it("emits require and exports.default for commonjs", function () {
+    let output = p.process("<template>Hi</template>", {
+      module_format: "commonjs",
+    });
+
+    expect(output.code).to
+      .equalCode(`const { template } = require("@ember/template-compiler");
+  Object.defineProperty(exports, "__esModule", {
+      value: true
+  });
+  exports.default = template(\`Hi\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`);
+  });
+
+  it("keeps directives and license comments above the import", function () {
+    let output = p.process(
+      `/** @license MIT */\n"use client";\n<template>Hi</template>`,
+    );
+
+    expect(output.code).to.equalCode(`/** @license MIT */ "use client";
+  import { template } from "@ember/template-compiler";
+  export default template(\`Hi\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`);
+  });
+
+  it("can place the import after existing imports", function () {
+    let output = p.process(
+      `import Foo from "./foo";\n<template><Foo /></template>`,
+      { import_placement: "after-imports" },
+    );
+
+    expect(output.code).to.equalCode(`import Foo from "./foo";
+  import { template } from "@ember/template-compiler";
+  export default template(\`<Foo />\`, {
+      eval () {
+          return eval(arguments[0]);
+      }
+  });`);
+  });
+
+  it("can minify and use CRLF line endings", function () {
+    let minified = p.process("<template>Hi</template>", { minify: true });
+    expect(minified.code).to.not.include("\n");
+
+    let crlf = p.process("<template>Hi</template>", { line_ending: "crlf" });
+    expect(crlf.code).to.include("\r\n");
+  });
+
+  it("throws for a target it doesn't know", function () {
+    expect(function () {
+      p.process("<template>Hi</template>", { target: "es2023" });
+    }).to.throw('Invalid option `target`: expected one of "es3", "es5",');
+  });
+
+  it("throws for option values it doesn't accept", function () {
+    expect(function () {
+      p.process("<template>Hi</template>", { module_format: "cjs" });
+    }).to.throw(
+      'Invalid option `module_format`: expected one of "module", "commonjs"',
+    );
+  });
+
   it("Emits parse errors with anonymous file", function () {
     expect(function () {
       p.process(`const thing = "face";
@@ -104,6 +202,149 @@ describe(`process`, function () {
     );
   });
 
+  it("writes source map options into the map", function () {
+    let output = p.process(`<template>Hi</template>`, {
+      filename: "/ci/build/app/components/hi.gjs",
+      sources_relative_to: "/ci/build/app",
+      source_root: "/app",
+      source_map_file: "hi.js",
+      sources_content: false,
+    });
+    let map = JSON.parse(output.map);
+
+    expect(map.sources).to.deep.equal(["components/hi.gjs"]);
+    expect(map.sourceRoot).to.equal("/app");
+    expect(map.file).to.equal("hi.js");
+    expect(map).to.not.have.property("sourcesContent");
+  });
+
+  describe("hot", function () {
+    // Runs the output of `process` like a dev server would, with fakes for
+    // the Ember APIs it imports. Like Ember, `setComponentTemplate` throws
+    // when a component already has a template.
+    const templates = new WeakMap();
+    const modules = {
+      "@ember/component": {
+        setComponentTemplate(factory, component) {
+          if (templates.has(component)) {
+            throw new Error(
+              "Cannot call `setComponentTemplate` multiple times on the same class",
+            );
+          }
+          templates.set(component, factory);
+          return component;
+        },
+        getComponentTemplate: (component) => templates.get(component),
+      },
+      "@ember/component/template-only": { default: () => ({}) },
+      "@ember/template-compiler": {
+        template(contents, { component = {} } = {}) {
+          let factory = Object.assign(() => contents, { __id: contents });
+          return modules["@ember/component"].setComponentTemplate(
+            factory,
+            component,
+          );
+        },
+      },
+    };
+    const render = (component) => templates.get(component)("owner");
+
+    function load(src, hot) {
+      let { code } = p.process(src, { filename: "app/hello.gjs", hot: true });
+      let imports = [];
+      code = code
+        .replace(/^import (.*?) from (".*?");$/gm, (_, bindings, source) => {
+          imports.push(
+            bindings.startsWith("{")
+              ? `const ${bindings.replace(/ as /g, ": ")} = modules[${source}];`
+              : `const ${bindings} = modules[${source}].default;`,
+          );
+          return "";
+        })
+        .replace(/^export default /m, "exports.default = ")
+        .replace(/^export class (\w+)/m, "exports.$1 = class $1")
+        .replace(/\bimport\.meta\.hot\b/g, "hot");
+      let exports = {};
+      new Function("modules", "exports", "hot", imports.join("\n") + code)(
+        modules,
+        exports,
+        hot,
+      );
+      return exports;
+    }
+
+    function devServer() {
+      return {
+        data: {},
+        invalidated: false,
+        accept() {},
+        invalidate() {
+          this.invalidated = true;
+        },
+      };
+    }
+
+    const source = (greeting) =>
+      `export class Hello {\n  <template>${greeting}</template>\n}\nexport default <template>${greeting}!</template>`;
+
+    it("gives templates an identity and adds an import.meta.hot hook", function () {
+      let { code } = p.process(source("Hi"), {
+        filename: "app/hello.gjs",
+        hot: true,
+      });
+
+      expect(code).to.match(
+        / contentTagHotClass\(import\.meta\.hot, "app\/hello\.gjs:0:[0-9a-f]{8}", this,/,
+      );
+      expect(code).to.match(
+        / contentTagHotTemplate\(import\.meta\.hot, "app\/hello\.gjs:1:[0-9a-f]{8}",/,
+      );
+      expect(code).to.match(/import\.meta\.hot\.accept\(\)/);
+      expect(p.process(source("Hi")).code).not.to.match(/import\.meta\.hot/);
+    });
+
+    it("swaps templates on the components from the first load", function () {
+      let hot = devServer();
+      let first = load(source("Hello"), hot);
+      expect(render(first.Hello)).to.equal("Hello");
+      expect(render(first.default)).to.equal("Hello!");
+
+      let second = load(source("Goodbye"), hot);
+      expect(hot.invalidated).to.equal(false);
+      expect(render(first.Hello)).to.equal("Goodbye");
+      expect(render(second.Hello)).to.equal("Goodbye");
+      expect(second.default).to.equal(first.default);
+      expect(render(first.default)).to.equal("Goodbye!");
+      // the properties Ember reads from a template factory follow the swap
+      expect(templates.get(first.Hello).__id).to.equal("Goodbye");
+    });
+
+    it("invalidates the module when its JS changes", function () {
+      let hot = devServer();
+      load(source("Hello"), hot);
+      load(`const x = 1;\n${source("Hello")}`, hot);
+      expect(hot.invalidated).to.equal(true);
+    });
+
+    it("still registers templates without a dev server", function () {
+      let loaded = load(source("Hi"), undefined);
+      expect(render(loaded.Hello)).to.equal("Hi");
+      expect(render(loaded.default)).to.equal("Hi!");
+    });
+
+    it("is rejected where it can't swap templates", function () {
+      for (let options of [
+        { flavor: "precompile-template" },
+        { static_blocks: false },
+        { module_format: "commonjs" },
+      ]) {
+        expect(() =>
+          p.process(source("Hi"), { ...options, hot: true }),
+        ).to.throw("Invalid option `hot`");
+      }
+    });
+  });
+
   it("Preserves typescript declare", function () {
     let output = p.process(`class X { declare a: string; }`);
     expect(output.code).to.match(/declare a: string/);
@@ -123,8 +364,8 @@ describe(`process`, function () {
 </template>`);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         export default template_UUID(\`<span>Hello</span>\`, {
+        `import { template } from "@ember/template-compiler";
+         export default template(\`<span>Hello</span>\`, {
              eval () {
                  return eval(arguments[0]);
              }
@@ -146,10 +387,10 @@ describe(`process`, function () {
       let output = p.process(input);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
+        `import { template } from "@ember/template-compiler";
          class Foo extends Component {
              static{
-                 template_UUID(\`<div>
+                 template(\`<div>
   <span>{{this.greeting}}</span>
 </div>\`, {
                      component: this,
@@ -174,8 +415,8 @@ describe(`process`, function () {
       let output = p.process(input);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         export default template_UUID(\`Hello
+        `import { template } from "@ember/template-compiler";
+         export default template(\`Hello
 <span>there</span>.
 <p>
   <span>how are you</span>
@@ -202,8 +443,8 @@ describe(`process`, function () {
       let output = p.process(input);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         let x = template_UUID(\`<div>
+        `import { template } from "@ember/template-compiler";
+         let x = template(\`<div>
   <pre>
     some code
       with indentation
@@ -228,8 +469,8 @@ describe(`process`, function () {
 
       // When opted out, original whitespace is preserved
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         let x = template_UUID(\`{{!-- prevent automatic de-indent --}}
+        `import { template } from "@ember/template-compiler";
+         let x = template(\`{{!-- prevent automatic de-indent --}}
     <pre>
       content here
     </pre>\`, {
@@ -256,10 +497,10 @@ describe(`process`, function () {
       let output = p.process(input);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
+        `import { template } from "@ember/template-compiler";
          class Component {
              method() {
-                 return template_UUID(\`<div>
+                 return template(\`<div>
   <span>Nested</span>
 </div>\`, {
                      eval () {
@@ -283,13 +524,13 @@ describe(`process`, function () {
       let output = p.process(input);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         const MyTemplate = template_UUID(\`<span>x</span>\`, {
+        `import { template } from "@ember/template-compiler";
+         const MyTemplate = template(\`<span>x</span>\`, {
              eval () {
                  return eval(arguments[0]);
              }
          });
-         export default template_UUID(\`<MyTemplate />\`, {
+         export default template(\`<MyTemplate />\`, {
              eval () {
                  return eval(arguments[0]);
              }
@@ -301,8 +542,8 @@ describe(`process`, function () {
       let output = p.process(`<template> <span>Hello</span> </template>`);
 
       expect(normalizeOutput(output.code)).to.equalCode(
-        `import { template as template_UUID } from "@ember/template-compiler";
-         export default template_UUID(\` <span>Hello</span> \`, {
+        `import { template } from "@ember/template-compiler";
+         export default template(\` <span>Hello</span> \`, {
              eval () {
                  return eval(arguments[0]);
              }
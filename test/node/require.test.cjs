@@ -18,8 +18,8 @@ describe("cjs/require", function () {
     let output = p.process("<template>Hi</template>");
 
     expect(normalizeOutput(output.code)).to
-      .equalCode(`import { template as template_UUID } from "@ember/template-compiler";
-  export default template_UUID(\`Hi\`, {
+      .equalCode(`import { template } from "@ember/template-compiler";
+  export default template(\`Hi\`, {
       eval () {
           return eval(arguments[0]);
       }
@@ -66,6 +66,13 @@ describe("cjs/require", function () {
           startUtf16Codepoint: 16,
           endUtf16Codepoint: 27,
         },
+        rawContents: "Hello!",
+        stripIndent: {
+          leadingLines: 0,
+          trailingLines: 0,
+          indent: 0,
+        },
+        lineOffsets: [{ originalLine: 0, originalColumn: 0 }],
       },
     ]);
   });
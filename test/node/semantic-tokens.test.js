@@ -0,0 +1,33 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`semanticTokens`, function () {
+  it("covers JS and templates in UTF-16", function () {
+    let src = `const a = "😀";\n<template><Foo @x={{a.b}} /></template>`;
+    let { tokenTypes, data } = p.semanticTokens(src);
+
+    let tokens = [];
+    for (let i = 0; i < data.length; i += 5) {
+      tokens.push([...data.slice(i, i + 3), tokenTypes[data[i + 3]]]);
+    }
+    expect(tokens).to.eql([
+      [0, 0, 5, "keyword"],
+      [0, 6, 1, "variable"],
+      [0, 4, 4, "string"],
+      [1, 1, 8, "tag"],
+      [0, 10, 3, "class"],
+      [0, 4, 2, "parameter"],
+      [0, 5, 1, "variable"],
+      [0, 2, 1, "property"],
+      [0, 8, 8, "tag"],
+    ]);
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.semanticTokens("import ;")).to.throw("Parse Error");
+  });
+});
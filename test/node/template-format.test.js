@@ -0,0 +1,33 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`formatTemplates`, function () {
+  it("reindents template contents", function () {
+    let src = `const a = 1;\nexport default class {\n  <template>\n<div   class="a">{{a}}</div>\n      {{!-- kept --}}\n  </template>\n}`;
+
+    expect(p.formatTemplates(src)).to.equal(
+      `const a = 1;\nexport default class {\n  <template>\n    <div class="a">{{a}}</div>\n    {{!-- kept --}}\n  </template>\n}`,
+    );
+  });
+
+  it("reads the format options", function () {
+    let src = `<template>\n<input type="text" disabled>\n</template>`;
+
+    expect(
+      p.formatTemplates(src, {
+        attributeWrapping: "one-per-line",
+        selfClosing: "always",
+      }),
+    ).to.equal(
+      `<template>\n  <input\n    type="text"\n    disabled\n  />\n</template>`,
+    );
+  });
+
+  it("throws on parse errors", function () {
+    expect(() => p.formatTemplates("import ;")).to.throw("Parse Error");
+  });
+});
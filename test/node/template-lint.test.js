@@ -0,0 +1,44 @@
+import chai from "chai";
+import { Preprocessor } from "content-tag";
+
+const { expect } = chai;
+
+const p = new Preprocessor();
+
+describe(`lintTemplates`, function () {
+  let src = `const a = "😀";\n<template>\n  {{#each @items as |item i|}}<button>{{item}}</button>{{/each}}\n  Hello\n</template>`;
+
+  it("runs the recommended rules in file coordinates", function () {
+    let diagnostics = p.lintTemplates(src);
+
+    expect(diagnostics.map((d) => [d.rule, d.severity])).to.eql([
+      ["no-unused-block-params", "error"],
+      ["require-button-type", "error"],
+    ]);
+    let { range } = diagnostics[0];
+    expect(src.slice(range.startUtf16Codepoint, range.endUtf16Codepoint)).to.equal(
+      "i",
+    );
+  });
+
+  it("reads the config", function () {
+    let diagnostics = p.lintTemplates(src, {
+      rules: { "no-bare-strings": "error", "require-button-type": "warn" },
+    });
+
+    expect(diagnostics.map((d) => [d.rule, d.severity])).to.eql([
+      ["no-unused-block-params", "error"],
+      ["require-button-type", "warn"],
+      ["no-bare-strings", "error"],
+    ]);
+    expect(diagnostics[2].message).to.equal(
+      "Non-translated string used: `Hello`",
+    );
+  });
+
+  it("throws on unknown rules", function () {
+    expect(() => p.lintTemplates(src, { rules: { nope: "off" } })).to.throw(
+      "Unknown template lint rule `nope`",
+    );
+  });
+});
@@ -0,0 +1,150 @@
+#![cfg(feature = "capi")]
+
+use std::ptr;
+
+use content_tag::capi::{
+    content_tag_parse, content_tag_process, content_tag_result_free, ContentTagBuffer,
+    ContentTagResult, ContentTagStatus,
+};
+
+fn text(buffer: &ContentTagBuffer) -> &str {
+    if buffer.data.is_null() {
+        assert_eq!(buffer.len, 0);
+        return "";
+    }
+    std::str::from_utf8(unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) }).unwrap()
+}
+
+fn json(buffer: &ContentTagBuffer) -> serde_json::Value {
+    serde_json::from_str(text(buffer)).unwrap()
+}
+
+// Calls `f` like a C caller would, and frees the result afterwards.
+fn with_result<T>(
+    call: unsafe extern "C" fn(*const u8, usize, *const u8, usize) -> *mut ContentTagResult,
+    src: &[u8],
+    options: &str,
+    f: impl FnOnce(&ContentTagResult) -> T,
+) -> T {
+    unsafe {
+        let result = call(src.as_ptr(), src.len(), options.as_ptr(), options.len());
+        assert!(!result.is_null());
+        let value = f(&*result);
+        content_tag_result_free(result);
+        value
+    }
+}
+
+#[test]
+fn processes_templates() {
+    with_result(
+        content_tag_process,
+        b"<template>Hello</template>",
+        "",
+        |result| {
+            assert_eq!(result.status, ContentTagStatus::Ok);
+            assert!(text(&result.output).contains("template(`Hello`"));
+            assert_eq!(json(&result.map)["version"], 3);
+            assert_eq!(text(&result.error), "");
+        },
+    );
+}
+
+#[test]
+fn reads_options() {
+    with_result(
+        content_tag_process,
+        b"<template>Hello</template>",
+        r#"{"filename": "a.gjs", "module_format": "commonjs"}"#,
+        |result| {
+            assert_eq!(result.status, ContentTagStatus::Ok);
+            assert!(text(&result.output).contains("require(\"@ember/template-compiler\")"));
+            assert_eq!(json(&result.map)["sources"][0], "a.gjs");
+        },
+    );
+}
+
+#[test]
+fn parses_templates() {
+    with_result(
+        content_tag_parse,
+        "const a = \"😀\";\n<template>Hi</template>".as_bytes(),
+        "",
+        |result| {
+            assert_eq!(result.status, ContentTagStatus::Ok);
+            let occurrences = json(&result.output);
+            assert_eq!(occurrences[0]["contents"], "Hi");
+            assert_eq!(occurrences[0]["range"]["startByte"], 18);
+            assert_eq!(occurrences[0]["range"]["startChar"], 15);
+            assert_eq!(text(&result.map), "");
+        },
+    );
+}
+
+#[test]
+fn reports_parse_errors() {
+    with_result(
+        content_tag_process,
+        b"const a = ;",
+        r#"{"filename": "a.gjs"}"#,
+        |result| {
+            assert_eq!(result.status, ContentTagStatus::ParseError);
+            assert_eq!(text(&result.output), "");
+            let error = json(&result.error);
+            assert!(error["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Parse Error at a.gjs:1:"));
+            assert!(error["detail"].as_str().unwrap().contains("const a = ;"));
+            assert_eq!(error["startByte"], 10);
+            assert!(error["endByte"].as_u64() >= error["startByte"].as_u64());
+        },
+    );
+}
+
+#[test]
+fn rejects_invalid_arguments() {
+    with_result(content_tag_process, b"\xff", "", |result| {
+        assert_eq!(result.status, ContentTagStatus::InvalidArgument);
+        assert!(json(&result.error)["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("src is not UTF-8"));
+    });
+    with_result(content_tag_parse, b"", "[]", |result| {
+        assert_eq!(result.status, ContentTagStatus::InvalidArgument);
+        assert_eq!(
+            json(&result.error)["message"],
+            "options is not a JSON object"
+        );
+    });
+    with_result(
+        content_tag_process,
+        b"<template>Hi</template>",
+        r#"{"hot": true, "flavor": "precompile-template"}"#,
+        |result| {
+            assert_eq!(result.status, ContentTagStatus::InvalidArgument);
+            assert!(json(&result.error)["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid option `hot`"));
+        },
+    );
+    unsafe {
+        let result = content_tag_process(ptr::null(), 1, ptr::null(), 0);
+        assert_eq!((*result).status, ContentTagStatus::InvalidArgument);
+        assert_eq!(json(&(*result).error)["message"], "src is null");
+        content_tag_result_free(result);
+    }
+}
+
+#[test]
+fn accepts_empty_input_and_frees_null() {
+    unsafe {
+        let result = content_tag_parse(ptr::null(), 0, ptr::null(), 0);
+        assert_eq!((*result).status, ContentTagStatus::Ok);
+        assert_eq!(json(&(*result).output), serde_json::json!([]));
+        content_tag_result_free(result);
+        content_tag_result_free(ptr::null_mut());
+    }
+}